@@ -12,13 +12,59 @@
 // You should have received a copy of the Apache 2.0 License along with this
 // software. If not, see <https://opensource.org/licenses/Apache-2.0>.
 
-#![allow(dead_code)]
+#![allow(dead_code, clippy::needless_borrows_for_generic_args)]
 
 #[macro_use]
 extern crate amplify_derive;
 
+use std::borrow::Cow;
+
+use strict_encoding::derive::CommitEncode;
+use strict_encoding::derive::{
+    ConfinedDecode, ConfinedEncode, NetworkDecode, NetworkEncode, StrictDumb,
+    StrictEncoding,
+};
 use strict_encoding::{StrictDecode, StrictEncode};
 
+/// Global allocator counting allocations, which is used to check that
+/// buffers pre-allocated by `emit_to_vec` methods do not grow.
+struct CountingAlloc;
+
+static ALLOCATIONS: std::sync::atomic::AtomicUsize =
+    std::sync::atomic::AtomicUsize::new(0);
+
+unsafe impl std::alloc::GlobalAlloc for CountingAlloc {
+    unsafe fn alloc(&self, layout: std::alloc::Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        std::alloc::System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: std::alloc::Layout) {
+        std::alloc::System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(
+        &self,
+        ptr: *mut u8,
+        layout: std::alloc::Layout,
+        new_size: usize,
+    ) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+        std::alloc::System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: CountingAlloc = CountingAlloc;
+
+/// Returns the result of the function and the number of allocations it made.
+fn count_allocations<T>(f: impl FnOnce() -> T) -> (T, usize) {
+    let before = ALLOCATIONS.load(std::sync::atomic::Ordering::SeqCst);
+    let result = f();
+    let after = ALLOCATIONS.load(std::sync::atomic::Ordering::SeqCst);
+    (result, after - before)
+}
+
 #[derive(StrictEncode, StrictDecode)]
 struct Me(u8);
 
@@ -42,6 +88,8 @@ enum Hi {
         heap: Heap,
     },
     Seventh,
+    #[deprecated]
+    Eighth,
 }
 
 #[derive(Clone, Copy, Ord, PartialOrd, Eq, PartialEq, Hash, Debug, Display)]
@@ -80,7 +128,7 @@ enum ByValue {
 
 // All variants have custom values apart from the first one, which should has
 // value = 1
-#[derive(StrictEncode, StrictDecode)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
 #[strict_encoding(by_value)]
 #[repr(u8)]
 enum CustomValues {
@@ -96,6 +144,18 @@ enum CustomValues {
     Bit64 = 8,
 }
 
+// Explicit values must take precedence over `by_order` strategy as well
+#[derive(Clone, Copy, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(by_order)]
+enum CustomOrder {
+    Zero,
+
+    #[strict_encoding(value = 42)]
+    Custom,
+
+    Two,
+}
+
 #[derive(StrictEncode, StrictDecode)]
 #[strict_encoding(by_order, repr = u16)]
 #[repr(u16)]
@@ -107,23 +167,2969 @@ enum U16 {
 }
 
 #[derive(StrictEncode, StrictDecode)]
-struct Skipping {
-    pub data: Vec<u8>,
+#[strict_encoding(transparent)]
+struct Wrapper(Me);
 
-    // This will initialize the field upon decoding with Option::default()
-    // value
+#[derive(StrictEncode, StrictDecode)]
+#[strict_encoding(transparent)]
+struct NamedWrapper {
+    inner: One,
     #[strict_encoding(skip)]
-    pub ephemeral: Option<bool>,
+    cache: Option<u8>,
 }
 
 #[derive(StrictEncode, StrictDecode)]
-enum CustomErr<Err>
+#[strict_encoding(emit_decode_into)]
+struct Patch {
+    data: Vec<u8>,
+    #[strict_encoding(skip)]
+    revision: u8,
+}
+
+#[derive(StrictEncode, StrictDecode)]
+struct Blob {
+    #[strict_encoding(byte_blob)]
+    payload: Vec<u8>,
+}
+
+#[derive(Clone, StrictEncode, StrictDecode)]
+#[strict_encoding(into = "u16", try_from = "u16")]
+struct Port {
+    number: u16,
+    #[strict_encoding(skip)]
+    name: Option<String>,
+}
+
+impl From<Port> for u16 {
+    fn from(port: Port) -> Self {
+        port.number
+    }
+}
+
+impl std::convert::TryFrom<u16> for Port {
+    type Error = &'static str;
+
+    fn try_from(number: u16) -> Result<Self, Self::Error> {
+        if number == 0 {
+            return Err("zero port number");
+        }
+        Ok(Port { number, name: None })
+    }
+}
+
+#[derive(StrictEncode, StrictDecode)]
+#[strict_encoding(construct_with = "Range::with_bounds")]
+struct Range {
+    start: u32,
+    end: u32,
+}
+
+impl Range {
+    fn with_bounds(start: u32, end: u32) -> Result<Self, String> {
+        if start > end {
+            return Err(format!("invalid range {}..{}", start, end));
+        }
+        Ok(Range { start, end })
+    }
+}
+
+#[derive(StrictEncode, StrictDecode)]
+struct Registry {
+    #[strict_encoding(sorted_map)]
+    entries: std::collections::HashMap<String, u8>,
+}
+
+// `BTreeMap` fields are already sorted, so `sorted_map` is ignored for them
+// with a warning
+#[allow(deprecated)]
+mod ledger {
+    use std::collections::BTreeMap;
+
+    use strict_encoding::{StrictDecode, StrictEncode};
+
+    #[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+    pub struct Ledger {
+        #[strict_encoding(sorted_map)]
+        pub balances: BTreeMap<u16, u8>,
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+struct Catalog {
+    #[strict_encoding(count_type = u32)]
+    entries: Vec<u16>,
+    #[strict_encoding(count_type = u8)]
+    tags: std::collections::BTreeSet<u8>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+enum CatalogUpdate {
+    Insert(#[strict_encoding(count_type = u64)] Vec<u8>),
+    Clear,
+}
+
+#[derive(StrictEncode, StrictDecode)]
+#[strict_encoding(magic = 0x5354_4B31u32)]
+struct IntMagic(u8);
+
+#[derive(StrictEncode, StrictDecode)]
+#[strict_encoding(magic = b"RGB")]
+struct BytesMagic(u8);
+
+struct NotEncodable;
+
+#[derive(StrictEncode, StrictDecode)]
+enum Either<A, B, C> {
+    Left(A),
+    Right(B),
+    Neither(#[strict_encoding(skip)] Option<C>),
+}
+
+#[derive(StrictEncode, StrictDecode)]
+#[strict_encoding(length_prefixed = u16)]
+struct Record {
+    kind: u8,
+    data: Vec<u8>,
+}
+
+// Field and variant names coinciding with the names of the variables used
+// internally by the derived code
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+struct Bindings {
+    e: u8,
+    d: u16,
+    len: u8,
+    data: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+struct Nested {
+    inner: Bindings,
+    tail: Option<Bindings>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+enum NestedEnum {
+    Plain(Bindings),
+    Named { e: Nested, len: u8, data: Bindings },
+}
+
+#[derive(StrictEncode, StrictDecode)]
+#[strict_encoding(ignore_trailing = 2)]
+struct OldMessage {
+    id: u8,
+}
+
+#[derive(StrictEncode, StrictDecode)]
+#[strict_encoding(ignore_trailing_all)]
+struct OldStream {
+    id: u8,
+}
+
+#[derive(StrictEncode, StrictDecode)]
+#[strict_encoding(length_prefixed = u8, ignore_trailing_all)]
+struct OldRecord {
+    id: u8,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(version = 3)]
+struct Versioned {
+    id: u8,
+    #[strict_encoding(until = 1)]
+    legacy: u8,
+    #[strict_encoding(since = 2)]
+    extra: u16,
+    #[strict_encoding(since = 2, until = 2)]
+    interim: u8,
+    #[strict_encoding(since = 1, until = 3)]
+    current: u8,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(version = 1u16, magic = b"V")]
+struct WideVersioned {
+    id: u8,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+struct Extensible {
+    id: u8,
+    #[strict_encoding(optional_tail)]
+    name: Option<String>,
+    #[strict_encoding(optional_tail)]
+    amount: Option<u32>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(optional_tail)]
+struct ImpliedTail {
+    header: Option<u8>,
+    id: u8,
+    flag: Option<u8>,
+    amount: Option<u16>,
+}
+
+#[derive(StrictEncode, StrictDecode)]
+#[strict_encoding(exact)]
+struct Exact {
+    id: u8,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+struct Upgraded {
+    id: u8,
+    #[strict_encoding(default_on_eof)]
+    flags: u16,
+    #[strict_encoding(default_on_eof, default = "10")]
+    limit: u32,
+    #[strict_encoding(optional_tail)]
+    note: Option<u8>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(by_value, default_variant = "Unknown")]
+#[repr(u8)]
+enum Resilient {
+    Unknown = 0,
+    Known = 1,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(checksum = "crc32")]
+struct Checked {
+    payload: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(checksum = "crc32", magic = b"C", length_prefixed = u8)]
+struct FramedChecked {
+    id: u8,
+}
+
+// Encode-only structure borrowing its data, which are encoded the same way
+// as the owned data of `OwnedPacket`
+#[derive(StrictEncode)]
+struct BorrowedPacket<'a> {
+    id: u8,
+    payload: &'a [u8],
+    #[strict_encoding(byte_blob)]
+    raw: &'a [u8],
+}
+
+// Reference fields of enum variants are encoded as the referenced values
+#[derive(StrictEncode)]
+enum BorrowedValue<'a> {
+    Number { x: &'a u16 },
+    Text(&'a str),
+    Bytes(&'a [u8]),
+}
+
+#[derive(PartialEq, Eq, Debug, StrictDecode)]
+struct OwnedPacket {
+    id: u8,
+    payload: Vec<u8>,
+    #[strict_encoding(byte_blob)]
+    raw: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(pad_to = 8)]
+struct Slot {
+    id: u16,
+    name: String,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(pad_to = 8, checksum = "crc32")]
+struct CheckedSlot {
+    id: u16,
+}
+
+#[derive(Clone, PartialEq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(batch_writes)]
+struct BatchedHeader {
+    kind: u8,
+    flag: bool,
+    #[strict_encoding(skip)]
+    cached: u64,
+    port: u16,
+    offset: i32,
+    ratio: f32,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(emit_decode_into)]
+struct Pinned {
+    #[strict_encoding(order = 2)]
+    name: String,
+    #[strict_encoding(skip)]
+    cache: u8,
+    #[strict_encoding(order = 1)]
+    id: u16,
+    #[strict_encoding(order = 10)]
+    flag: bool,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(construct_with = "PinnedRange::new")]
+struct PinnedRange(
+    #[strict_encoding(order = 1)] u8,
+    #[strict_encoding(order = 0)] u8,
+);
+
+impl PinnedRange {
+    fn new(start: u8, end: u8) -> Result<Self, String> {
+        if start > end {
+            return Err("range start exceeds end".to_owned());
+        }
+        Ok(PinnedRange(start, end))
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+struct Index {
+    #[strict_encoding(sort_keys)]
+    entries: std::collections::HashMap<u16, String>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+enum InfallibleReply {
+    Ok(u8),
+    Failure {
+        code: u16,
+        error: std::convert::Infallible,
+    },
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(via_deref)]
+struct Amount(u64);
+
+impl std::ops::Deref for Amount {
+    type Target = u64;
+
+    fn deref(&self) -> &u64 {
+        &self.0
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+struct Marked<T> {
+    id: u32,
+    marker: std::marker::PhantomData<T>,
+}
+
+struct Unencodable;
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(dyn_safe)]
+struct Frame {
+    kind: u8,
+    body: Vec<u8>,
+}
+
+trait DynFrame {
+    fn encode(&self, e: &mut dyn std::io::Write) -> usize;
+}
+
+impl DynFrame for Frame {
+    fn encode(&self, e: &mut dyn std::io::Write) -> usize {
+        self.strict_encode_dyn(e).unwrap()
+    }
+}
+
+#[derive(Clone, Copy, StrictEncode, StrictDecode)]
+#[strict_encoding(union_tag = "kind", repr = u16)]
+#[repr(C)]
+union Register {
+    #[strict_encoding(value = 1)]
+    word: u32,
+    #[strict_encoding(value = 2)]
+    half: u16,
+}
+
+impl Register {
+    /// Registers with the highest bit set in the lowest half are words
+    unsafe fn kind(&self) -> u16 {
+        if self.half & 0x8000 != 0 {
+            1
+        } else {
+            2
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(const_size)]
+struct FrameHeader {
+    kind: u8,
+    flags: u16,
+    id: [u8; 4],
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(const_size, magic = 0xFEu8, version = 1)]
+struct FrameEnvelope {
+    header: FrameHeader,
+    #[strict_encoding(skip)]
+    cache: Vec<u8>,
+    checksum: u32,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+struct Coordinates {
+    #[strict_encoding(tuple_fields)]
+    position: (u8, u16, u32, u8, u8, u8),
+    label: String,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+enum Shape {
+    Point(#[strict_encoding(tuple_fields)] (u8, u8, u8, u8, u8, u8, u8)),
+}
+
+// Tuples longer than 12 elements implement no `std` traits, so the structure
+// can't derive them
+#[derive(StrictEncode, StrictDecode)]
+#[allow(clippy::type_complexity)]
+struct LongTuple {
+    #[strict_encoding(tuple_fields, tuple_arity = 13)]
+    values: (u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u8, u16),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(bitpack, const_size)]
+struct Permissions {
+    read: bool,
+    write: bool,
+    exec: bool,
+    owner: u16,
+    f0: bool,
+    f1: bool,
+    f2: bool,
+    f3: bool,
+    f4: bool,
+    f5: bool,
+    f6: bool,
+    f7: bool,
+    f8: bool,
+}
+
+const TAG_PING: u8 = 0x10;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(by_value)]
+enum Command {
+    #[strict_encoding(value = TAG_PING)]
+    Ping,
+    #[strict_encoding(value = Command::TAG_PONG)]
+    Pong,
+    #[strict_encoding(value = 0x30)]
+    Close,
+}
+
+impl Command {
+    const TAG_PONG: u32 = 0x20;
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(type_tagged)]
+struct Invoice {
+    amount: u32,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(type_tagged, name = "receipt", checksum = "crc32")]
+struct Receipt {
+    amount: u32,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(type_tagged)]
+enum Archived<T> {
+    Item(T),
+}
+
+mod deps {
+    pub use strict_encoding as strict_enc;
+
+    pub mod nested {
+        use super::strict_enc::{StrictDecode, StrictEncode};
+
+        #[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+        #[strict_encoding(crate = super::strict_enc)]
+        pub struct Relative(pub u16);
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(crate = crate::deps::strict_enc)]
+struct ReExported {
+    inner: deps::nested::Relative,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+struct Holder<T, U>
 where
-    Err: std::error::Error + StrictEncode + StrictDecode,
+    T: Clone,
 {
-    Other(Err),
+    inner: T,
+    marker: std::marker::PhantomData<U>,
 }
 
-fn main() {
-    assert_eq!(ByValue::Bit64.strict_serialize().unwrap(), vec![8])
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(inline = "always")]
+struct Pixel(u8, u8, u8);
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(inline = "never", by_value, repr = u8)]
+enum Channel {
+    Red = 1,
+    Green = 2,
+    Blue = 3,
+}
+
+trait Schema {
+    type Item;
+}
+
+struct Bytes;
+
+impl Schema for Bytes {
+    type Item = u8;
+}
+
+#[derive(StrictEncode, StrictDecode)]
+#[strict_encoding(
+    encode_bound = "S::Item: StrictEncode",
+    decode_bound = "S::Item: StrictDecode"
+)]
+struct Items<S: Schema> {
+    items: Vec<S::Item>,
+}
+
+// Encoded to nothing for any `T`, but unlike a bare `PhantomData` is not
+// recognized by the derive macros, which would bound `T` on their own
+struct Marker<T>(std::marker::PhantomData<T>);
+
+impl<T> StrictEncode for Marker<T> {
+    fn strict_encode<E: std::io::Write>(
+        &self,
+        _: E,
+    ) -> Result<usize, strict_encoding::Error> {
+        Ok(0)
+    }
+}
+
+impl<T> StrictDecode for Marker<T> {
+    fn strict_decode<D: std::io::Read>(
+        _: D,
+    ) -> Result<Self, strict_encoding::Error> {
+        Ok(Marker(std::marker::PhantomData))
+    }
+}
+
+#[derive(StrictEncode, StrictDecode)]
+#[strict_encoding(bound = "")]
+struct Tagged<T> {
+    id: u16,
+    marker: Marker<T>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+struct Keywords {
+    r#type: u8,
+    r#match: String,
+    r#fn: Option<u16>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(type_tagged)]
+struct r#Loop {
+    r#in: u8,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(size_hint, version = 1)]
+struct Sample {
+    id: u32,
+    levels: [u8; 3],
+    #[strict_encoding(skip)]
+    cached: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(size_hint)]
+struct Samples {
+    count: u8,
+    samples: Vec<Sample>,
+}
+
+#[derive(StrictEncode, StrictDecode)]
+struct Phantoms<'a, T, U> {
+    id: u16,
+    producer: std::marker::PhantomData<fn() -> T>,
+    borrowed: std::marker::PhantomData<&'a U>,
+    tagged: Tagged<std::marker::PhantomData<T>>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(emit_append)]
+enum Event {
+    Start(u8),
+    Stop,
+}
+
+const EXTENDED: bool = false;
+const COMPACT: bool = true;
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(const_size)]
+struct Telemetry {
+    id: u16,
+    #[strict_encoding(skip_if_const = "COMPACT")]
+    detail: u32,
+    #[strict_encoding(skip_if_const = "EXTENDED")]
+    flags: u8,
+}
+
+#[derive(StrictEncode, StrictDecode)]
+struct Cache<T> {
+    #[strict_encoding(skip)]
+    index: std::collections::HashMap<u32, T>,
+    payload: Vec<u8>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(repr = u8, by_value)]
+enum Discriminant {
+    Foo = 10,
+    Bar = 20,
+}
+
+#[derive(StrictEncode, StrictDecode)]
+#[strict_encoding(no_bound = "T", no_decode_bound = "U")]
+struct Observed<T, U> {
+    tagged: Tagged<T>,
+    other: Tagged<U>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+enum Algorithm {
+    Sha256,
+    #[strict_encoding(skip, replace_with = "Self::Sha256")]
+    Sha1(u8),
+    #[strict_encoding(skip)]
+    Md5,
+    Blake3,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+struct Packet<const N: usize> {
+    bytes: [u8; N],
+    flags: u16,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+struct Series<T, const N: usize> {
+    items: [T; N],
+    #[strict_encoding(skip)]
+    cached: Option<T>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(c_layout)]
+#[repr(C)]
+struct CHeader {
+    tag: u8,
+    len: u32,
+    flags: [u16; 2],
+    last: u8,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(c_layout)]
+#[repr(C, align(32))]
+struct CAligned {
+    tag: u8,
+    len: u32,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(no_tag, by_value, repr = u8)]
+enum Payload {
+    #[strict_encoding(value = 0x10)]
+    Ping(u16),
+    #[strict_encoding(value = 0x20)]
+    Pong,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+struct Quote<'a> {
+    name: Cow<'a, str>,
+    data: Cow<'a, [u8]>,
+    kind: Cow<'static, str>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+struct Grade(u8);
+
+// Types named `Cow` which are not `std::borrow::Cow` are encoded as usual
+mod farm {
+    use strict_encoding::{StrictDecode, StrictEncode};
+
+    #[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+    pub struct Cow<T>(pub T);
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+struct Scorecard<'a, T: Clone> {
+    best: Cow<'a, Grade>,
+    all: std::borrow::Cow<'a, [T]>,
+    last: Cow<'a, T>,
+    cow: farm::Cow<u8>,
+}
+
+trait Protocol {
+    type Message;
+    type Reply;
+}
+
+struct Ping;
+
+impl Protocol for Ping {
+    type Message = u16;
+    type Reply = String;
+}
+
+// `Ping` implements neither `StrictEncode` nor `StrictDecode`
+#[derive(StrictEncode, StrictDecode)]
+struct Node<P: Protocol> {
+    msg: P::Message,
+    replies: Vec<<P as Protocol>::Reply>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(align = 16, const_size)]
+struct Cell {
+    id: u8,
+    value: u32,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(emit_canonical, ignore_trailing = 4)]
+struct Consensus {
+    id: u16,
+}
+
+trait Handler<T> {
+    fn handle(&self, item: &T) -> bool;
+}
+
+struct Accept;
+
+impl<T> Handler<T> for Accept {
+    fn handle(&self, _: &T) -> bool {
+        true
+    }
+}
+
+impl<T> Default for Box<dyn Handler<T>> {
+    fn default() -> Self {
+        Box::new(Accept)
+    }
+}
+
+// Neither `dyn Handler<T>` nor `T` are required to implement strict encoding
+#[derive(StrictEncode, StrictDecode)]
+struct Worker<T> {
+    id: u16,
+    #[strict_encoding(skip)]
+    handler: Box<dyn Handler<T>>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(field_prefix)]
+struct Handshake {
+    version: u8,
+    #[strict_encoding(skip)]
+    session: u64,
+    agent: String,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(field_prefix, field_prefix_type = u16, const_size)]
+struct Hello(u8, u16);
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(by_order, repr = u8)]
+enum Message {
+    Ping,
+    Data(Vec<u8>),
+    Pong,
+    Error { code: u16, message: String },
+}
+
+// Collection without strict encoding implementation, like `SmallVec`
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Inline<T>(Vec<T>);
+
+impl<T> Inline<T> {
+    fn new() -> Self {
+        Inline(Vec::new())
+    }
+
+    fn push(&mut self, item: T) {
+        self.0.push(item)
+    }
+
+    fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn iter(&self) -> std::slice::Iter<'_, T> {
+        self.0.iter()
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+struct Route {
+    #[strict_encoding(collection = "Inline::new()", max_len = 3)]
+    hops: Inline<u16>,
+    #[strict_encoding(collection = "Inline::new()", count_type = u8)]
+    stops: Inline<u8>,
+}
+
+#[derive(
+    Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode, StrictDumb,
+)]
+struct Account {
+    id: u32,
+    #[strict_encoding(dumb = "\"anonymous\".to_owned()")]
+    name: String,
+    #[strict_encoding(skip)]
+    balance: u64,
+}
+
+#[derive(
+    Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode, StrictDumb,
+)]
+#[strict_encoding(by_order)]
+enum Status {
+    #[strict_encoding(skip)]
+    Unknown,
+    Active(u8),
+    #[strict_encoding(dumb)]
+    Closed {
+        #[strict_encoding(dumb = "7")]
+        code: u16,
+        reason: String,
+    },
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictDumb)]
+enum Level {
+    #[strict_encoding(skip)]
+    Hidden,
+    Low(u8),
+    High,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug)]
+struct Opaque(u8);
+
+// Type parameters of the fields not using their default values do not require
+// `Default` bounds
+#[derive(Clone, PartialEq, Eq, Debug, StrictDumb)]
+struct Queue<T> {
+    id: u8,
+    #[strict_encoding(dumb = "Vec::new()")]
+    items: Vec<T>,
+    #[strict_encoding(skip)]
+    cached: Option<T>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictDumb)]
+enum Choice<A, B> {
+    Unset,
+    Left(A),
+    Right(B),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+struct Outcome<R> {
+    id: u8,
+    result: Result<u16, R>,
+}
+
+// Types named `Result` which are not `std::result::Result` are encoded as usual
+mod proto {
+    use std::io;
+
+    use strict_encoding::{Error, StrictDecode, StrictEncode};
+
+    #[derive(Clone, PartialEq, Eq, Debug)]
+    pub struct Result<A, B>(pub A, pub B);
+
+    impl<A: StrictEncode, B: StrictEncode> StrictEncode for Result<A, B> {
+        fn strict_encode<E: io::Write>(
+            &self,
+            mut e: E,
+        ) -> std::result::Result<usize, Error> {
+            Ok(self.0.strict_encode(&mut e)? + self.1.strict_encode(&mut e)?)
+        }
+    }
+
+    impl<A: StrictDecode, B: StrictDecode> StrictDecode for Result<A, B> {
+        fn strict_decode<D: io::Read>(
+            mut d: D,
+        ) -> std::result::Result<Self, Error> {
+            Ok(Result(A::strict_decode(&mut d)?, B::strict_decode(&mut d)?))
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+struct Verdict {
+    local: proto::Result<u8, u8>,
+    std: std::result::Result<u8, u8>,
+}
+
+// Network encoding with big-endian integers, standing for the actual network
+// encoding crate
+mod network_encoding {
+    use std::io;
+
+    pub use strict_encoding::Error;
+
+    pub trait NetworkEncode {
+        fn network_encode<E: io::Write>(&self, e: E) -> Result<usize, Error>;
+
+        fn network_serialize(&self) -> Result<Vec<u8>, Error> {
+            let mut data = vec![];
+            self.network_encode(&mut data)?;
+            Ok(data)
+        }
+    }
+
+    pub trait NetworkDecode: Sized {
+        fn network_decode<D: io::Read>(d: D) -> Result<Self, Error>;
+
+        fn network_deserialize(data: impl AsRef<[u8]>) -> Result<Self, Error> {
+            Self::network_decode(data.as_ref())
+        }
+    }
+
+    impl NetworkEncode for u16 {
+        fn network_encode<E: io::Write>(
+            &self,
+            mut e: E,
+        ) -> Result<usize, Error> {
+            e.write_all(&self.to_be_bytes())?;
+            Ok(2)
+        }
+    }
+
+    impl NetworkDecode for u16 {
+        fn network_decode<D: io::Read>(mut d: D) -> Result<Self, Error> {
+            let mut buf = [0u8; 2];
+            d.read_exact(&mut buf)?;
+            Ok(u16::from_be_bytes(buf))
+        }
+    }
+}
+
+#[derive(
+    Clone,
+    PartialEq,
+    Eq,
+    Debug,
+    StrictEncode,
+    StrictDecode,
+    NetworkEncode,
+    NetworkDecode,
+)]
+#[strict_encoding(by_order)]
+#[network_encoding(by_value, repr = u16)]
+enum Link {
+    #[network_encoding(value = 0x0100)]
+    Open(u16),
+    #[strict_encoding(value = 0x20)]
+    #[network_encoding(value = 0x0001)]
+    Closed,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, NetworkEncode, NetworkDecode)]
+struct Session {
+    strict_mode: u16,
+    port: u16,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(length_prefixed = u32)]
+struct Datagram {
+    port: u16,
+    body: String,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(scoped_helpers, emit_append, const_size)]
+struct Sensor {
+    id: u8,
+    reading: u16,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncoding)]
+#[strict_encoding(repr = u8, by_value)]
+enum Mode {
+    #[strict_encoding(value = 0x10)]
+    Read,
+    #[strict_encoding(value = 0x20)]
+    Write(u16),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncoding)]
+#[strict_encoding(encode_only)]
+struct Report<'a> {
+    mode: Mode,
+    #[strict_encoding(skip)]
+    cache: Option<&'a [u8]>,
+}
+
+const KEY_LEN: usize = 4;
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncoding)]
+struct PublicKey {
+    #[strict_encoding(assert_eq = 2)]
+    version: u8,
+    #[strict_encoding(assert_eq = KEY_LEN)]
+    len: u16,
+    #[strict_encoding(assert_eq = KEY_LEN)]
+    key: Vec<u8>,
+}
+
+mod vault {
+    use strict_encoding::derive::StrictEncoding;
+
+    #[derive(Clone, PartialEq, Eq, Debug, StrictEncoding)]
+    pub struct Sealed {
+        nonce: u32,
+        payload: Vec<u8>,
+    }
+
+    impl Sealed {
+        pub fn new(nonce: u32, payload: Vec<u8>) -> Self {
+            Sealed { nonce, payload }
+        }
+    }
+}
+
+#[derive(Debug)]
+enum AppError {
+    Encoding(strict_encoding::Error),
+}
+
+impl From<strict_encoding::Error> for AppError {
+    fn from(err: strict_encoding::Error) -> Self {
+        AppError::Encoding(err)
+    }
+}
+
+fn load_mode(data: &[u8]) -> Result<Mode, AppError> {
+    Ok(Mode::strict_deserialize(data)?)
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(max_len = 2)]
+struct Roster {
+    names: Vec<String>,
+    #[strict_encoding(max_len = 3)]
+    ids: Vec<u8>,
+    level: u8,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(by_order, max_len = 1)]
+enum Batch {
+    Single(Vec<u8>),
+    Many(#[strict_encoding(max_len = 4)] Vec<u8>),
+}
+
+mod confined_encoding {
+    use std::io;
+
+    pub use strict_encoding::Error;
+
+    pub trait ConfinedEncode {
+        fn confined_encode<E: io::Write>(&self, e: E) -> Result<usize, Error>;
+
+        fn confined_serialize(&self) -> Result<Vec<u8>, Error> {
+            let mut data = vec![];
+            self.confined_encode(&mut data)?;
+            Ok(data)
+        }
+    }
+
+    pub trait ConfinedDecode: Sized {
+        fn confined_decode<D: io::Read>(d: D) -> Result<Self, Error>;
+
+        fn confined_deserialize(data: impl AsRef<[u8]>) -> Result<Self, Error> {
+            Self::confined_decode(data.as_ref())
+        }
+    }
+
+    impl ConfinedEncode for Vec<u8> {
+        fn confined_encode<E: io::Write>(
+            &self,
+            mut e: E,
+        ) -> Result<usize, Error> {
+            e.write_all(&[self.len() as u8])?;
+            e.write_all(self)?;
+            Ok(self.len() + 1)
+        }
+    }
+
+    impl ConfinedDecode for Vec<u8> {
+        fn confined_decode<D: io::Read>(mut d: D) -> Result<Self, Error> {
+            let mut len = [0u8; 1];
+            d.read_exact(&mut len)?;
+            let mut data = vec![0u8; len[0] as usize];
+            d.read_exact(&mut data)?;
+            Ok(data)
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, ConfinedEncode, ConfinedDecode)]
+#[confined_encoding(max_len = 2)]
+struct Digest {
+    tags: Vec<u8>,
+    #[confined_encoding(max_len = 4)]
+    data: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, ConfinedEncode, ConfinedDecode)]
+#[confined_encoding(max_len = 1)]
+struct Capsule {
+    strict_tags: Vec<u8>,
+}
+
+mod commit_verify {
+    pub trait CommitEncode {
+        fn commit_encode<E: std::io::Write>(&self, e: E) -> usize;
+
+        fn commit_serialize(&self) -> Vec<u8> {
+            let mut data = vec![];
+            self.commit_encode(&mut data);
+            data
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+struct Secret(u32);
+
+impl Secret {
+    fn conceal(&self) -> u8 {
+        (self.0 % 256) as u8
+    }
+}
+
+#[derive(
+    Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode, CommitEncode,
+)]
+struct Anchor {
+    height: u16,
+    #[commit_encoding(hash = sha256)]
+    script: Vec<u8>,
+    #[commit_encoding(conceal)]
+    secret: Secret,
+    #[commit_encoding(skip)]
+    cache: u8,
+}
+
+// Only the committed fields require strict encoding of their type parameters
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, CommitEncode)]
+struct Sealed<T, U> {
+    #[strict_encoding(skip)]
+    seal: U,
+    #[commit_encoding(skip)]
+    note: T,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(sequence = "kind, id, payload")]
+struct Parcel {
+    id: u16,
+    payload: Vec<u8>,
+    #[strict_encoding(skip)]
+    received: bool,
+    kind: u8,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(sequence = "1, 0")]
+struct Swapped(u8, u16);
+
+mod plain {
+    use strict_encoding::derive::{StrictDecode, StrictEncode};
+
+    #[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+    #[strict_encoding(helpers)]
+    pub struct Pair<T> {
+        pub first: T,
+        pub second: T,
+    }
+
+    // Inherent helpers are callable without the traits in scope
+    pub fn roundtrip(
+        pair: &Pair<u8>,
+    ) -> Result<Pair<u8>, strict_encoding::Error> {
+        Pair::strict_deserialize(pair.strict_serialize()?)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(length_suffix = u8)]
+struct Trailer {
+    kind: u8,
+    #[strict_encoding(skip)]
+    cached: bool,
+    items: Vec<u16>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(count_assert = 4, by_order)]
+enum Octant {
+    Zero,
+    One,
+    #[strict_encoding(skip)]
+    Reserved,
+    Two,
+    Three,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(allow_order_skew)]
+struct Migrated {
+    #[strict_encoding(order = 0)]
+    id: u16,
+    #[strict_encoding(encode_order = 2, decode_order = 1)]
+    fee: u8,
+    #[strict_encoding(order = 3)]
+    flags: u8,
+    #[strict_encoding(skip)]
+    cached: bool,
+    #[strict_encoding(encode_order = 1, decode_order = 2)]
+    amount: u32,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(len_helper, repr = u16)]
+enum FeeInput {
+    Empty,
+    Witness(Vec<u8>),
+    Padded(FeeRecord),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(len_helper, length_prefixed = u8, pad_to = 16)]
+struct FeeRecord {
+    amount: u64,
+    note: String,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default, StrictEncode, StrictDecode)]
+struct LedgerShort {
+    id: u16,
+    #[strict_skip]
+    cache: Vec<u8>,
+    value: u8,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default, StrictEncode, StrictDecode)]
+struct LedgerFull {
+    id: u16,
+    #[strict_encoding(skip)]
+    cache: Vec<u8>,
+    value: u8,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(by_order)]
+enum LedgerKind {
+    Credit,
+    #[strict_skip]
+    #[strict_encoding(replace_with = "Self::Credit")]
+    Legacy,
+    Debit,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+enum Snippet<'a> {
+    Text(Cow<'a, str>),
+    Bytes { data: Cow<'a, [u8]>, tag: u8 },
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+enum OwnedSnippet {
+    Text(String),
+    Bytes { data: Vec<u8>, tag: u8 },
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(emit_to_vec, len_helper)]
+struct Tick {
+    symbol: u16,
+    prices: Vec<u64>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(emit_to_vec)]
+struct Candle {
+    open: u64,
+    close: u64,
+    volume: u32,
+    venue: String,
+}
+
+const OPCODE_BASE: u8 = 0x40;
+const OPCODE_STEP: u8 = 4;
+const OPCODE_POP: u8 = OPCODE_BASE + 2 * OPCODE_STEP;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(const_values, by_value)]
+#[repr(u8)]
+enum Opcode {
+    #[strict_encoding(value = OPCODE_BASE)]
+    Push = 1,
+    #[strict_encoding(value = OPCODE_POP)]
+    Pop = 2,
+    Nop = OPCODE_STEP - 1,
+    #[strict_encoding(skip, replace_with = "Self::Nop")]
+    Halt = 4,
+}
+
+#[derive(Debug)]
+enum ClientError {
+    Decoding(strict_encoding::Error),
+}
+
+impl From<strict_encoding::Error> for ClientError {
+    fn from(err: strict_encoding::Error) -> Self {
+        ClientError::Decoding(err)
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(error_type = "ClientError", helpers)]
+struct Greeting {
+    version: u8,
+    nonce: u16,
+}
+
+fn read_greeting(data: &[u8]) -> Result<Greeting, ClientError> {
+    let handshake = Greeting::strict_decode(data)?;
+    Ok(handshake)
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(crate = crate::deps::strict_enc, conversions)]
+struct Envelope<T> {
+    kind: u8,
+    body: T,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(max_encoded_len = 8, emit_decode_into)]
+struct Beacon {
+    id: u16,
+    payload: Vec<u8>,
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(by_order, max_encoded_len = 4)]
+enum Signal {
+    Idle,
+    Level(u16),
+    Burst(Vec<u8>),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(no_tag, max_encoded_len = 2)]
+enum Bounded {
+    Short(u16),
+    Long(Vec<u8>),
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, StrictEncode, StrictDecode)]
+#[strict_encoding(optional_checksum = "crc32")]
+struct Reading {
+    sensor: u8,
+    value: u16,
+}
+
+type Tag = u16;
+
+#[derive(StrictEncode, StrictDecode)]
+#[strict_encoding(by_order, repr = Tag)]
+enum Aliased {
+    First,
+    Second,
+}
+
+#[derive(StrictEncode, StrictDecode)]
+struct Skipping {
+    pub data: Vec<u8>,
+
+    // This will initialize the field upon decoding with Option::default()
+    // value
+    #[strict_encoding(skip)]
+    pub ephemeral: Option<bool>,
+}
+
+#[derive(StrictEncode, StrictDecode)]
+enum CustomErr<Err>
+where
+    Err: std::error::Error + StrictEncode + StrictDecode,
+{
+    Other(Err),
+}
+
+fn main() {
+    assert_eq!(ByValue::Bit64.strict_serialize().unwrap(), vec![8]);
+
+    for (val, data) in [
+        (CustomValues::Bit8, 1u8),
+        (CustomValues::Bit16, 11),
+        (CustomValues::Bit32, 12),
+        (CustomValues::Bit64, 13),
+    ] {
+        assert_eq!(val.strict_serialize().unwrap(), vec![data]);
+        assert_eq!(CustomValues::strict_deserialize([data]).unwrap(), val);
+    }
+    for (val, data) in [
+        (CustomOrder::Zero, 0u8),
+        (CustomOrder::Custom, 42),
+        (CustomOrder::Two, 2),
+    ] {
+        assert_eq!(val.strict_serialize().unwrap(), vec![data]);
+        assert_eq!(CustomOrder::strict_deserialize([data]).unwrap(), val);
+    }
+    assert_eq!(Aliased::Second.strict_serialize().unwrap(), vec![1, 0]);
+    assert_eq!(Wrapper(Me(7)).strict_serialize().unwrap(), vec![7]);
+    assert_eq!(Wrapper::strict_deserialize([7]).unwrap().0 .0, 7);
+
+    let mut patch = Patch {
+        data: vec![],
+        revision: 5,
+    };
+    patch.strict_decode_into(&[2u8, 0, 1, 2][..]).unwrap();
+    assert_eq!(patch.data, vec![1, 2]);
+    assert_eq!(patch.revision, 5);
+
+    let blob = Blob {
+        payload: vec![0xAB, 0xCD],
+    };
+    let ser = blob.strict_serialize().unwrap();
+    assert_eq!(ser, vec![2, 0, 0, 0, 0xAB, 0xCD]);
+    assert_eq!(
+        Blob::strict_deserialize(&ser).unwrap().payload,
+        blob.payload
+    );
+
+    let port = Port {
+        number: 8080,
+        name: Some("http".to_string()),
+    };
+    assert_eq!(port.strict_serialize().unwrap(), vec![0x90, 0x1F]);
+    assert_eq!(Port::strict_deserialize([0x90, 0x1F]).unwrap().number, 8080);
+    assert!(Port::strict_deserialize([0, 0]).is_err());
+
+    let range = Range::with_bounds(1, 2).unwrap();
+    let ser = range.strict_serialize().unwrap();
+    assert_eq!(Range::strict_deserialize(&ser).unwrap().end, 2);
+    assert!(Range::strict_deserialize([2, 0, 0, 0, 1, 0, 0, 0]).is_err());
+
+    let mut registry = Registry {
+        entries: Default::default(),
+    };
+    registry.entries.insert("b".to_string(), 2);
+    registry.entries.insert("a".to_string(), 1);
+    let ser = registry.strict_serialize().unwrap();
+    assert_eq!(ser, vec![2, 0, 1, 0, b'a', 1, 1, 0, b'b', 2]);
+    assert_eq!(Registry::strict_deserialize(&ser).unwrap().entries.len(), 2);
+    // Unordered entries are accepted
+    assert_eq!(
+        Registry::strict_deserialize([2, 0, 1, 0, b'b', 2, 1, 0, b'a', 1])
+            .unwrap()
+            .entries,
+        registry.entries
+    );
+    let ledger = ledger::Ledger {
+        balances: vec![(1, 1), (256, 2)].into_iter().collect(),
+    };
+    let ser = ledger.strict_serialize().unwrap();
+    assert_eq!(ser, ledger.balances.strict_serialize().unwrap());
+    assert_eq!(ser, vec![2, 0, 1, 0, 1, 0, 1, 2]);
+    assert_eq!(ledger::Ledger::strict_deserialize(&ser).unwrap(), ledger);
+
+    let catalog = Catalog {
+        entries: vec![0x0102],
+        tags: vec![7, 5].into_iter().collect(),
+    };
+    let data = catalog.strict_serialize().unwrap();
+    assert_eq!(data, vec![1u8, 0, 0, 0, 2, 1, 2, 5, 7]);
+    assert_eq!(Catalog::strict_deserialize(&data).unwrap(), catalog);
+    let overflow = Catalog {
+        entries: vec![],
+        tags: (0..=255).collect(),
+    };
+    assert!(matches!(
+        overflow.strict_serialize(),
+        Err(strict_encoding::Error::ExceedMaxItems(256))
+    ));
+
+    let update = CatalogUpdate::Insert(vec![3, 4]);
+    let data = update.strict_serialize().unwrap();
+    assert_eq!(data, vec![0u8, 2, 0, 0, 0, 0, 0, 0, 0, 3, 4]);
+    assert_eq!(CatalogUpdate::strict_deserialize(&data).unwrap(), update);
+
+    assert_eq!(
+        IntMagic(7).strict_serialize().unwrap(),
+        vec![0x31, 0x4B, 0x54, 0x53, 7]
+    );
+    assert_eq!(IntMagic(7).strict_encode(vec![]).unwrap(), 5);
+    assert_eq!(BytesMagic::strict_deserialize(b"RGB\x07").unwrap().0, 7);
+    assert!(matches!(
+        BytesMagic::strict_deserialize(b"RGC\x07"),
+        Err(strict_encoding::Error::DataIntegrityError(msg))
+            if msg.contains("BytesMagic")
+    ));
+    // Data too short to contain the magic bytes
+    assert!(matches!(
+        BytesMagic::strict_deserialize(b"RG"),
+        Err(strict_encoding::Error::Io(_))
+    ));
+
+    let either = Either::<u8, u16, NotEncodable>::Right(0x0201);
+    let ser = either.strict_serialize().unwrap();
+    assert_eq!(ser, vec![1, 1, 2]);
+    match Either::<u8, u16, NotEncodable>::strict_deserialize(&ser).unwrap() {
+        Either::Right(val) => assert_eq!(val, 0x0201),
+        _ => panic!("wrong enum variant decoded"),
+    }
+
+    let record = Record {
+        kind: 1,
+        data: vec![0xFF],
+    };
+    let ser = record.strict_serialize().unwrap();
+    assert_eq!(ser, vec![4, 0, 1, 1, 0, 0xFF]);
+    assert_eq!(Record::strict_deserialize(&ser).unwrap().data, vec![0xFF]);
+    // Inner data are longer than declared by the prefix
+    assert!(Record::strict_deserialize([3, 0, 1, 1, 0, 0xFF]).is_err());
+    // Inner data are shorter than declared by the prefix
+    assert!(Record::strict_deserialize([5, 0, 1, 1, 0, 0xFF, 0]).is_err());
+
+    let bindings = Bindings {
+        e: 1,
+        d: 2,
+        len: 3,
+        data: vec![4],
+    };
+    let nested = Nested {
+        inner: bindings.clone(),
+        tail: Some(bindings.clone()),
+    };
+    let ser = nested.strict_serialize().unwrap();
+    assert_eq!(ser, vec![1, 2, 0, 3, 1, 0, 4, 1, 1, 2, 0, 3, 1, 0, 4]);
+    assert_eq!(Nested::strict_deserialize(&ser).unwrap(), nested);
+    let nested_enum = NestedEnum::Named {
+        e: nested,
+        len: 5,
+        data: bindings.clone(),
+    };
+    let ser = nested_enum.strict_serialize().unwrap();
+    assert_eq!(ser.len(), 1 + 15 + 1 + 7);
+    assert_eq!(NestedEnum::strict_deserialize(&ser).unwrap(), nested_enum);
+    let plain = NestedEnum::Plain(bindings);
+    assert_eq!(
+        NestedEnum::strict_deserialize(plain.strict_serialize().unwrap())
+            .unwrap(),
+        plain
+    );
+
+    assert_eq!(OldMessage { id: 7 }.strict_serialize().unwrap(), vec![7]);
+    assert_eq!(OldMessage::strict_deserialize([7]).unwrap().id, 7);
+    assert_eq!(OldMessage::strict_deserialize([7, 1, 2]).unwrap().id, 7);
+    let mut stream: &[u8] = &[7, 1, 2, 3];
+    assert_eq!(OldMessage::strict_decode(&mut stream).unwrap().id, 7);
+    assert_eq!(stream, [3]);
+    assert_eq!(OldStream::strict_deserialize([7, 1, 2, 3]).unwrap().id, 7);
+    stream = &[3, 7, 1, 2, 0xFF];
+    assert_eq!(OldRecord::strict_decode(&mut stream).unwrap().id, 7);
+    assert_eq!(stream, [0xFF]);
+
+    let versioned = Versioned {
+        id: 1,
+        legacy: 0,
+        extra: 0x0102,
+        interim: 0,
+        current: 4,
+    };
+    let ser = versioned.strict_serialize().unwrap();
+    assert_eq!(ser, vec![3, 1, 2, 1, 4]);
+    assert_eq!(Versioned::strict_deserialize(&ser).unwrap(), versioned);
+    assert_eq!(
+        Versioned::strict_deserialize([1, 1, 5, 6]).unwrap(),
+        Versioned {
+            id: 1,
+            legacy: 5,
+            extra: 0,
+            interim: 0,
+            current: 6,
+        }
+    );
+    assert_eq!(
+        Versioned::strict_deserialize([2, 1, 2, 1, 7, 8]).unwrap(),
+        Versioned {
+            id: 1,
+            legacy: 0,
+            extra: 0x0102,
+            interim: 7,
+            current: 8,
+        }
+    );
+    assert!(matches!(
+        Versioned::strict_deserialize([4, 1, 2, 1, 4]),
+        Err(strict_encoding::Error::ValueOutOfRange("Versioned", _, 4))
+    ));
+    let wide = WideVersioned { id: 9 };
+    let ser = wide.strict_serialize().unwrap();
+    assert_eq!(ser, vec![b'V', 1, 0, 9]);
+    assert_eq!(WideVersioned::strict_deserialize(&ser).unwrap(), wide);
+
+    let extensible = Extensible {
+        id: 1,
+        name: Some("ab".to_owned()),
+        amount: Some(3),
+    };
+    let ser = extensible.strict_serialize().unwrap();
+    assert_eq!(ser, vec![1, 2, 0, b'a', b'b', 3, 0, 0, 0]);
+    assert_eq!(Extensible::strict_deserialize(&ser).unwrap(), extensible);
+    let short = Extensible {
+        id: 1,
+        name: Some("ab".to_owned()),
+        amount: None,
+    };
+    assert_eq!(Extensible::strict_deserialize(&ser[..5]).unwrap(), short);
+    assert_eq!(short.strict_serialize().unwrap(), ser[..5].to_vec());
+    let empty = Extensible {
+        id: 1,
+        name: None,
+        amount: None,
+    };
+    assert_eq!(Extensible::strict_deserialize([1]).unwrap(), empty);
+    // EOF in the middle of a field
+    assert!(Extensible::strict_deserialize(&ser[..7]).is_err());
+    // Present field after an absent one can't be encoded
+    assert!(Extensible {
+        id: 1,
+        name: None,
+        amount: Some(3),
+    }
+    .strict_serialize()
+    .is_err());
+    let implied = ImpliedTail {
+        header: Some(1),
+        id: 2,
+        flag: None,
+        amount: None,
+    };
+    let ser = implied.strict_serialize().unwrap();
+    assert_eq!(ser, vec![1, 1, 2]);
+    assert_eq!(ImpliedTail::strict_deserialize(&ser).unwrap(), implied);
+    assert_eq!(
+        ImpliedTail::strict_deserialize([1, 1, 2, 3, 4, 0]).unwrap(),
+        ImpliedTail {
+            header: Some(1),
+            id: 2,
+            flag: Some(3),
+            amount: Some(4),
+        }
+    );
+
+    let mut stream: &[u8] = &[7];
+    assert_eq!(Exact::strict_decode(&mut stream).unwrap().id, 7);
+    stream = &[7, 8];
+    assert!(matches!(
+        Exact::strict_decode(&mut stream),
+        Err(strict_encoding::Error::DataNotEntirelyConsumed)
+    ));
+
+    let upgraded = Upgraded {
+        id: 1,
+        flags: 2,
+        limit: 3,
+        note: None,
+    };
+    let ser = upgraded.strict_serialize().unwrap();
+    assert_eq!(ser, vec![1, 2, 0, 3, 0, 0, 0]);
+    assert_eq!(Upgraded::strict_deserialize(&ser).unwrap(), upgraded);
+    assert_eq!(
+        Upgraded::strict_deserialize([1]).unwrap(),
+        Upgraded {
+            id: 1,
+            flags: 0,
+            limit: 10,
+            note: None,
+        }
+    );
+    assert_eq!(
+        Upgraded::strict_deserialize([1, 2, 0]).unwrap(),
+        Upgraded {
+            id: 1,
+            flags: 2,
+            limit: 10,
+            note: None,
+        }
+    );
+    // EOF in the middle of a field
+    assert!(Upgraded::strict_deserialize([1, 2]).is_err());
+
+    assert_eq!(
+        Resilient::strict_deserialize([1]).unwrap(),
+        Resilient::Known
+    );
+    assert_eq!(
+        Resilient::strict_deserialize([0]).unwrap(),
+        Resilient::Unknown
+    );
+    assert_eq!(
+        Resilient::strict_deserialize([0xFF]).unwrap(),
+        Resilient::Unknown
+    );
+
+    let checked = Checked {
+        payload: b"123456789".to_vec(),
+    };
+    let mut ser = checked.strict_serialize().unwrap();
+    assert_eq!(ser.len(), 2 + 9 + 4);
+    assert_eq!(ser[11..], [0xE1, 0xA2, 0x37, 0x3D]);
+    assert_eq!(checked.strict_encode(vec![]).unwrap(), 15);
+    assert_eq!(Checked::strict_deserialize(&ser).unwrap(), checked);
+    // Corrupted payload
+    ser[3] ^= 0x01;
+    assert!(matches!(
+        Checked::strict_deserialize(&ser),
+        Err(strict_encoding::Error::DataIntegrityError(msg))
+            if msg.contains("3d37a2e1")
+    ));
+    ser[3] ^= 0x01;
+    // Corrupted checksum
+    ser[14] ^= 0x80;
+    assert!(Checked::strict_deserialize(&ser).is_err());
+    let framed = FramedChecked { id: 7 };
+    let ser = framed.strict_serialize().unwrap();
+    assert_eq!(ser[..3], [b'C', 1, 7]);
+    assert_eq!(ser.len(), 7);
+    assert_eq!(FramedChecked::strict_deserialize(&ser).unwrap(), framed);
+    assert!(
+        FramedChecked::strict_deserialize([b'C', 1, 8, 0, 0, 0, 0]).is_err()
+    );
+
+    let buf = [1u8, 2, 3];
+    let borrowed = BorrowedPacket {
+        id: 7,
+        payload: &buf[..2],
+        raw: &buf,
+    };
+    let ser = borrowed.strict_serialize().unwrap();
+    assert_eq!(ser, vec![7, 2, 0, 1, 2, 3, 0, 0, 0, 1, 2, 3]);
+    assert_eq!(
+        OwnedPacket::strict_deserialize(&ser).unwrap(),
+        OwnedPacket {
+            id: 7,
+            payload: vec![1, 2],
+            raw: vec![1, 2, 3],
+        }
+    );
+
+    let slot = Slot {
+        id: 1,
+        name: "ab".to_owned(),
+    };
+    let ser = slot.strict_serialize().unwrap();
+    assert_eq!(ser, vec![1, 0, 2, 0, b'a', b'b', 0, 0]);
+    assert_eq!(slot.strict_encode(vec![]).unwrap(), 8);
+    assert_eq!(Slot::strict_deserialize(&ser).unwrap(), slot);
+    // Non-zero padding
+    assert!(Slot::strict_deserialize([1, 0, 2, 0, b'a', b'b', 0, 1]).is_err());
+    // Data exceeding the block size
+    assert!(Slot {
+        id: 1,
+        name: "abcde".to_owned(),
+    }
+    .strict_serialize()
+    .is_err());
+    let mut stream: &[u8] = &[1, 0, 1, 0, b'a', 0, 0, 0, 0xFF];
+    assert_eq!(Slot::strict_decode(&mut stream).unwrap().name, "a");
+    assert_eq!(stream, [0xFF]);
+    let checked_slot = CheckedSlot { id: 1 };
+    let ser = checked_slot.strict_serialize().unwrap();
+    assert_eq!(ser.len(), 8);
+    assert_eq!(ser[6..], [0, 0]);
+    assert_eq!(CheckedSlot::strict_deserialize(&ser).unwrap(), checked_slot);
+
+    let header = BatchedHeader {
+        kind: 1,
+        flag: true,
+        cached: 100,
+        port: 0x0203,
+        offset: -1,
+        ratio: 1.0,
+    };
+    let mut ser = vec![];
+    assert_eq!(header.strict_encode(&mut ser).unwrap(), 12);
+    let mut expected = vec![1, 1, 3, 2, 0xFF, 0xFF, 0xFF, 0xFF];
+    expected.extend(1.0f32.to_le_bytes());
+    assert_eq!(ser, expected);
+    assert_eq!(
+        BatchedHeader::strict_deserialize(&ser).unwrap(),
+        BatchedHeader {
+            cached: 0,
+            ..header
+        }
+    );
+
+    let pinned = Pinned {
+        name: "a".to_owned(),
+        cache: 5,
+        id: 0x0102,
+        flag: true,
+    };
+    let ser = pinned.strict_serialize().unwrap();
+    assert_eq!(ser, vec![2, 1, 1, 0, b'a', 1]);
+    assert_eq!(
+        Pinned::strict_deserialize(&ser).unwrap(),
+        Pinned {
+            cache: 0,
+            ..pinned.clone()
+        }
+    );
+    let mut target = Pinned {
+        name: String::new(),
+        cache: 7,
+        id: 0,
+        flag: false,
+    };
+    target.strict_decode_into(&ser[..]).unwrap();
+    assert_eq!(target, Pinned { cache: 7, ..pinned });
+    let range = PinnedRange(1, 2);
+    let ser = range.strict_serialize().unwrap();
+    assert_eq!(ser, vec![2, 1]);
+    assert_eq!(PinnedRange::strict_deserialize(&ser).unwrap(), range);
+    assert!(PinnedRange::strict_deserialize([1, 2]).is_err());
+
+    let mut first = Index {
+        entries: std::collections::HashMap::with_capacity(4),
+    };
+    let mut second = Index {
+        entries: std::collections::HashMap::with_capacity(64),
+    };
+    for key in 0..16u16 {
+        first.entries.insert(key * 31, key.to_string());
+        second
+            .entries
+            .insert((15 - key) * 31, (15 - key).to_string());
+    }
+    let ser = first.strict_serialize().unwrap();
+    assert_eq!(ser, second.strict_serialize().unwrap());
+    assert_eq!(Index::strict_deserialize(&ser).unwrap(), first);
+    // Unordered entries are accepted
+    assert_eq!(
+        Index::strict_deserialize([2, 0, 2, 0, 0, 0, 1, 0, 0, 0])
+            .unwrap()
+            .entries
+            .len(),
+        2
+    );
+
+    let reply = InfallibleReply::Ok(7);
+    let data = reply.strict_serialize().unwrap();
+    assert_eq!(data, vec![0u8, 7]);
+    assert_eq!(InfallibleReply::strict_deserialize(&data).unwrap(), reply);
+    assert!(matches!(
+        InfallibleReply::strict_deserialize([1u8, 0, 0]),
+        Err(strict_encoding::Error::UnsupportedDataStructure(_))
+    ));
+
+    let amount = Amount(1_000);
+    let data = amount.strict_serialize().unwrap();
+    assert_eq!(data, 1_000u64.strict_serialize().unwrap());
+    assert_eq!(Amount::strict_deserialize(&data).unwrap(), amount);
+
+    let marked = Marked::<Unencodable> {
+        id: 7,
+        marker: std::marker::PhantomData,
+    };
+    let data = marked.strict_serialize().unwrap();
+    assert_eq!(data, vec![7u8, 0, 0, 0]);
+    assert_eq!(
+        Marked::<Unencodable>::strict_deserialize(&data).unwrap().id,
+        marked.id
+    );
+
+    assert!(matches!(
+        Hi::strict_deserialize([9u8]),
+        Err(strict_encoding::Error::EnumValueNotKnown("Hi", 9))
+    ));
+
+    let frame = Frame {
+        kind: 3,
+        body: vec![1, 2],
+    };
+    let frames: Vec<Box<dyn DynFrame>> = vec![Box::new(frame.clone())];
+    let mut data = vec![];
+    assert_eq!(frames[0].encode(&mut data), 5);
+    assert_eq!(data, frame.strict_serialize().unwrap());
+
+    let word = Register { word: 0x0001_8000 };
+    let data = word.strict_serialize().unwrap();
+    assert_eq!(data, vec![1u8, 0, 0x00, 0x80, 0x01, 0x00]);
+    let decoded = Register::strict_deserialize(&data).unwrap();
+    assert_eq!(unsafe { decoded.word }, 0x0001_8000);
+    let data = Register { half: 7 }.strict_serialize().unwrap();
+    assert_eq!(data, vec![2u8, 0, 7, 0]);
+    assert_eq!(
+        unsafe { Register::strict_deserialize(&data).unwrap().half },
+        7
+    );
+    assert!(matches!(
+        Register::strict_deserialize([3u8, 0, 0, 0]),
+        Err(strict_encoding::Error::DataIntegrityError(_))
+    ));
+
+    let header = FrameHeader {
+        kind: 1,
+        flags: 2,
+        id: [3; 4],
+    };
+    assert_eq!(FrameHeader::ENCODED_SIZE, 7);
+    assert_eq!(
+        header.strict_serialize().unwrap().len(),
+        FrameHeader::ENCODED_SIZE
+    );
+    let envelope = FrameEnvelope {
+        header,
+        cache: vec![],
+        checksum: 0,
+    };
+    assert_eq!(FrameEnvelope::ENCODED_SIZE, 13);
+    assert_eq!(
+        envelope.strict_serialize().unwrap().len(),
+        FrameEnvelope::ENCODED_SIZE
+    );
+
+    let coordinates = Coordinates {
+        position: (1, 2, 3, 4, 5, 6),
+        label: "a".to_owned(),
+    };
+    let data = coordinates.strict_serialize().unwrap();
+    assert_eq!(data, vec![1u8, 2, 0, 3, 0, 0, 0, 4, 5, 6, 1, 0, b'a']);
+    assert_eq!(Coordinates::strict_deserialize(&data).unwrap(), coordinates);
+    let shape = Shape::Point((1, 2, 3, 4, 5, 6, 7));
+    let data = shape.strict_serialize().unwrap();
+    assert_eq!(data, vec![0u8, 1, 2, 3, 4, 5, 6, 7]);
+    assert_eq!(Shape::strict_deserialize(&data).unwrap(), shape);
+
+    let permissions = Permissions {
+        read: true,
+        write: false,
+        exec: true,
+        owner: 0x0201,
+        f0: false,
+        f1: true,
+        f2: false,
+        f3: false,
+        f4: false,
+        f5: false,
+        f6: false,
+        f7: true,
+        f8: true,
+    };
+    let data = permissions.strict_serialize().unwrap();
+    assert_eq!(data, vec![0b101u8, 0x01, 0x02, 0b1000_0010, 1]);
+    assert_eq!(Permissions::ENCODED_SIZE, data.len());
+    assert_eq!(Permissions::strict_deserialize(&data).unwrap(), permissions);
+    assert!(matches!(
+        Permissions::strict_deserialize([0b1101u8, 0x01, 0x02, 0, 0]),
+        Err(strict_encoding::Error::DataIntegrityError(_))
+    ));
+
+    for (command, tag) in [
+        (Command::Ping, 0x10u8),
+        (Command::Pong, 0x20),
+        (Command::Close, 0x30),
+    ] {
+        assert_eq!(command.strict_serialize().unwrap(), vec![tag]);
+        assert_eq!(Command::strict_deserialize([tag]).unwrap(), command);
+    }
+
+    let invoice = Invoice { amount: 5 };
+    let data = invoice.strict_serialize().unwrap();
+    assert_eq!(&data[..9], b"\x07\x00Invoice");
+    assert_eq!(Invoice::strict_deserialize(&data).unwrap(), invoice);
+    let receipt = Receipt { amount: 5 };
+    let data = receipt.strict_serialize().unwrap();
+    assert_eq!(&data[..9], b"\x07\x00receipt");
+    assert_eq!(Receipt::strict_deserialize(&data).unwrap(), receipt);
+    assert!(matches!(
+        Receipt::strict_deserialize(&invoice.strict_serialize().unwrap()),
+        Err(strict_encoding::Error::DataIntegrityError(_))
+    ));
+    let archived = Archived::Item(7u8);
+    let data = archived.strict_serialize().unwrap();
+    assert_eq!(data, b"\x08\x00Archived\x00\x07");
+    assert_eq!(Archived::<u8>::strict_deserialize(&data).unwrap(), archived);
+    assert_eq!(
+        Archived::<i8>::strict_deserialize(&data).unwrap(),
+        Archived::Item(7i8)
+    );
+
+    let re_exported = ReExported {
+        inner: deps::nested::Relative(0x0102),
+    };
+    let data = re_exported.strict_serialize().unwrap();
+    assert_eq!(data, vec![0x02u8, 0x01]);
+    assert_eq!(ReExported::strict_deserialize(&data).unwrap(), re_exported);
+
+    let holder = Holder::<u16, Unencodable> {
+        inner: 5,
+        marker: std::marker::PhantomData,
+    };
+    let data = holder.strict_serialize().unwrap();
+    assert_eq!(data, vec![5u8, 0]);
+    assert_eq!(
+        Holder::<u16, Unencodable>::strict_deserialize(&data)
+            .unwrap()
+            .inner,
+        5
+    );
+
+    let pixel = Pixel(1, 2, 3);
+    let data = pixel.strict_serialize().unwrap();
+    assert_eq!(data, vec![1u8, 2, 3]);
+    assert_eq!(Pixel::strict_deserialize(&data).unwrap(), pixel);
+    let data = Channel::Blue.strict_serialize().unwrap();
+    assert_eq!(data, vec![3u8]);
+    assert_eq!(Channel::strict_deserialize(&data).unwrap(), Channel::Blue);
+
+    let items = Items::<Bytes> { items: vec![1, 2] };
+    let data = items.strict_serialize().unwrap();
+    assert_eq!(data, vec![2u8, 0, 1, 2]);
+    assert_eq!(
+        Items::<Bytes>::strict_deserialize(&data).unwrap().items,
+        items.items
+    );
+    let tagged = Tagged::<Unencodable> {
+        id: 3,
+        marker: Marker(std::marker::PhantomData),
+    };
+    let data = tagged.strict_serialize().unwrap();
+    assert_eq!(data, vec![3u8, 0]);
+    assert_eq!(
+        Tagged::<Unencodable>::strict_deserialize(&data).unwrap().id,
+        3
+    );
+
+    let keywords = Keywords {
+        r#type: 1,
+        r#match: "m".to_owned(),
+        r#fn: Some(2),
+    };
+    let data = keywords.strict_serialize().unwrap();
+    assert_eq!(data, vec![1u8, 1, 0, b'm', 1, 2, 0]);
+    assert_eq!(Keywords::strict_deserialize(&data).unwrap(), keywords);
+
+    let raw = r#Loop { r#in: 1 };
+    let data = raw.strict_serialize().unwrap();
+    assert_eq!(data, b"\x04\x00Loop\x01".to_vec());
+    assert_eq!(r#Loop::strict_deserialize(&data).unwrap(), raw);
+
+    let sample = Sample {
+        id: 1,
+        levels: [2, 3, 4],
+        cached: vec![],
+    };
+    let data = sample.strict_serialize().unwrap();
+    assert_eq!(sample.strict_encode_size_hint(), Some(data.len()));
+    assert_eq!(sample.strict_encode_size_hint(), Some(8));
+    let samples = Samples {
+        count: 1,
+        samples: vec![sample],
+    };
+    assert_eq!(samples.strict_encode_size_hint(), None);
+
+    let phantoms = Phantoms::<Unencodable, Unencodable> {
+        id: 4,
+        producer: std::marker::PhantomData,
+        borrowed: std::marker::PhantomData,
+        tagged: Tagged {
+            id: 5,
+            marker: Marker(std::marker::PhantomData),
+        },
+    };
+    let data = phantoms.strict_serialize().unwrap();
+    assert_eq!(data, vec![4u8, 0, 5, 0]);
+    let decoded =
+        Phantoms::<Unencodable, Unencodable>::strict_deserialize(&data)
+            .unwrap();
+    assert_eq!((decoded.id, decoded.tagged.id), (4, 5));
+
+    let mut buf = vec![0xFFu8];
+    assert_eq!(Event::Start(2).strict_encode_append(&mut buf).unwrap(), 2);
+    assert_eq!(Event::Stop.strict_encode_append(&mut buf).unwrap(), 1);
+    assert_eq!(buf, vec![0xFFu8, 0, 2, 1]);
+
+    let telemetry = Telemetry {
+        id: 1,
+        detail: 2,
+        flags: 3,
+    };
+    let data = telemetry.strict_serialize().unwrap();
+    assert_eq!(data, vec![1u8, 0, 3]);
+    assert_eq!(Telemetry::ENCODED_SIZE, 3);
+    assert_eq!(
+        Telemetry::strict_deserialize(&data).unwrap(),
+        Telemetry {
+            detail: 0,
+            ..telemetry
+        }
+    );
+
+    let mut cache = Cache::<fn() -> u8> {
+        index: Default::default(),
+        payload: vec![1, 2],
+    };
+    cache.index.insert(1, || 1);
+    let data = cache.strict_serialize().unwrap();
+    assert_eq!(data, vec![2u8, 0, 1, 2]);
+    let decoded = Cache::<fn() -> u8>::strict_deserialize(&data).unwrap();
+    assert_eq!(decoded.payload, cache.payload);
+    assert!(decoded.index.is_empty());
+
+    for (value, byte) in [(Discriminant::Foo, 10u8), (Discriminant::Bar, 20)] {
+        let data = value.strict_serialize().unwrap();
+        assert_eq!(data, vec![byte]);
+        assert_eq!(Discriminant::strict_deserialize(&data).unwrap(), value);
+    }
+    assert!(Discriminant::strict_deserialize([1u8]).is_err());
+
+    let observed = Observed::<Unencodable, u8> {
+        tagged: Tagged {
+            id: 1,
+            marker: Marker(std::marker::PhantomData),
+        },
+        other: Tagged {
+            id: 2,
+            marker: Marker(std::marker::PhantomData),
+        },
+    };
+    let data = observed.strict_serialize().unwrap();
+    assert_eq!(data, vec![1u8, 0, 2, 0]);
+    let decoded =
+        Observed::<Unencodable, Unencodable>::strict_deserialize(&data)
+            .unwrap();
+    assert_eq!((decoded.tagged.id, decoded.other.id), (1, 2));
+
+    let data = Algorithm::Blake3.strict_serialize().unwrap();
+    assert_eq!(data, vec![3u8]);
+    assert_eq!(
+        Algorithm::strict_deserialize(&data).unwrap(),
+        Algorithm::Blake3
+    );
+    assert_eq!(
+        Algorithm::strict_deserialize([1u8]).unwrap(),
+        Algorithm::Sha256
+    );
+    assert!(Algorithm::strict_deserialize([2u8]).is_err());
+    assert!(matches!(
+        Algorithm::Sha1(1).strict_serialize(),
+        Err(strict_encoding::Error::UnsupportedDataStructure(_))
+    ));
+
+    let packet = Packet::<2> {
+        bytes: [1, 2],
+        flags: 3,
+    };
+    let data = packet.strict_serialize().unwrap();
+    assert_eq!(data, vec![1u8, 2, 3, 0]);
+    assert_eq!(Packet::<2>::strict_deserialize(&data).unwrap(), packet);
+    let packet = Packet::<4> {
+        bytes: [1, 2, 3, 4],
+        flags: 5,
+    };
+    let data = packet.strict_serialize().unwrap();
+    assert_eq!(data, vec![1u8, 2, 3, 4, 5, 0]);
+    assert_eq!(Packet::<4>::strict_deserialize(&data).unwrap(), packet);
+    let series = Series::<u16, 3> {
+        items: [1, 2, 3],
+        cached: None,
+    };
+    let data = series.strict_serialize().unwrap();
+    assert_eq!(data, vec![1u8, 0, 2, 0, 3, 0]);
+    assert_eq!(Series::<u16, 3>::strict_deserialize(&data).unwrap(), series);
+    let series = Series::<String, 2> {
+        items: ["a".to_owned(), "bc".to_owned()],
+        cached: None,
+    };
+    let data = series.strict_serialize().unwrap();
+    assert_eq!(data, vec![1u8, 0, b'a', 2, 0, b'b', b'c']);
+    assert_eq!(
+        Series::<String, 2>::strict_deserialize(&data).unwrap(),
+        series
+    );
+
+    let header = CHeader {
+        tag: 1,
+        len: 2,
+        flags: [3, 4],
+        last: 5,
+    };
+    let data = header.strict_serialize().unwrap();
+    assert_eq!(data.len(), std::mem::size_of::<CHeader>());
+    assert_eq!(data, vec![1u8, 0, 0, 0, 2, 0, 0, 0, 3, 0, 4, 0, 5, 0, 0, 0]);
+    assert_eq!(CHeader::strict_deserialize(&data).unwrap(), header);
+    let aligned = CAligned { tag: 1, len: 2 };
+    let data = aligned.strict_serialize().unwrap();
+    assert_eq!(data.len(), 32);
+    assert_eq!(data[..8], [1u8, 0, 0, 0, 2, 0, 0, 0]);
+    assert!(data[8..].iter().all(|byte| *byte == 0));
+    assert_eq!(CAligned::strict_deserialize(&data).unwrap(), aligned);
+
+    let payload = Payload::Ping(5);
+    assert_eq!(payload.strict_variant_tag(), 0x10);
+    assert_eq!(Payload::Pong.strict_variant_tag(), 0x20);
+    let data = payload.strict_serialize().unwrap();
+    assert_eq!(data, vec![5u8, 0]);
+    assert_eq!(
+        Payload::strict_decode_variant(0x10, &data[..]).unwrap(),
+        payload
+    );
+    assert_eq!(
+        Payload::strict_decode_variant(0x20, &[][..]).unwrap(),
+        Payload::Pong
+    );
+    assert!(Payload::strict_decode_variant(0x30, &data[..]).is_err());
+
+    let bytes = [7u8, 8];
+    let quote = Quote {
+        name: Cow::Borrowed("ab"),
+        data: Cow::Borrowed(&bytes[..]),
+        kind: Cow::Owned("c".to_owned()),
+    };
+    let data = quote.strict_serialize().unwrap();
+    assert_eq!(data, vec![2u8, 0, b'a', b'b', 2, 0, 7, 8, 1, 0, b'c']);
+    let decoded = Quote::strict_deserialize(&data).unwrap();
+    assert!(matches!(decoded.name, Cow::Owned(_)));
+    assert!(matches!(decoded.data, Cow::Owned(_)));
+    assert_eq!(decoded, quote);
+
+    let grades = [Grade(1), Grade(2)];
+    let card = Scorecard {
+        best: Cow::Borrowed(&grades[1]),
+        all: Cow::Borrowed(&grades[..]),
+        last: Cow::Owned(Grade(3)),
+        cow: farm::Cow(4),
+    };
+    let data = card.strict_serialize().unwrap();
+    assert_eq!(data, vec![2u8, 2, 0, 1, 2, 3, 4]);
+    let decoded = Scorecard::<Grade>::strict_deserialize(&data).unwrap();
+    assert!(matches!(decoded.best, Cow::Owned(_)));
+    assert_eq!(decoded, card);
+
+    let node = Node::<Ping> {
+        msg: 5,
+        replies: vec!["ok".to_owned()],
+    };
+    let data = node.strict_serialize().unwrap();
+    assert_eq!(data, vec![5u8, 0, 1, 0, 2, 0, b'o', b'k']);
+    let decoded = Node::<Ping>::strict_deserialize(&data).unwrap();
+    assert_eq!(decoded.msg, node.msg);
+    assert_eq!(decoded.replies, node.replies);
+
+    let cell = Cell { id: 1, value: 2 };
+    assert_eq!(Cell::ENCODED_SIZE, 16);
+    let mut data = vec![];
+    assert_eq!(cell.strict_encode(&mut data).unwrap(), 16);
+    assert_eq!(data, vec![1u8, 2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]);
+    data.push(0xFF);
+    let mut stream = &data[..];
+    assert_eq!(Cell::strict_decode(&mut stream).unwrap(), cell);
+    assert_eq!(stream, &[0xFF]);
+    data[15] = 1;
+    assert!(Cell::strict_decode(&data[..]).is_err());
+
+    assert!(Consensus::strict_is_canonical(&[1, 0]).unwrap());
+    assert!(!Consensus::strict_is_canonical(&[1, 0, 0xFF]).unwrap());
+    assert!(Consensus::strict_is_canonical(&[1]).is_err());
+
+    let worker = Worker::<Ping> {
+        id: 3,
+        handler: Box::new(Accept),
+    };
+    let data = worker.strict_serialize().unwrap();
+    assert_eq!(data, vec![3u8, 0]);
+    let worker = Worker::<Ping>::strict_deserialize(&data).unwrap();
+    assert_eq!(worker.id, 3);
+    assert!(worker.handler.handle(&Ping));
+
+    let handshake = Handshake {
+        version: 1,
+        session: 0,
+        agent: "a".to_owned(),
+    };
+    let data = handshake.strict_serialize().unwrap();
+    assert_eq!(data, vec![0u8, 1, 2, 1, 0, b'a']);
+    assert_eq!(Handshake::strict_deserialize(&data).unwrap(), handshake);
+    assert!(Handshake::strict_deserialize([0u8, 1, 1, 1, 0, b'a']).is_err());
+
+    let hello = Hello(1, 2);
+    assert_eq!(Hello::ENCODED_SIZE, 7);
+    let data = hello.strict_serialize().unwrap();
+    assert_eq!(data, vec![0u8, 0, 1, 1, 0, 2, 0]);
+    assert_eq!(Hello::strict_deserialize(&data).unwrap(), hello);
+
+    for (message, data) in [
+        (Message::Ping, vec![0u8]),
+        (Message::Data(vec![7, 8]), vec![1u8, 2, 0, 7, 8]),
+        (Message::Pong, vec![2u8]),
+        (
+            Message::Error {
+                code: 404,
+                message: "no".to_owned(),
+            },
+            vec![3u8, 0x94, 0x01, 2, 0, b'n', b'o'],
+        ),
+    ] {
+        assert_eq!(message.strict_serialize().unwrap(), data);
+        assert_eq!(Message::strict_deserialize(&data).unwrap(), message);
+    }
+    assert!(Message::strict_deserialize([4u8]).is_err());
+    assert!(Message::strict_deserialize([3u8, 0x94]).is_err());
+
+    let route = Route {
+        hops: Inline(vec![1, 2]),
+        stops: Inline(vec![9]),
+    };
+    let data = route.strict_serialize().unwrap();
+    assert_eq!(data, vec![2u8, 0, 1, 0, 2, 0, 1, 9]);
+    assert_eq!(Route::strict_deserialize(&data).unwrap(), route);
+    let route = Route {
+        hops: Inline(vec![1, 2, 3, 4]),
+        stops: Inline::new(),
+    };
+    assert!(route.strict_serialize().is_err());
+    assert!(
+        Route::strict_deserialize([4u8, 0, 1, 0, 2, 0, 3, 0, 4, 0, 0]).is_err()
+    );
+    let route = Route {
+        hops: Inline::new(),
+        stops: Inline((0..=255).collect()),
+    };
+    assert!(matches!(
+        route.strict_serialize(),
+        Err(strict_encoding::Error::ExceedMaxItems(256))
+    ));
+
+    assert_eq!(
+        Account::strict_dumb(),
+        Account {
+            id: 0,
+            name: "anonymous".to_owned(),
+            balance: 0,
+        }
+    );
+    assert_eq!(
+        Status::strict_dumb(),
+        Status::Closed {
+            code: 7,
+            reason: String::new(),
+        }
+    );
+    let data = Status::strict_dumb().strict_serialize().unwrap();
+    assert_eq!(data, vec![2u8, 7, 0, 0, 0]);
+    assert_eq!(Level::strict_dumb(), Level::Low(0));
+    assert_eq!(
+        Queue::<Opaque>::strict_dumb(),
+        Queue {
+            id: 0,
+            items: vec![],
+            cached: None
+        }
+    );
+    assert_eq!(Choice::<Opaque, Opaque>::strict_dumb(), Choice::Unset);
+
+    let outcome = Outcome::<String> {
+        id: 1,
+        result: Ok(2),
+    };
+    let data = outcome.strict_serialize().unwrap();
+    assert_eq!(data, vec![1u8, 0, 2, 0]);
+    assert_eq!(Outcome::strict_deserialize(&data).unwrap(), outcome);
+    let outcome = Outcome::<String> {
+        id: 1,
+        result: Err("e".to_owned()),
+    };
+    let data = outcome.strict_serialize().unwrap();
+    assert_eq!(data, vec![1u8, 1, 1, 0, b'e']);
+    assert_eq!(Outcome::strict_deserialize(&data).unwrap(), outcome);
+    assert!(Outcome::<String>::strict_deserialize([1u8, 2, 2, 0]).is_err());
+    let verdict = Verdict {
+        local: proto::Result(1, 2),
+        std: Err(3),
+    };
+    let data = verdict.strict_serialize().unwrap();
+    assert_eq!(data, vec![1u8, 2, 1, 3]);
+    assert_eq!(Verdict::strict_deserialize(&data).unwrap(), verdict);
+
+    use network_encoding::{NetworkDecode, NetworkEncode};
+    let link = Link::Open(0x0203);
+    let data = link.strict_serialize().unwrap();
+    assert_eq!(data, vec![0u8, 0x03, 0x02]);
+    assert_eq!(Link::strict_deserialize(&data).unwrap(), link);
+    let data = link.network_serialize().unwrap();
+    assert_eq!(data, vec![0x01u8, 0x00, 0x02, 0x03]);
+    assert_eq!(Link::network_deserialize(&data).unwrap(), link);
+    assert_eq!(Link::Closed.network_serialize().unwrap(), vec![0u8, 1]);
+    assert_eq!(Link::Closed.strict_serialize().unwrap(), vec![0x20u8]);
+    let session = Session {
+        strict_mode: 1,
+        port: 0x0203,
+    };
+    let data = session.network_serialize().unwrap();
+    assert_eq!(data, vec![0u8, 1, 0x02, 0x03]);
+    assert_eq!(Session::network_deserialize(&data).unwrap(), session);
+
+    let datagram = Datagram {
+        port: 7,
+        body: "hi".to_owned(),
+    };
+    let mut data = datagram.strict_serialize().unwrap();
+    assert_eq!(data, vec![6u8, 0, 0, 0, 7, 0, 2, 0, b'h', b'i']);
+    assert_eq!(Datagram::strict_deserialize(&data).unwrap(), datagram);
+    // Parsers unaware of the format skip the record using its declared length
+    data.push(0xAA);
+    let len = u32::strict_deserialize(&data[..4]).unwrap() as usize;
+    assert_eq!(data[4 + len..], [0xAA]);
+
+    let sensor = Sensor { id: 1, reading: 2 };
+    let mut buf = vec![0xFFu8];
+    assert_eq!(
+        sensor.strict_encode_append(&mut buf).unwrap(),
+        Sensor::ENCODED_SIZE
+    );
+    assert_eq!(buf, vec![0xFFu8, 1, 2, 0]);
+    assert_eq!(Sensor::strict_deserialize(&buf[1..]).unwrap(), sensor);
+
+    let mode = Mode::Write(3);
+    let data = mode.strict_serialize().unwrap();
+    assert_eq!(data, vec![0x20u8, 3, 0]);
+    assert_eq!(Mode::strict_deserialize(&data).unwrap(), mode);
+    let report = Report {
+        mode: Mode::Read,
+        cache: None,
+    };
+    assert_eq!(report.strict_serialize().unwrap(), vec![0x10u8]);
+
+    let key = PublicKey {
+        version: 2,
+        len: 4,
+        key: vec![1, 2, 3, 4],
+    };
+    let data = key.strict_serialize().unwrap();
+    assert_eq!(PublicKey::strict_deserialize(&data).unwrap(), key);
+    assert!(
+        PublicKey::strict_deserialize([3u8, 4, 0, 4, 0, 1, 2, 3, 4]).is_err()
+    );
+    assert!(PublicKey::strict_deserialize([2u8, 3, 0, 3, 0, 1, 2, 3]).is_err());
+
+    let sealed = vault::Sealed::new(1, vec![2]);
+    let data = sealed.strict_serialize().unwrap();
+    assert_eq!(data, vec![1u8, 0, 0, 0, 1, 0, 2]);
+    assert_eq!(vault::Sealed::strict_deserialize(&data).unwrap(), sealed);
+
+    assert!(matches!(load_mode(&[0x10u8]), Ok(Mode::Read)));
+    assert!(matches!(load_mode(&[0x30u8]), Err(AppError::Encoding(_))));
+
+    let roster = Roster {
+        names: vec!["a".to_owned()],
+        ids: vec![1, 2, 3],
+        level: 0,
+    };
+    let data = roster.strict_serialize().unwrap();
+    assert_eq!(Roster::strict_deserialize(&data).unwrap(), roster);
+    let mut long = roster.clone();
+    long.names = vec!["a".to_owned(); 3];
+    assert!(long.strict_serialize().is_err());
+    long.names.pop();
+    long.ids.push(4);
+    assert!(long.strict_serialize().is_err());
+    // Decoding checks the limits as well
+    let data = [2u8, 0, 0, 0, 0, 0, 4, 0, 1, 2, 3, 4, 0];
+    assert!(matches!(
+        Roster::strict_deserialize(data),
+        Err(strict_encoding::Error::ExceedMaxItems(4))
+    ));
+
+    assert!(Batch::Single(vec![1]).strict_serialize().is_ok());
+    assert!(Batch::Single(vec![1, 2]).strict_serialize().is_err());
+    let batch = Batch::Many(vec![1, 2, 3, 4]);
+    let data = batch.strict_serialize().unwrap();
+    assert_eq!(Batch::strict_deserialize(&data).unwrap(), batch);
+
+    use confined_encoding::{ConfinedDecode, ConfinedEncode};
+    let digest = Digest {
+        tags: vec![1],
+        data: vec![2, 3, 4],
+    };
+    let data = digest.confined_serialize().unwrap();
+    assert_eq!(data, vec![1u8, 1, 3, 2, 3, 4]);
+    assert_eq!(Digest::confined_deserialize(&data).unwrap(), digest);
+    assert!(Digest::confined_deserialize([3u8, 1, 2, 3, 0]).is_err());
+    let digest = Digest {
+        tags: vec![],
+        data: vec![0; 5],
+    };
+    assert!(digest.confined_serialize().is_err());
+    let capsule = Capsule {
+        strict_tags: vec![7],
+    };
+    let data = capsule.confined_serialize().unwrap();
+    assert_eq!(data, vec![1u8, 7]);
+    assert_eq!(Capsule::confined_deserialize(&data).unwrap(), capsule);
+    let capsule = Capsule {
+        strict_tags: vec![1, 2],
+    };
+    assert!(capsule.confined_serialize().is_err());
+
+    use bitcoin_hashes::{sha256, Hash};
+    use commit_verify::CommitEncode;
+    let anchor = Anchor {
+        height: 5,
+        script: vec![1, 2],
+        secret: Secret(0x0102),
+        cache: 9,
+    };
+    let data = anchor.commit_serialize();
+    let mut expected = vec![5u8, 0];
+    expected.extend(sha256::Hash::hash(&[2u8, 0, 1, 2]).into_inner());
+    expected.push(2);
+    assert_eq!(data, expected);
+    assert_eq!(anchor.commit_encode(&mut vec![]), 35);
+    let sealed = Sealed {
+        seal: 0x0201u16,
+        note: Opaque(3),
+    };
+    assert_eq!(sealed.commit_serialize(), vec![1u8, 2]);
+
+    let parcel = Parcel {
+        id: 0x0201,
+        payload: vec![3],
+        received: false,
+        kind: 4,
+    };
+    let data = parcel.strict_serialize().unwrap();
+    assert_eq!(data, vec![4u8, 1, 2, 1, 0, 3]);
+    assert_eq!(Parcel::strict_deserialize(&data).unwrap(), parcel);
+    let data = Swapped(1, 2).strict_serialize().unwrap();
+    assert_eq!(data, vec![2u8, 0, 1]);
+    assert_eq!(Swapped::strict_deserialize(&data).unwrap(), Swapped(1, 2));
+
+    let pair = plain::Pair {
+        first: 1u8,
+        second: 2,
+    };
+    assert_eq!(plain::roundtrip(&pair).unwrap(), pair);
+    assert!(matches!(
+        plain::Pair::<u8>::strict_deserialize([1u8, 2, 3]),
+        Err(strict_encoding::Error::DataNotEntirelyConsumed)
+    ));
+
+    let trailer = Trailer {
+        kind: 3,
+        cached: false,
+        items: vec![0x0201],
+    };
+    let data = trailer.strict_serialize().unwrap();
+    assert_eq!(data, vec![3u8, 1, 0, 1, 2, 5]);
+    assert_eq!(trailer.strict_encode(&mut vec![]).unwrap(), 6);
+    let mut log = vec![9u8, 9, 9];
+    log.extend(&data);
+    assert_eq!(Trailer::strict_decode_from_slice(&log).unwrap(), trailer);
+    assert!(Trailer::strict_decode_from_slice(&[3u8, 1, 0, 1, 2, 4]).is_err());
+    assert!(matches!(
+        Trailer::strict_decode_from_slice(&[0u8, 0, 0, 7, 4]),
+        Err(strict_encoding::Error::DataNotEntirelyConsumed)
+    ));
+    assert!(Trailer::strict_decode_from_slice(&[3u8, 1, 0, 1, 2, 9]).is_err());
+    assert!(Trailer::strict_decode_from_slice(&[]).is_err());
+
+    assert_eq!(Octant::Two.strict_serialize().unwrap(), vec![3u8]);
+    assert_eq!(Octant::strict_deserialize([4u8]).unwrap(), Octant::Three);
+
+    let migrated = Migrated {
+        id: 0x0201,
+        fee: 3,
+        flags: 4,
+        cached: true,
+        amount: 5,
+    };
+    let data = migrated.strict_serialize().unwrap();
+    // Encoded as `id, amount, fee, flags`
+    assert_eq!(data, vec![1u8, 2, 5, 0, 0, 0, 3, 4]);
+    // Decoded as `id, fee, amount, flags`
+    assert_eq!(
+        Migrated::strict_deserialize(&data).unwrap(),
+        Migrated {
+            id: 0x0201,
+            fee: 5,
+            flags: 4,
+            cached: false,
+            amount: 0x0300_0000,
+        }
+    );
+    let data = [1u8, 2, 3, 5, 0, 0, 0, 4];
+    assert_eq!(
+        Migrated::strict_deserialize(data).unwrap(),
+        Migrated {
+            id: 0x0201,
+            fee: 3,
+            flags: 4,
+            cached: false,
+            amount: 5,
+        }
+    );
+
+    let inputs = [
+        FeeInput::Empty,
+        FeeInput::Witness(vec![1, 2, 3]),
+        FeeInput::Padded(FeeRecord {
+            amount: 10,
+            note: "fee".to_string(),
+        }),
+    ];
+    for (input, len) in inputs.iter().zip([2usize, 7, 18]) {
+        assert_eq!(input.strict_serialized_len(), len);
+        assert_eq!(input.strict_serialize().unwrap().len(), len);
+    }
+
+    let short = LedgerShort {
+        id: 1,
+        cache: vec![1, 2],
+        value: 3,
+    };
+    let full = LedgerFull {
+        id: 1,
+        cache: vec![1, 2],
+        value: 3,
+    };
+    let data = short.strict_serialize().unwrap();
+    assert_eq!(data, full.strict_serialize().unwrap());
+    assert_eq!(data, vec![1u8, 0, 3]);
+    assert_eq!(
+        LedgerShort::strict_deserialize(&data).unwrap(),
+        LedgerShort {
+            cache: vec![],
+            ..short
+        }
+    );
+    assert_eq!(LedgerKind::Debit.strict_serialize().unwrap(), vec![2u8]);
+    assert!(LedgerKind::Legacy.strict_serialize().is_err());
+    assert_eq!(
+        LedgerKind::strict_deserialize([1u8]).unwrap(),
+        LedgerKind::Credit
+    );
+
+    {
+        // Function-local types, with the field type having inherent methods
+        // named like the trait ones, and local traits shadowing the names of
+        // strict encoding traits
+        #[allow(dead_code)]
+        trait StrictEncode {}
+        #[allow(dead_code)]
+        trait StrictDecode {}
+
+        #[derive(Clone, Copy, PartialEq, Eq, Debug)]
+        struct Inner(u8);
+        impl Inner {
+            fn strict_encode<E: std::io::Write>(
+                &self,
+                _: E,
+            ) -> Result<usize, strict_encoding::Error> {
+                unreachable!("inherent method must not be called")
+            }
+            fn strict_decode<D: std::io::Read>(
+                _: D,
+            ) -> Result<Self, strict_encoding::Error> {
+                unreachable!("inherent method must not be called")
+            }
+        }
+        impl strict_encoding::StrictEncode for Inner {
+            fn strict_encode<E: std::io::Write>(
+                &self,
+                e: E,
+            ) -> Result<usize, strict_encoding::Error> {
+                strict_encoding::StrictEncode::strict_encode(&self.0, e)
+            }
+        }
+        impl strict_encoding::StrictDecode for Inner {
+            fn strict_decode<D: std::io::Read>(
+                d: D,
+            ) -> Result<Self, strict_encoding::Error> {
+                strict_encoding::StrictDecode::strict_decode(d).map(Inner)
+            }
+        }
+
+        #[derive(
+            Clone,
+            PartialEq,
+            Eq,
+            Debug,
+            strict_encoding::StrictEncode,
+            strict_encoding::StrictDecode,
+        )]
+        #[strict_encoding(helpers)]
+        struct Local {
+            inner: Inner,
+            list: Vec<Inner>,
+            name: String,
+        }
+
+        let local = Local {
+            inner: Inner(1),
+            list: vec![Inner(2)],
+            name: "a".to_string(),
+        };
+        let data = local.strict_serialize().unwrap();
+        assert_eq!(data, vec![1u8, 1, 0, 2, 1, 0, b'a']);
+        assert_eq!(Local::strict_deserialize(&data).unwrap(), local);
+        let _ = Inner::strict_encode::<Vec<u8>>;
+        let _ = Inner::strict_decode::<&[u8]>;
+    }
+
+    let bytes = [1u8, 2, 3];
+    let snippets = [
+        (
+            Snippet::Text(Cow::Borrowed("hi")),
+            OwnedSnippet::Text("hi".to_owned()),
+        ),
+        (
+            Snippet::Bytes {
+                data: Cow::Borrowed(&bytes[..]),
+                tag: 9,
+            },
+            OwnedSnippet::Bytes {
+                data: bytes.to_vec(),
+                tag: 9,
+            },
+        ),
+    ];
+    for (snippet, owned) in snippets {
+        let data = snippet.strict_serialize().unwrap();
+        assert_eq!(data, owned.strict_serialize().unwrap());
+        let decoded = Snippet::strict_deserialize(&data).unwrap();
+        assert_eq!(decoded, snippet);
+        assert!(matches!(
+            decoded,
+            Snippet::Text(Cow::Owned(_))
+                | Snippet::Bytes {
+                    data: Cow::Owned(_),
+                    ..
+                }
+        ));
+    }
+
+    let tick = Tick {
+        symbol: 7,
+        prices: (0..32).collect(),
+    };
+    let (naive, naive_allocations) = count_allocations(|| {
+        let mut buf = Vec::new();
+        tick.strict_encode(&mut buf).unwrap();
+        buf
+    });
+    let (data, allocations) =
+        count_allocations(|| tick.strict_encode_to_vec().unwrap());
+    assert_eq!(data, naive);
+    assert_eq!(data.capacity(), data.len());
+    assert_eq!(allocations, 1);
+    assert!(allocations < naive_allocations);
+
+    let candle = Candle {
+        open: 100,
+        close: 105,
+        volume: 3000,
+        venue: String::new(),
+    };
+    let (data, allocations) =
+        count_allocations(|| candle.strict_encode_to_vec().unwrap());
+    assert_eq!(data, candle.strict_serialize().unwrap());
+    // Only the length of the string prefix is not known at compile time
+    assert_eq!(data.len(), 22);
+    assert!(data.capacity() >= 20);
+    assert!(allocations <= 2);
+
+    assert_eq!(Opcode::Pop.strict_serialize().unwrap(), vec![0x48u8]);
+    assert_eq!(Opcode::strict_deserialize([0x40u8]).unwrap(), Opcode::Push);
+    assert_eq!(Opcode::strict_deserialize([0x48u8]).unwrap(), Opcode::Pop);
+    assert_eq!(Opcode::strict_deserialize([3u8]).unwrap(), Opcode::Nop);
+    assert_eq!(Opcode::strict_deserialize([4u8]).unwrap(), Opcode::Nop);
+    assert!(Opcode::strict_deserialize([0x44u8]).is_err());
+
+    let greeting = Greeting {
+        version: 1,
+        nonce: 0x0302,
+    };
+    let data = greeting.strict_serialize().unwrap();
+    assert_eq!(read_greeting(&data).unwrap(), greeting);
+    assert!(matches!(
+        read_greeting(&data[..2]),
+        Err(ClientError::Decoding(strict_encoding::Error::Io(_)))
+    ));
+    assert!(matches!(
+        Greeting::strict_deserialize([1u8, 2, 3, 4]),
+        Err(ClientError::Decoding(
+            strict_encoding::Error::DataNotEntirelyConsumed
+        ))
+    ));
+    let decoded: Result<Greeting, strict_encoding::Error> =
+        StrictDecode::strict_decode(&data[..]);
+    assert_eq!(decoded.unwrap(), greeting);
+
+    let envelope = Envelope {
+        kind: 2,
+        body: vec![0xAAu16],
+    };
+    let bytes: Vec<u8> = (&envelope).into();
+    assert_eq!(bytes, vec![2u8, 1, 0, 0xAA, 0]);
+    assert_eq!(
+        <Envelope<Vec<u16>> as std::convert::TryFrom<&[u8]>>::try_from(
+            &bytes[..]
+        )
+        .unwrap(),
+        envelope
+    );
+    assert!(matches!(
+        <Envelope<u8> as std::convert::TryFrom<&[u8]>>::try_from(&[2u8, 3, 4]),
+        Err(strict_encoding::Error::DataNotEntirelyConsumed)
+    ));
+
+    assert_eq!(Beacon::STRICT_MAX_LEN, 8);
+    let beacon = Beacon {
+        id: 1,
+        payload: vec![1, 2, 3, 4],
+    };
+    let data = beacon.strict_serialize().unwrap();
+    assert_eq!(data.len(), 8);
+    assert_eq!(Beacon::strict_deserialize(&data).unwrap(), beacon);
+    let oversized = Beacon {
+        id: 1,
+        payload: vec![1, 2, 3, 4, 5],
+    };
+    assert!(matches!(
+        oversized.strict_serialize(),
+        Err(strict_encoding::Error::DataIntegrityError(_))
+    ));
+    // The length prefix claims more data than the limit allows
+    assert!(matches!(
+        Beacon::strict_deserialize([1u8, 0, 5, 0, 1, 2, 3, 4, 5]),
+        Err(strict_encoding::Error::DataIntegrityError(_))
+    ));
+    // Truncated data are still reported as such
+    assert!(matches!(
+        Beacon::strict_deserialize([1u8, 0, 4, 0, 1, 2]),
+        Err(strict_encoding::Error::Io(_))
+    ));
+
+    assert_eq!(Signal::STRICT_MAX_LEN, 4);
+    let data = Signal::Level(7).strict_serialize().unwrap();
+    assert_eq!(Signal::strict_deserialize(&data).unwrap(), Signal::Level(7));
+    let data = Signal::Burst(vec![9]).strict_serialize().unwrap();
+    assert_eq!(data, vec![2u8, 1, 0, 9]);
+    assert!(Signal::Burst(vec![9, 9]).strict_serialize().is_err());
+    assert!(matches!(
+        Signal::strict_decode(&[2u8, 2, 0, 9, 9][..]),
+        Err(strict_encoding::Error::DataIntegrityError(_))
+    ));
+
+    let mut target = beacon.clone();
+    target.strict_decode_into(&[2u8, 0, 1, 0, 7][..]).unwrap();
+    assert_eq!(
+        target,
+        Beacon {
+            id: 2,
+            payload: vec![7]
+        }
+    );
+    assert!(matches!(
+        target.strict_decode_into(&[1u8, 0, 5, 0, 1, 2, 3, 4, 5][..]),
+        Err(strict_encoding::Error::DataIntegrityError(_))
+    ));
+
+    assert_eq!(Bounded::STRICT_MAX_LEN, 2);
+    assert_eq!(
+        Bounded::strict_decode_variant(0, &[7u8, 0][..]).unwrap(),
+        Bounded::Short(7)
+    );
+    assert!(matches!(
+        Bounded::strict_decode_variant(1, &[1u8, 0, 9][..]),
+        Err(strict_encoding::Error::DataIntegrityError(_))
+    ));
+
+    let reading = Reading {
+        sensor: 3,
+        value: 0x0201,
+    };
+    let data = reading.strict_serialize().unwrap();
+    assert_eq!(data, vec![3u8, 1, 2, 0x26, 0x37, 0x12, 0x0A]);
+    assert_eq!(Reading::strict_deserialize(&data).unwrap(), reading);
+    // Legacy writers did not append the checksum
+    assert_eq!(Reading::strict_deserialize(&data[..3]).unwrap(), reading);
+    let mut corrupted = data.clone();
+    corrupted[1] ^= 0xFF;
+    assert!(matches!(
+        Reading::strict_deserialize(&corrupted),
+        Err(strict_encoding::Error::DataIntegrityError(_))
+    ));
+    assert!(matches!(
+        Reading::strict_deserialize(&data[..5]),
+        Err(strict_encoding::Error::DataIntegrityError(_))
+    ));
+
+    let long = LongTuple {
+        values: (1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 0x0D0E),
+    };
+    let data = long.strict_serialize().unwrap();
+    assert_eq!(
+        data,
+        vec![1u8, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 0x0E, 0x0D]
+    );
+    let decoded = LongTuple::strict_deserialize(&data).unwrap();
+    assert_eq!(decoded.values.0, 1);
+    assert_eq!(decoded.values.11, 12);
+    assert_eq!(decoded.values.12, 0x0D0E);
+
+    let number = 7u16;
+    let data = BorrowedValue::Number { x: &number }
+        .strict_serialize()
+        .unwrap();
+    assert_eq!(data, vec![0u8, 7, 0]);
+    let data = BorrowedValue::Text("hi").strict_serialize().unwrap();
+    assert_eq!(data, vec![1u8, 2, 0, b'h', b'i']);
+    let data = BorrowedValue::Bytes(&[9]).strict_serialize().unwrap();
+    assert_eq!(data, vec![2u8, 1, 0, 9]);
 }
@@ -0,0 +1,143 @@
+// LNP/BP client-side-validation library implementing respective LNPBP
+// specifications & standards (LNPBP-7, 8, 9, 42)
+//
+// Written in 2019-2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the Apache 2.0 License along with this
+// software. If not, see <https://opensource.org/licenses/Apache-2.0>.
+
+use proc_macro2::{Literal, TokenStream as TokenStream2};
+use syn::spanned::Spanned;
+use syn::{Error, Field, Result};
+
+use crate::flavor::Import;
+use crate::param::EncodingDerive;
+use crate::size::primitive_size;
+
+/// Tracks groups of consecutive `bool` fields of the structures marked with
+/// `#[strict_encoding(bitpack)]`, which are packed into bytes, eight fields
+/// per byte, with the first field of the group taking the lowest bit. The
+/// currently open byte is flushed after its eighth field, before the next
+/// encoded non-bool field and at the end of the structure.
+pub(crate) struct BitPack {
+    enabled: bool,
+    /// Number of bits taken in the currently open byte, if any
+    bits: Option<u8>,
+}
+
+impl BitPack {
+    pub(crate) fn new(enabled: bool) -> BitPack {
+        BitPack {
+            enabled,
+            bits: None,
+        }
+    }
+
+    /// Returns whether the field is packed into bits.
+    pub(crate) fn packs(
+        &self,
+        field: &Field,
+        encoding: &EncodingDerive,
+    ) -> Result<bool> {
+        if !self.enabled || primitive_size(&field.ty) != Some((1, true)) {
+            return Ok(false);
+        }
+        if encoding.since.is_some() || encoding.until.is_some() {
+            return Err(Error::new(
+                field.span(),
+                "`bitpack` can't be used with `since` or `until` bool fields",
+            ));
+        }
+        if encoding.skip_if_const.is_some() {
+            return Err(Error::new(
+                field.span(),
+                "`bitpack` can't be used with `skip_if_const` bool fields",
+            ));
+        }
+        Ok(true)
+    }
+
+    /// Adds `bool` value to the currently open byte, flushing it first if it
+    /// is full.
+    pub(crate) fn encode_bool(&mut self, value: TokenStream2) -> TokenStream2 {
+        let mut stream = if self.bits == Some(8) {
+            self.encode_flush()
+        } else {
+            TokenStream2::new()
+        };
+        let bit = match self.bits {
+            Some(bit) => bit,
+            None => {
+                stream.extend(quote! { let mut __strict_bits = 0u8; });
+                0
+            }
+        };
+        self.bits = Some(bit + 1);
+        let bit = Literal::u8_unsuffixed(bit);
+        stream.extend(quote! { __strict_bits |= (#value as u8) << #bit; });
+        stream
+    }
+
+    /// Writes the currently open byte, if any.
+    pub(crate) fn encode_flush(&mut self) -> TokenStream2 {
+        match self.bits.take() {
+            Some(_) => quote! {
+                ::std::io::Write::write_all(&mut __strict_e, &[__strict_bits])?;
+                __strict_len += 1;
+            },
+            None => TokenStream2::new(),
+        }
+    }
+
+    /// Returns statements reading the next byte, if required, and expression
+    /// extracting the `bool` value from it.
+    pub(crate) fn decode_bool(
+        &mut self,
+        import: &Import,
+    ) -> (TokenStream2, TokenStream2) {
+        let mut stream = if self.bits == Some(8) {
+            self.decode_flush(import)
+        } else {
+            TokenStream2::new()
+        };
+        let bit = match self.bits {
+            Some(bit) => bit,
+            None => {
+                let decode_trait = &import.decode_trait;
+                let decode = import.method("decode");
+                stream.extend(quote! {
+                    let __strict_bits =
+                        <u8 as #decode_trait>::#decode(&mut __strict_d)?;
+                });
+                0
+            }
+        };
+        self.bits = Some(bit + 1);
+        let bit = Literal::u8_unsuffixed(bit);
+        (stream, quote! { __strict_bits & (1 << #bit) != 0 })
+    }
+
+    /// Closes the currently open byte, if any, checking that its unused bits
+    /// are zero.
+    pub(crate) fn decode_flush(&mut self, import: &Import) -> TokenStream2 {
+        match self.bits.take() {
+            Some(bits) if bits < 8 => {
+                let bits = Literal::u8_unsuffixed(bits);
+                quote! {
+                    if __strict_bits >> #bits != 0 {
+                        return Err(#import::Error::DataIntegrityError(
+                            "non-zero unused bits in packed bool fields".to_owned(),
+                        ));
+                    }
+                }
+            }
+            _ => TokenStream2::new(),
+        }
+    }
+}
@@ -0,0 +1,290 @@
+// LNP/BP client-side-validation library implementing respective LNPBP
+// specifications & standards (LNPBP-7, 8, 9, 42)
+//
+// Written in 2019-2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the Apache 2.0 License along with this
+// software. If not, see <https://opensource.org/licenses/Apache-2.0>.
+
+use proc_macro2::{TokenStream as TokenStream2, TokenTree};
+use quote::ToTokens;
+use std::collections::HashSet;
+use syn::{
+    Attribute, Data, DeriveInput, Error, Fields, GenericArgument, Generics,
+    Ident, PathArguments, Result, Type, TypePath, WherePredicate,
+};
+
+use amplify::proc_attr::ParametrizedAttr;
+
+use crate::param::{
+    cow_borrowed, field_param, is_phantom_data, EncodingDerive,
+};
+use crate::ATTR_NAME;
+
+/// Returns generics of the type extended with `T: #bound` predicates for each
+/// type parameter `T` mentioned by the given `types`, apart from the
+/// `excluded` ones, and with `T::Assoc: #bound` predicates for the associated
+/// types of the type parameters used by these types. The `types` are the ones
+/// which the derived implementation requires to implement the bound trait,
+/// like the types of the encoded fields (see [`encoded_types`]). If `manual`
+/// predicates are provided (via `bound` attribute argument), they are used
+/// instead of the inferred ones.
+pub(crate) fn bounded_generics(
+    input: &DeriveInput,
+    bound: TokenStream2,
+    manual: Option<&Vec<WherePredicate>>,
+    excluded: &[Ident],
+    types: &[Type],
+) -> Result<Generics> {
+    let params = input
+        .generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect::<HashSet<_>>();
+    if let Some(ident) = excluded.iter().find(|ident| !params.contains(ident)) {
+        return Err(Error::new(
+            ident.span(),
+            format!("`{}` is not a type parameter of the type", ident),
+        ));
+    }
+
+    if let Some(predicates) = manual {
+        let mut generics = input.generics.clone();
+        generics
+            .make_where_clause()
+            .predicates
+            .extend(predicates.iter().cloned());
+        return Ok(generics);
+    }
+
+    let mut used = Usage::default();
+    for ty in types {
+        collect_type(ty, &params, &mut used);
+    }
+
+    let mut generics = input.generics.clone();
+    // Iterating over generics, not the set, to keep predicate order stable
+    for param in input.generics.type_params() {
+        let ident = &param.ident;
+        if used.params.contains(ident) && !excluded.contains(ident) {
+            generics
+                .make_where_clause()
+                .predicates
+                .push(parse_quote! { #ident: #bound });
+        }
+    }
+    for (ident, ty) in used.projections {
+        if !excluded.contains(&ident) {
+            generics
+                .make_where_clause()
+                .predicates
+                .push(parse_quote! { #ty: #bound });
+        }
+    }
+
+    Ok(generics)
+}
+
+/// Type parameters and associated type projections (like `T::Assoc` or
+/// `<T as Trait>::Assoc`) used by the encoded fields. Projections are bounded
+/// on their own, without requiring their type parameter to be encodable.
+#[derive(Default)]
+struct Usage {
+    params: HashSet<Ident>,
+    /// Projections in the order of their first use, with the type parameter
+    /// they are based on
+    projections: Vec<(Ident, Type)>,
+}
+
+impl Usage {
+    fn add_projection(&mut self, ident: &Ident, ty: &Type) {
+        let repr = ty.to_token_stream().to_string();
+        if !self
+            .projections
+            .iter()
+            .any(|(_, known)| known.to_token_stream().to_string() == repr)
+        {
+            self.projections.push((ident.clone(), ty.clone()));
+        }
+    }
+}
+
+/// Returns type parameter on which the path type is projected: `T` for both
+/// `T::Assoc` and `<T as Trait>::Assoc`.
+fn projection_base<'ty>(
+    path: &'ty TypePath,
+    params: &HashSet<Ident>,
+) -> Option<&'ty Ident> {
+    let base = match path.qself {
+        Some(ref qself) => match *qself.ty {
+            Type::Path(ref base) if base.qself.is_none() => {
+                base.path.get_ident()?
+            }
+            _ => return None,
+        },
+        None if path.path.segments.len() > 1
+            && path.path.leading_colon.is_none() =>
+        {
+            let first = path.path.segments.first()?;
+            if !first.arguments.is_empty() {
+                return None;
+            }
+            &first.ident
+        }
+        None => return None,
+    };
+    Some(base).filter(|ident| params.contains(ident))
+}
+
+/// Returns types which must implement `StrictEncode` for the derived
+/// implementation: the types of non-skipped fields, with `Cow<'a, B>` fields
+/// represented by the borrowed type `B` they are encoded from.
+pub(crate) fn encoded_types(input: &DeriveInput) -> Result<Vec<Type>> {
+    Ok(strict_field_types(input)?
+        .into_iter()
+        .map(|ty| match cow_borrowed(&ty) {
+            Some(borrowed) => borrowed.clone(),
+            None => ty,
+        })
+        .collect())
+}
+
+/// Returns types which must implement `StrictDecode` for the derived
+/// implementation: the types of non-skipped fields, with `Cow<'a, B>` fields
+/// represented by the owned type `B::Owned` they are decoded into.
+pub(crate) fn decoded_types(input: &DeriveInput) -> Result<Vec<Type>> {
+    Ok(strict_field_types(input)?
+        .into_iter()
+        .map(|ty| match cow_borrowed(&ty) {
+            Some(borrowed) => parse_quote! {
+                <#borrowed as ::std::borrow::ToOwned>::Owned
+            },
+            None => ty,
+        })
+        .collect())
+}
+
+/// Returns types of the non-skipped structure fields or of the fields of the
+/// non-skipped enum variants.
+fn strict_field_types(input: &DeriveInput) -> Result<Vec<Type>> {
+    let mut types = vec![];
+    match input.data {
+        Data::Struct(ref data) => {
+            collect_fields(&data.fields, false, &mut types)?
+        }
+        Data::Enum(ref data) => {
+            for variant in &data.variants {
+                if is_skipped(&variant.attrs, true)? {
+                    continue;
+                }
+                collect_fields(&variant.fields, true, &mut types)?;
+            }
+        }
+        Data::Union(ref data) => collect_fields(
+            &Fields::Named(data.fields.clone()),
+            true,
+            &mut types,
+        )?,
+    }
+    Ok(types)
+}
+
+fn is_skipped(attrs: &[Attribute], is_enum: bool) -> Result<bool> {
+    let mut param = ParametrizedAttr::with(ATTR_NAME, attrs)?;
+    Ok(EncodingDerive::try_from(&mut param, false, is_enum)?.skip)
+}
+
+fn collect_fields(
+    fields: &Fields,
+    is_enum: bool,
+    types: &mut Vec<Type>,
+) -> Result<()> {
+    for field in fields.iter() {
+        let mut param = field_param(field)?;
+        if EncodingDerive::try_from(&mut param, false, is_enum)?.skip {
+            continue;
+        }
+        types.push(field.ty.clone());
+    }
+    Ok(())
+}
+
+/// Collects type parameters and their associated type projections mentioned by
+/// the type, ignoring the ones used only inside `PhantomData`, which is encoded
+/// to nothing.
+fn collect_type(ty: &Type, params: &HashSet<Ident>, used: &mut Usage) {
+    match ty {
+        _ if is_phantom_data(ty) => {}
+        Type::Path(path) => {
+            if let Some(ident) = projection_base(path, params) {
+                used.add_projection(ident, ty);
+                return;
+            }
+            if let Some(ref qself) = path.qself {
+                collect_type(&qself.ty, params, used);
+            }
+            for segment in &path.path.segments {
+                if params.contains(&segment.ident) {
+                    used.params.insert(segment.ident.clone());
+                }
+                match segment.arguments {
+                    PathArguments::None => {}
+                    PathArguments::AngleBracketed(ref args) => {
+                        for arg in &args.args {
+                            match arg {
+                                GenericArgument::Type(ty) => {
+                                    collect_type(ty, params, used)
+                                }
+                                other => collect_tokens(
+                                    other.to_token_stream(),
+                                    params,
+                                    used,
+                                ),
+                            }
+                        }
+                    }
+                    PathArguments::Parenthesized(ref args) => {
+                        collect_tokens(args.to_token_stream(), params, used)
+                    }
+                }
+            }
+        }
+        Type::Array(array) => collect_type(&array.elem, params, used),
+        Type::Group(group) => collect_type(&group.elem, params, used),
+        Type::Paren(paren) => collect_type(&paren.elem, params, used),
+        Type::Reference(reference) => {
+            collect_type(&reference.elem, params, used)
+        }
+        Type::Slice(slice) => collect_type(&slice.elem, params, used),
+        Type::Tuple(tuple) => {
+            for elem in &tuple.elems {
+                collect_type(elem, params, used);
+            }
+        }
+        _ => collect_tokens(ty.to_token_stream(), params, used),
+    }
+}
+
+fn collect_tokens(
+    tokens: impl IntoIterator<Item = TokenTree>,
+    params: &HashSet<Ident>,
+    used: &mut Usage,
+) {
+    for token in tokens {
+        match token {
+            TokenTree::Ident(ident) if params.contains(&ident) => {
+                used.params.insert(ident);
+            }
+            TokenTree::Group(group) => {
+                collect_tokens(group.stream(), params, used)
+            }
+            _ => {}
+        }
+    }
+}
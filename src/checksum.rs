@@ -0,0 +1,74 @@
+// LNP/BP client-side-validation library implementing respective LNPBP
+// specifications & standards (LNPBP-7, 8, 9, 42)
+//
+// Written in 2019-2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the Apache 2.0 License along with this
+// software. If not, see <https://opensource.org/licenses/Apache-2.0>.
+
+use proc_macro2::TokenStream as TokenStream2;
+
+/// Checksum algorithms supported by `checksum` attribute argument.
+pub(crate) const CHECKSUMS: &[&str] = &["crc32"];
+
+/// Returns definition of `__StrictCrc32` adapter type, which computes CRC32
+/// (IEEE 802.3) checksum over all data passing through the wrapped writer or
+/// reader. Since proc macro crates can't export regular items, the adapter is
+/// generated inside the body of the function using it.
+pub(crate) fn crc32_adapter() -> TokenStream2 {
+    quote! {
+        struct __StrictCrc32<T> {
+            inner: T,
+            crc: u32,
+        }
+
+        impl<T> __StrictCrc32<T> {
+            fn new(inner: T) -> Self {
+                __StrictCrc32 { inner, crc: !0 }
+            }
+
+            fn update(&mut self, data: &[u8]) {
+                for byte in data {
+                    self.crc ^= *byte as u32;
+                    for _ in 0..8 {
+                        self.crc = if self.crc & 1 == 1 {
+                            (self.crc >> 1) ^ 0xEDB8_8320
+                        } else {
+                            self.crc >> 1
+                        };
+                    }
+                }
+            }
+
+            fn checksum(&self) -> u32 {
+                !self.crc
+            }
+        }
+
+        impl<T: ::std::io::Write> ::std::io::Write for __StrictCrc32<T> {
+            fn write(&mut self, buf: &[u8]) -> ::std::io::Result<usize> {
+                let len = self.inner.write(buf)?;
+                self.update(&buf[..len]);
+                Ok(len)
+            }
+
+            fn flush(&mut self) -> ::std::io::Result<()> {
+                self.inner.flush()
+            }
+        }
+
+        impl<T: ::std::io::Read> ::std::io::Read for __StrictCrc32<T> {
+            fn read(&mut self, buf: &mut [u8]) -> ::std::io::Result<usize> {
+                let len = self.inner.read(buf)?;
+                self.update(&buf[..len]);
+                Ok(len)
+            }
+        }
+    }
+}
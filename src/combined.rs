@@ -0,0 +1,58 @@
+// LNP/BP client-side-validation library implementing respective LNPBP
+// specifications & standards (LNPBP-7, 8, 9, 42)
+//
+// Written in 2019-2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the Apache 2.0 License along with this
+// software. If not, see <https://opensource.org/licenses/Apache-2.0>.
+
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::{Data, DeriveInput, Error, Result};
+
+use amplify::proc_attr::ParametrizedAttr;
+
+use crate::decode::decode_derive;
+use crate::encode::encode_derive;
+use crate::flavor::STRICT;
+use crate::param::EncodingDerive;
+use crate::ATTR_NAME;
+
+/// Derives both `StrictEncode` and `StrictDecode` implementations from the
+/// same `#[strict_encoding]` attributes, unless one of them is disabled with
+/// `encode_only` or `decode_only` argument.
+pub(crate) fn combined_derive(input: DeriveInput) -> Result<TokenStream2> {
+    let mut global_param = ParametrizedAttr::with(ATTR_NAME, &input.attrs)?;
+    let is_enum = matches!(input.data, Data::Enum(_) | Data::Union(_));
+    let encoding = EncodingDerive::try_from(&mut global_param, true, is_enum)?;
+
+    let mut tokens = TokenStream2::new();
+    if !encoding.decode_only {
+        tokens.extend(encode_derive(input.clone(), &STRICT)?);
+    }
+    if !encoding.encode_only {
+        tokens.extend(decode_derive(input, &STRICT)?);
+    }
+    Ok(tokens)
+}
+
+/// Fails if `encode_only` or `decode_only` argument, which selects the
+/// implementations generated by `StrictEncoding` derive, is given to separate
+/// `StrictEncode` or `StrictDecode` derive, where it would have no effect.
+pub(crate) fn separate_derive_check(input: DeriveInput) -> Result<DeriveInput> {
+    let mut global_param = ParametrizedAttr::with(ATTR_NAME, &input.attrs)?;
+    let is_enum = matches!(input.data, Data::Enum(_) | Data::Union(_));
+    let encoding = EncodingDerive::try_from(&mut global_param, true, is_enum)?;
+    if encoding.encode_only || encoding.decode_only {
+        return Err(Error::new(
+            Span::call_site(),
+            "`encode_only` and `decode_only` arguments can be used only with `StrictEncoding` derive",
+        ));
+    }
+    Ok(input)
+}
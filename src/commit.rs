@@ -0,0 +1,185 @@
+// LNP/BP client-side-validation library implementing respective LNPBP
+// specifications & standards (LNPBP-7, 8, 9, 42)
+//
+// Written in 2019-2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the Apache 2.0 License along with this
+// software. If not, see <https://opensource.org/licenses/Apache-2.0>.
+
+use std::convert::TryInto;
+
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Error, Field, Ident, Index, Path, Result, Type};
+
+use amplify::proc_attr::{
+    ArgValue, ArgValueReq, AttrReq, ParametrizedAttr, TypeClass, ValueClass,
+};
+
+use crate::bound::bounded_generics;
+use crate::encode::field_ref;
+
+/// Name of the attribute configuring commitment encoding derivation.
+const COMMIT_ATTR_NAME: &str = "commit_encoding";
+
+/// Hash functions of `bitcoin_hashes` crate which can be used with `hash`
+/// field argument.
+const COMMIT_HASHES: &[&str] = &[
+    "sha256",
+    "sha256d",
+    "sha512",
+    "hash160",
+    "ripemd160",
+    "sha1",
+];
+
+pub(crate) fn commit_derive(input: DeriveInput) -> Result<TokenStream2> {
+    let ident_name = &input.ident;
+
+    let mut global_param =
+        ParametrizedAttr::with(COMMIT_ATTR_NAME, &input.attrs)?;
+    global_param.check(AttrReq::with(map! {
+        "crate" => ArgValueReq::with_default(ident!(commit_verify)),
+        "strict_crate" => ArgValueReq::with_default(ident!(strict_encoding))
+    }))?;
+    let import = crate_arg(&global_param, "crate", ident!(commit_verify));
+    let strict_import =
+        crate_arg(&global_param, "strict_crate", ident!(strict_encoding));
+
+    let data = match input.data {
+        Data::Struct(ref data) => data,
+        _ => {
+            return Err(Error::new(
+                Span::call_site(),
+                "`CommitEncode` can be derived for structures only",
+            ))
+        }
+    };
+
+    let mut inner_impl = TokenStream2::new();
+    let mut committed = vec![];
+    for (index, field) in data.fields.iter().enumerate() {
+        let name = field
+            .ident
+            .as_ref()
+            .map(|ident| quote! { #ident })
+            .unwrap_or_else(|| {
+                let index = Index::from(index);
+                quote! { #index }
+            });
+        inner_impl.extend(commit_field(
+            field,
+            name,
+            &strict_import,
+            &mut committed,
+        )?);
+    }
+
+    let generics = bounded_generics(
+        &input,
+        quote! { #strict_import::StrictEncode },
+        None,
+        &[],
+        &committed,
+    )?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        #[allow(clippy::all, unused_qualifications, deprecated)]
+        impl #impl_generics #import::CommitEncode for #ident_name #ty_generics #where_clause {
+            fn commit_encode<E: ::std::io::Write>(&self, mut __strict_e: E) -> usize {
+                let mut __strict_len = 0usize;
+                #inner_impl
+                __strict_len
+            }
+        }
+    })
+}
+
+/// Generates commitment encoding of a single field according to its
+/// `#[commit_encoding]` attribute. Type of the field is added to `committed`
+/// if the field value is strict encoded: skipped fields are not encoded, and
+/// concealed fields encode the value returned by their `conceal` method, which
+/// type is checked by the method call itself.
+fn commit_field(
+    field: &Field,
+    name: TokenStream2,
+    strict_import: &Path,
+    committed: &mut Vec<Type>,
+) -> Result<TokenStream2> {
+    let mut param = ParametrizedAttr::with(COMMIT_ATTR_NAME, &field.attrs)?;
+    param.check(AttrReq::with(map! {
+        "skip" => ArgValueReq::Prohibited,
+        "conceal" => ArgValueReq::Prohibited,
+        "hash" => ArgValueReq::Optional(ValueClass::Type(TypeClass::Path))
+    }))?;
+    if param.args.contains_key("skip") {
+        if param.args.len() > 1 {
+            return Err(Error::new(
+                field.span(),
+                "skipped field can't have other `commit_encoding` arguments",
+            ));
+        }
+        return Ok(TokenStream2::new());
+    }
+
+    let value = if param.args.contains_key("conceal") {
+        quote! { &self.#name.conceal() }
+    } else {
+        committed.push(field.ty.clone());
+        field_ref(&field.ty, quote! { self.#name }, false)
+    };
+
+    let hash = match param.args.get("hash").cloned() {
+        None => {
+            return Ok(quote_spanned! { field.span() =>
+                __strict_len += #strict_import::StrictEncode::strict_encode(#value, &mut __strict_e)
+                    .expect("strict encoding of commitment data must not fail");
+            })
+        }
+        Some(arg) => hash_arg(arg, field)?,
+    };
+    Ok(quote_spanned! { field.span() =>
+        {
+            let __strict_data = #strict_import::StrictEncode::strict_serialize(#value)
+                .expect("strict encoding of commitment data must not fail");
+            let __strict_hash = ::bitcoin_hashes::Hash::into_inner(
+                <::bitcoin_hashes::#hash::Hash as ::bitcoin_hashes::Hash>::hash(&__strict_data),
+            );
+            ::std::io::Write::write_all(&mut __strict_e, &__strict_hash)
+                .expect("commitment writer must not fail");
+            __strict_len += __strict_hash.len();
+        }
+    })
+}
+
+fn crate_arg(param: &ParametrizedAttr, name: &str, default: Ident) -> Path {
+    param
+        .args
+        .get(name)
+        .cloned()
+        .unwrap_or_else(|| ArgValue::from(default))
+        .try_into()
+        .expect("amplify_syn is broken: requirements for crate arg are not satisfied")
+}
+
+/// Parses name of the hash function given with `hash` field argument.
+fn hash_arg(arg: ArgValue, field: &Field) -> Result<Ident> {
+    let hash: Option<Ident> = arg.try_into().ok();
+    match hash {
+        Some(hash) if COMMIT_HASHES.contains(&&*hash.to_string()) => Ok(hash),
+        _ => Err(Error::new(
+            field.span(),
+            format!(
+                "`hash` argument must be one of {}",
+                COMMIT_HASHES.join(", ")
+            ),
+        )),
+    }
+}
@@ -12,97 +12,709 @@
 // You should have received a copy of the Apache 2.0 License along with this
 // software. If not, see <https://opensource.org/licenses/Apache-2.0>.
 
-use proc_macro2::{Span, TokenStream as TokenStream2};
+use proc_macro2::{Literal, Span, TokenStream as TokenStream2};
 use quote::{ToTokens, TokenStreamExt};
+use syn::ext::IdentExt;
 use syn::spanned::Spanned;
 use syn::{
-    Data, DataEnum, DataStruct, DeriveInput, Error, Field, Fields, Ident,
-    ImplGenerics, Index, LitStr, Result, TypeGenerics, WhereClause,
+    Data, DataEnum, DataStruct, DataUnion, DeriveInput, Error, Expr, Field,
+    Fields, Generics, Ident, ImplGenerics, Index, LitStr, Path, Result, Type,
+    TypeGenerics, WhereClause,
 };
 
 use amplify::proc_attr::ParametrizedAttr;
 
-use crate::param::EncodingDerive;
-use crate::ATTR_NAME;
+use crate::bitpack::BitPack;
+use crate::bound::{bounded_generics, decoded_types};
+use crate::checksum::crc32_adapter;
+use crate::flavor::{Flavor, Import};
+use crate::layout::{c_layout_check, c_layout_padding};
+use crate::limit::decode_limited;
+use crate::param::{
+    container_max_len, count_type_check, cow_borrowed, elementwise_array,
+    field_param, field_prefix_lit, field_prefix_repr, is_btree_map,
+    is_infallible, is_result, is_std_collection, optional_tail_start,
+    order_skew_note, transparent_field, tuple_elems, union_fields,
+    via_deref_check, wire_order, EncodingDerive, OrderSide,
+};
+use crate::size::primitive_size;
+use crate::type_tag::{decode_type_tag, type_tag};
+use crate::{skipped_enum_warning, variant_count_check, ATTR_NAME};
 
-pub(crate) fn decode_derive(input: DeriveInput) -> Result<TokenStream2> {
-    let (impl_generics, ty_generics, where_clause) =
-        input.generics.split_for_impl();
+pub(crate) fn decode_derive(
+    input: DeriveInput,
+    flavor: &Flavor,
+) -> Result<TokenStream2> {
+    let input = flavor.input(input);
     let ident_name = &input.ident;
 
     let global_param = ParametrizedAttr::with(ATTR_NAME, &input.attrs)?;
 
-    match input.data {
-        Data::Struct(data) => decode_struct_impl(
-            data,
+    let is_enum = matches!(input.data, Data::Enum(_) | Data::Union(_));
+    let encoding =
+        EncodingDerive::try_from(&mut global_param.clone(), true, is_enum)?;
+    let import = flavor.import(encoding.use_crate.as_ref());
+    let decode_trait = &import.decode_trait;
+    if encoding.c_layout {
+        c_layout_check(&input)?;
+    }
+    let tag = type_tag(&input, &encoding);
+    let inline = encoding.inline_attr();
+    // Enums without tag and structures with length suffix do not implement
+    // `StrictDecode`
+    let deserialize_impl = if encoding.helpers
+        && !encoding.no_tag
+        && encoding.length_suffix.is_none()
+    {
+        decode_deserialize_impl(
+            &input,
+            &import,
+            encoding.error_type.as_ref(),
+            &encoding.helper_vis(),
+        )
+    } else {
+        TokenStream2::new()
+    };
+    let error_impl = match encoding.error_type {
+        Some(ref error_type) => decode_error_impl(
+            &input,
+            &import,
+            error_type,
+            &encoding.helper_vis(),
+        ),
+        None => TokenStream2::new(),
+    };
+    let helpers = encoding.scoped(quote! {
+        #deserialize_impl
+        #error_impl
+    });
+    let conversion_impl = if encoding.conversions {
+        decode_conversion_impl(&input, &import)
+    } else {
+        TokenStream2::new()
+    };
+    if let Some(surrogate) = encoding.try_from {
+        let (impl_generics, ty_generics, where_clause) =
+            input.generics.split_for_impl();
+        let decode_impl = decode_surrogate_impl(
+            surrogate,
             ident_name,
-            global_param,
+            &import,
+            inline,
             impl_generics,
             ty_generics,
             where_clause,
+        )?;
+        return Ok(quote! {
+            #decode_impl
+            #conversion_impl
+            #helpers
+        });
+    }
+
+    let generics = bounded_generics(
+        &input,
+        quote! { #decode_trait },
+        encoding.decode_bound.as_ref(),
+        &encoding.decode_no_bound,
+        &decoded_types(&input)?,
+    )?;
+
+    let decode_impl = match input.data {
+        Data::Struct(data) => decode_struct_impl(
+            data,
+            ident_name,
+            &import,
+            global_param,
+            &generics,
+            tag,
         ),
         Data::Enum(data) => decode_enum_impl(
             data,
             ident_name,
+            &import,
             global_param,
-            impl_generics,
-            ty_generics,
-            where_clause,
+            &generics,
+            tag,
         ),
         //strict_encode_inner_enum(&input, &data),
-        Data::Union(_) => Err(Error::new_spanned(
-            &input,
-            "Deriving StrictDecode is not supported in unions",
-        )),
+        Data::Union(data) => decode_union_impl(
+            data,
+            ident_name,
+            &import,
+            global_param,
+            &generics,
+            tag,
+        ),
+    }?;
+
+    Ok(quote! {
+        #decode_impl
+        #conversion_impl
+        #helpers
+    })
+}
+
+fn decode_deserialize_impl(
+    input: &DeriveInput,
+    import: &Import,
+    error_type: Option<&Type>,
+    vis: &TokenStream2,
+) -> TokenStream2 {
+    let decode_trait = &import.decode_trait;
+    let deserialize = import.method("deserialize");
+    let ident_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+    let (error, map_err) = match error_type {
+        Some(ty) => (ty.to_token_stream(), error_conversion(ty, import)),
+        None => (quote! { #import::Error }, TokenStream2::new()),
+    };
+
+    quote! {
+        #[allow(clippy::all, unused_qualifications)]
+        impl #impl_generics #ident_name #ty_generics #where_clause {
+            /// Deserializes the value from its strict encoding, failing with
+            /// `Error::DataNotEntirelyConsumed` if the data are not entirely
+            /// consumed.
+            #vis fn #deserialize(data: impl AsRef<[u8]>) -> Result<Self, #error>
+            where
+                Self: #decode_trait,
+            {
+                #import::#deserialize(data) #map_err
+            }
+        }
     }
 }
 
-fn decode_struct_impl(
-    data: DataStruct,
+/// Generates `TryFrom<&[u8]>` conversion deserializing the value and failing
+/// if the data are not entirely consumed.
+fn decode_conversion_impl(
+    input: &DeriveInput,
+    import: &Import,
+) -> TokenStream2 {
+    let decode_trait = &import.decode_trait;
+    let deserialize = import.method("deserialize");
+    let ident_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+    let mut where_clause =
+        where_clause.cloned().unwrap_or_else(|| WhereClause {
+            where_token: Default::default(),
+            predicates: Default::default(),
+        });
+    where_clause
+        .predicates
+        .push(parse_quote! { #ident_name #ty_generics: #decode_trait });
+
+    quote! {
+        #[allow(clippy::all, unused_qualifications)]
+        impl #impl_generics ::core::convert::TryFrom<&[u8]> for #ident_name #ty_generics #where_clause {
+            type Error = #import::Error;
+
+            fn try_from(data: &[u8]) -> Result<Self, Self::Error> {
+                #import::#deserialize(data)
+            }
+        }
+    }
+}
+
+/// Generates inherent `strict_decode` method returning the custom error type,
+/// which takes precedence over `StrictDecode::strict_decode` when called as
+/// `Type::strict_decode`.
+fn decode_error_impl(
+    input: &DeriveInput,
+    import: &Import,
+    error_type: &Type,
+    vis: &TokenStream2,
+) -> TokenStream2 {
+    let decode_trait = &import.decode_trait;
+    let decode = import.method("decode");
+    let ident_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+    let map_err = error_conversion(error_type, import);
+
+    quote! {
+        #[allow(clippy::all, unused_qualifications)]
+        impl #impl_generics #ident_name #ty_generics #where_clause {
+            /// Decodes the value with its `StrictDecode` implementation,
+            /// converting decoding errors into the custom error type.
+            #vis fn #decode<D: ::std::io::Read>(d: D) -> Result<Self, #error_type>
+            where
+                Self: #decode_trait,
+            {
+                <Self as #decode_trait>::#decode(d) #map_err
+            }
+        }
+    }
+}
+
+/// Converts decoding error into the custom error type, failing to compile
+/// (pointing at the type) if the type doesn't implement `From<Error>`.
+fn error_conversion(error_type: &Type, import: &Import) -> TokenStream2 {
+    quote_spanned! { error_type.span() =>
+        .map_err(<#error_type as ::core::convert::From<#import::Error>>::from)
+    }
+}
+
+fn decode_surrogate_impl(
+    surrogate: Type,
     ident_name: &Ident,
-    mut global_param: ParametrizedAttr,
+    import: &Import,
+    inline: TokenStream2,
     impl_generics: ImplGenerics,
     ty_generics: TypeGenerics,
     where_clause: Option<&WhereClause>,
 ) -> Result<TokenStream2> {
+    let decode_trait = &import.decode_trait;
+    let decode = import.method("decode");
+    let mut where_clause =
+        where_clause.cloned().unwrap_or_else(|| WhereClause {
+            where_token: Default::default(),
+            predicates: Default::default(),
+        });
+    where_clause
+        .predicates
+        .push(parse_quote! { #surrogate: #decode_trait });
+    where_clause.predicates.push(parse_quote! {
+        Self: ::core::convert::TryFrom<#surrogate>
+    });
+    where_clause.predicates.push(parse_quote! {
+        <Self as ::core::convert::TryFrom<#surrogate>>::Error: ::core::fmt::Display
+    });
+
+    Ok(quote! {
+        #[allow(clippy::all, unused_qualifications)]
+        impl #impl_generics #decode_trait for #ident_name #ty_generics #where_clause {
+            #inline
+            fn #decode<D: ::std::io::Read>(__strict_d: D) -> Result<Self, #import::Error> {
+                let surrogate = <#surrogate as #decode_trait>::#decode(__strict_d)?;
+                <Self as ::core::convert::TryFrom<#surrogate>>::try_from(surrogate)
+                    .map_err(|err| #import::Error::DataIntegrityError(err.to_string()))
+            }
+        }
+    })
+}
+
+fn decode_struct_impl(
+    data: DataStruct,
+    ident_name: &Ident,
+    import: &Import,
+    mut global_param: ParametrizedAttr,
+    generics: &Generics,
+    type_tag: Option<TokenStream2>,
+) -> Result<TokenStream2> {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let decode_trait = &import.decode_trait;
+    let decode = import.method("decode");
+    let decode_into = import.method("decode_into");
+    let decode_from_slice = import.method("decode_from_slice");
     let encoding = EncodingDerive::try_from(&mut global_param, true, false)?;
+    uninhabited_check(&data.fields)?;
+    let inline = encoding.inline_attr();
+    let mut header_impl = TokenStream2::new();
+    if let Some(block_size) = encoding.pad_to {
+        header_impl.append_all(quote! {
+            let mut __strict_d = ::std::io::Read::take(__strict_d, #block_size);
+        });
+    }
+    // Aligned data are read through an unlimited `Take` as well, counting the
+    // consumed bytes
+    if encoding.align.is_some() {
+        header_impl.append_all(quote! {
+            let mut __strict_d = ::std::io::Read::take(__strict_d, u64::MAX);
+        });
+    }
+    // Type tag is covered by padding, but not by checksum
+    header_impl.append_all(decode_type_tag(type_tag, import));
+    if encoding.checksum.is_some() {
+        let adapter = crc32_adapter();
+        header_impl.append_all(quote! {
+            #adapter
+            let mut __strict_d = __StrictCrc32::new(__strict_d);
+        });
+    }
+    if let Some(magic) = encoding.magic {
+        header_impl.append_all(decode_magic(magic, ident_name, import));
+    }
+    if let Some(version) = encoding.version {
+        header_impl.append_all(decode_version(
+            version,
+            &encoding.version_repr,
+            ident_name,
+            import,
+        ));
+    }
+    let mut trailer_impl = TokenStream2::new();
+    // Trailing bytes are discarded before the length prefix boundary check,
+    // such that they may be used for extending length-prefixed records
+    if let Some(max) = encoding.ignore_trailing {
+        trailer_impl.append_all(quote! {
+            ::std::io::copy(
+                &mut ::std::io::Read::take(&mut __strict_d, #max),
+                &mut ::std::io::sink(),
+            )?;
+        });
+    } else if encoding.ignore_trailing_all {
+        trailer_impl.append_all(quote! {
+            ::std::io::copy(&mut __strict_d, &mut ::std::io::sink())?;
+        });
+    } else if encoding.exact {
+        trailer_impl.append_all(quote! {
+            match ::std::io::Read::read_exact(&mut __strict_d, &mut [0u8; 1]) {
+                Ok(()) => return Err(#import::Error::DataNotEntirelyConsumed),
+                Err(err) if err.kind() == ::std::io::ErrorKind::UnexpectedEof => {}
+                Err(err) => return Err(err.into()),
+            }
+        });
+    }
+    if let Some(width) = encoding.length_prefixed {
+        header_impl.append_all(quote! {
+            let __strict_body_len = <#width as #decode_trait>::#decode(&mut __strict_d)?;
+            let mut __strict_d = ::std::io::Read::take(__strict_d, __strict_body_len as u64);
+        });
+        trailer_impl.append_all(quote! {
+            if __strict_d.limit() != 0 {
+                return Err(#import::Error::DataNotEntirelyConsumed);
+            }
+        });
+        if encoding.checksum.is_some()
+            || encoding.pad_to.is_some()
+            || encoding.align.is_some()
+        {
+            trailer_impl.append_all(quote! {
+                let mut __strict_d = __strict_d.into_inner();
+            });
+        }
+    }
+    if encoding.checksum.is_some() && encoding.optional_checksum {
+        let type_name =
+            LitStr::new(&ident_name.unraw().to_string(), ident_name.span());
+        // Data written without the checksum end right after the fields
+        trailer_impl.append_all(quote! {
+            let __strict_actual = __strict_d.checksum();
+            let mut __strict_rest = Vec::new();
+            ::std::io::Read::read_to_end(&mut __strict_d.inner, &mut __strict_rest)?;
+            if !__strict_rest.is_empty() {
+                if __strict_rest.len() != 4 {
+                    return Err(#import::Error::DataIntegrityError(format!(
+                        "{} trailing bytes after {} data do not form a checksum",
+                        __strict_rest.len(), #type_name,
+                    )));
+                }
+                let __strict_expected = <u32 as #decode_trait>::#decode(&__strict_rest[..])?;
+                if __strict_actual != __strict_expected {
+                    return Err(#import::Error::DataIntegrityError(format!(
+                        "checksum mismatch for {}: expected {:08x}, got {:08x}",
+                        #type_name, __strict_expected, __strict_actual,
+                    )));
+                }
+            }
+        });
+    } else if encoding.checksum.is_some() {
+        let type_name =
+            LitStr::new(&ident_name.unraw().to_string(), ident_name.span());
+        trailer_impl.append_all(quote! {
+            let __strict_actual = __strict_d.checksum();
+            let __strict_expected = <u32 as #decode_trait>::#decode(&mut __strict_d.inner)?;
+            if __strict_actual != __strict_expected {
+                return Err(#import::Error::DataIntegrityError(format!(
+                    "checksum mismatch for {}: expected {:08x}, got {:08x}",
+                    #type_name, __strict_expected, __strict_actual,
+                )));
+            }
+        });
+        if encoding.pad_to.is_some() || encoding.align.is_some() {
+            trailer_impl.append_all(quote! {
+                let mut __strict_d = __strict_d.inner;
+            });
+        }
+    }
+    if encoding.pad_to.is_some() {
+        let type_name =
+            LitStr::new(&ident_name.unraw().to_string(), ident_name.span());
+        trailer_impl.append_all(quote! {
+            let mut __strict_padding = vec![0u8; __strict_d.limit() as usize];
+            ::std::io::Read::read_exact(&mut __strict_d, &mut __strict_padding)?;
+            if __strict_padding.iter().any(|byte| *byte != 0) {
+                return Err(#import::Error::DataIntegrityError(format!(
+                    "non-zero padding bytes in {}",
+                    #type_name,
+                )));
+            }
+        });
+    }
+    if let Some(align) = encoding.align {
+        let type_name =
+            LitStr::new(&ident_name.unraw().to_string(), ident_name.span());
+        trailer_impl.append_all(quote! {
+            let __strict_read = u64::MAX - __strict_d.limit();
+            let mut __strict_padding =
+                vec![0u8; ((#align - __strict_read % #align) % #align) as usize];
+            ::std::io::Read::read_exact(&mut __strict_d, &mut __strict_padding)?;
+            if __strict_padding.iter().any(|byte| *byte != 0) {
+                return Err(#import::Error::DataIntegrityError(format!(
+                    "non-zero padding bytes in {}",
+                    #type_name,
+                )));
+            }
+        });
+    }
+    let tail_start = optional_tail_start(&data.fields, encoding.optional_tail)?;
+    if tail_start.is_some()
+        && (encoding.checksum.is_some()
+            || encoding.pad_to.is_some()
+            || encoding.align.is_some())
+    {
+        return Err(Error::new(
+            Span::call_site(),
+            "`checksum`, `pad_to` and `align` can't be used with optional tail fields",
+        ));
+    }
+    if tail_start.is_some() && encoding.field_prefix.is_some() {
+        return Err(Error::new(
+            Span::call_site(),
+            "`field_prefix` can't be used with optional tail fields",
+        ));
+    }
+    if tail_start.is_some() && encoding.bitpack {
+        return Err(Error::new(
+            Span::call_site(),
+            "`bitpack` can't be used with optional tail fields",
+        ));
+    }
+    if tail_start.is_some() {
+        header_impl.append_all(quote! {
+            let mut __strict_tail_eof = false;
+        });
+    }
 
-    let inner_impl = match data.fields {
-        Fields::Named(ref fields) => {
-            decode_fields_impl(&fields.named, global_param, false)?
+    let decode_into_impl = if encoding.decode_into {
+        let inner_impl = decode_into_fields_impl(
+            &data.fields,
+            global_param.clone(),
+            import,
+            encoding.version,
+            tail_start,
+            encoding.bitpack,
+        )?;
+        let body = decode_limited(
+            quote! {
+                #header_impl
+                #inner_impl
+                #trailer_impl
+                Ok(())
+            },
+            quote! { () },
+            encoding.max_encoded_len,
+            ident_name,
+            import,
+        );
+        quote! {
+            #[allow(clippy::all, unused_qualifications, unreachable_code)]
+            impl #impl_generics #ident_name #ty_generics #where_clause {
+                /// Decodes data into the existing value in place, reusing
+                /// its allocations. Skipped fields are left untouched.
+                #inline
+                pub fn #decode_into<D: ::std::io::Read>(&mut self, mut __strict_d: D) -> Result<(), #import::Error> {
+                    #body
+                }
+            }
         }
-        Fields::Unnamed(ref fields) => {
-            decode_fields_impl(&fields.unnamed, global_param, false)?
+    } else {
+        TokenStream2::new()
+    };
+
+    if encoding.transparent {
+        if encoding.via_deref {
+            via_deref_check(&data.fields)?;
         }
-        Fields::Unit => quote! {},
+        let (inner, _) = transparent_field(&data.fields)?;
+        let decoder = if encoding.via_deref {
+            quote! {
+                <<Self as ::core::ops::Deref>::Target as #decode_trait>::#decode
+            }
+        } else {
+            quote! { #decode_trait::#decode }
+        };
+        let mut inner_impl = TokenStream2::new();
+        for (index, field) in data.fields.iter().enumerate() {
+            let name = field
+                .ident
+                .as_ref()
+                .map(Ident::to_token_stream)
+                .unwrap_or_else(|| Index::from(index).to_token_stream());
+            if index == inner {
+                inner_impl.append_all(quote_spanned! { field.span() =>
+                    #name: #decoder(__strict_d)?,
+                });
+            } else {
+                inner_impl.append_all(quote_spanned! { field.span() =>
+                    #name: Default::default(),
+                });
+            }
+        }
+
+        return Ok(quote! {
+            #[allow(clippy::all, unused_qualifications, unreachable_code)]
+            impl #impl_generics #decode_trait for #ident_name #ty_generics #where_clause {
+                #inline
+                fn #decode<D: ::std::io::Read>(__strict_d: D) -> Result<Self, #import::Error> {
+                    Ok(#ident_name { #inner_impl })
+                }
+            }
+
+            #decode_into_impl
+        });
+    }
+
+    if let Some(constructor) = encoding.construct_with {
+        let inner_impl = decode_constructor_fields_impl(
+            &data.fields,
+            global_param,
+            constructor,
+            import,
+            encoding.version,
+            tail_start,
+            encoding.bitpack,
+        )?;
+
+        let body = decode_limited(
+            quote! {
+                #header_impl
+                let decoded = { #inner_impl }?;
+                #trailer_impl
+                Ok(decoded)
+            },
+            quote! { Self },
+            encoding.max_encoded_len,
+            ident_name,
+            import,
+        );
+        return Ok(quote! {
+            #[allow(clippy::all, unused_qualifications, unreachable_code)]
+            impl #impl_generics #decode_trait for #ident_name #ty_generics #where_clause {
+                #inline
+                fn #decode<D: ::std::io::Read>(mut __strict_d: D) -> Result<Self, #import::Error> {
+                    #body
+                }
+            }
+
+            #decode_into_impl
+        });
+    }
+
+    let (bindings, inner_impl) = match data.fields {
+        ref fields if encoding.c_layout => {
+            decode_c_layout_fields_impl(fields, import)?
+        }
+        Fields::Named(ref fields) => decode_fields_impl(
+            &fields.named,
+            global_param,
+            import,
+            false,
+            encoding.version,
+            tail_start,
+            encoding.bitpack,
+        )?,
+        Fields::Unnamed(ref fields) => decode_fields_impl(
+            &fields.unnamed,
+            global_param,
+            import,
+            false,
+            encoding.version,
+            tail_start,
+            encoding.bitpack,
+        )?,
+        Fields::Unit => (TokenStream2::new(), TokenStream2::new()),
     };
 
-    let import = encoding.use_crate;
+    if let Some(width) = encoding.length_suffix {
+        return Ok(quote! {
+            #[allow(clippy::all, unused_qualifications, unreachable_code)]
+            impl #impl_generics #ident_name #ty_generics #where_clause {
+                /// Decodes the value from the end of the slice, where its
+                /// encoding is followed by the encoding length. Preceding
+                /// data in the slice are not read.
+                #inline
+                pub fn #decode_from_slice(data: &[u8]) -> Result<Self, #import::Error> {
+                    let __strict_width = ::core::mem::size_of::<#width>();
+                    if data.len() < __strict_width {
+                        return Err(::std::io::Error::from(::std::io::ErrorKind::UnexpectedEof).into());
+                    }
+                    let (__strict_rest, __strict_suffix) = data.split_at(data.len() - __strict_width);
+                    let __strict_body_len = <#width as #decode_trait>::#decode(__strict_suffix)? as usize;
+                    if __strict_body_len > __strict_rest.len() {
+                        return Err(::std::io::Error::from(::std::io::ErrorKind::UnexpectedEof).into());
+                    }
+                    let mut __strict_d = &__strict_rest[__strict_rest.len() - __strict_body_len..];
+                    #header_impl
+                    #bindings
+                    let decoded = #ident_name { #inner_impl };
+                    if !__strict_d.is_empty() {
+                        return Err(#import::Error::DataNotEntirelyConsumed);
+                    }
+                    Ok(decoded)
+                }
+            }
+        });
+    }
 
+    let body = decode_limited(
+        quote! {
+            #header_impl
+            #bindings
+            let decoded = #ident_name { #inner_impl };
+            #trailer_impl
+            Ok(decoded)
+        },
+        quote! { Self },
+        encoding.max_encoded_len,
+        ident_name,
+        import,
+    );
     Ok(quote! {
-        #[allow(unused_qualifications)]
-        impl #impl_generics #import::StrictDecode for #ident_name #ty_generics #where_clause {
-            #[inline]
-            fn strict_decode<D: ::std::io::Read>(mut d: D) -> Result<Self, #import::Error> {
-                use #import::StrictDecode;
-                Ok(#ident_name { #inner_impl })
+        #[allow(clippy::all, unused_qualifications, unreachable_code)]
+        impl #impl_generics #decode_trait for #ident_name #ty_generics #where_clause {
+            #inline
+            fn #decode<D: ::std::io::Read>(mut __strict_d: D) -> Result<Self, #import::Error> {
+                #body
             }
         }
+
+        #decode_into_impl
     })
 }
 
 fn decode_enum_impl(
     data: DataEnum,
     ident_name: &Ident,
+    import: &Import,
     mut global_param: ParametrizedAttr,
-    impl_generics: ImplGenerics,
-    ty_generics: TypeGenerics,
-    where_clause: Option<&WhereClause>,
+    generics: &Generics,
+    type_tag: Option<TokenStream2>,
 ) -> Result<TokenStream2> {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let decode_trait = &import.decode_trait;
+    let decode_variant = import.method("decode_variant");
+    let decode = import.method("decode");
     let encoding = EncodingDerive::try_from(&mut global_param, true, true)?;
+    let inline = encoding.inline_attr();
+    if encoding.union_tag.is_some() {
+        return Err(Error::new(
+            Span::call_site(),
+            "`union_tag` can be used with unions only",
+        ));
+    }
+    let no_tag = encoding.no_tag;
     let repr = encoding.repr;
+    let type_tag_impl = decode_type_tag(type_tag, import);
 
+    let const_values = encoding.const_values;
+    let mut consts = TokenStream2::new();
     let mut inner_impl = TokenStream2::new();
+    let mut decoded_variants = 0usize;
 
     for (order, variant) in data.variants.iter().enumerate() {
         let mut local_param =
@@ -111,34 +723,89 @@ fn decode_enum_impl(
         // First, test individual attribute
         let _ = EncodingDerive::try_from(&mut local_param, false, true)?;
         // Second, combine global and local together
-        let mut combined = global_param.clone().merged(local_param.clone())?;
-        combined.args.remove("repr");
-        combined.args.remove("crate");
+        let mut combined = EncodingDerive::merged(
+            &global_param,
+            local_param.clone(),
+            variant.span(),
+            "variant",
+            "type",
+        )?;
+        EncodingDerive::strip_global_args(&mut combined);
         let encoding = EncodingDerive::try_from(&mut combined, false, true)?;
+        EncodingDerive::inherit_max_len(&global_param, &mut local_param);
+
+        let ident = &variant.ident;
+        // Explicit variant value always takes precedence over the type-level
+        // `by_order`/`by_value` strategy
+        let mut value = match (&encoding.value, encoding.by_order) {
+            (Some(val), _) => quote! { (#val) as #repr },
+            (None, true) => Index::from(order).to_token_stream(),
+            (None, false) => quote! { Self::#ident as #repr },
+        };
+        // Each value is evaluated once into a constant, which can't reference
+        // `Self` being declared inside the method body
+        if const_values {
+            let name = format_ident!("V_{}", ident.unraw());
+            if let (None, false) = (&encoding.value, encoding.by_order) {
+                value = quote! { #ident_name::#ident as #repr };
+            }
+            consts.append_all(quote_spanned! { variant.span() =>
+                #[allow(non_upper_case_globals, deprecated)]
+                const #name: #repr = #value;
+            });
+            value = name.to_token_stream();
+        }
 
         if encoding.skip {
+            // Values of skipped variants may be decoded as a replacement
+            if let Some(replacement) = encoding.replace_with {
+                inner_impl.append_all(quote_spanned! { variant.span() =>
+                    #[allow(deprecated)]
+                    x if x == #value => #replacement,
+                });
+            }
             continue;
         }
+        decoded_variants += 1;
 
-        let field_impl = match variant.fields {
-            Fields::Named(ref fields) => {
-                decode_fields_impl(&fields.named, local_param, true)?
-            }
-            Fields::Unnamed(ref fields) => {
-                decode_fields_impl(&fields.unnamed, local_param, true)?
-            }
-            Fields::Unit => TokenStream2::new(),
+        let (bindings, field_impl) = match variant.fields {
+            Fields::Named(ref fields) => decode_fields_impl(
+                &fields.named,
+                local_param,
+                import,
+                true,
+                None,
+                None,
+                false,
+            )?,
+            Fields::Unnamed(ref fields) => decode_fields_impl(
+                &fields.unnamed,
+                local_param,
+                import,
+                true,
+                None,
+                None,
+                false,
+            )?,
+            Fields::Unit => (TokenStream2::new(), TokenStream2::new()),
         };
 
-        let ident = &variant.ident;
-        let value = match (encoding.value, encoding.by_order) {
-            (Some(val), _) => val.to_token_stream(),
-            (None, true) => Index::from(order as usize).to_token_stream(),
-            (None, false) => quote! { Self::#ident as #repr },
+        // Referencing deprecated variants must not produce warnings in the
+        // user crate
+        let allow_deprecated = if variant
+            .attrs
+            .iter()
+            .any(|attr| attr.path.is_ident("deprecated"))
+        {
+            quote! { #[allow(deprecated)] }
+        } else {
+            TokenStream2::new()
         };
 
         inner_impl.append_all(quote_spanned! { variant.span() =>
+            #allow_deprecated
             x if x == #value => {
+                #bindings
                 Self::#ident {
                     #field_impl
                 }
@@ -146,43 +813,225 @@ fn decode_enum_impl(
         });
     }
 
-    let import = encoding.use_crate;
-    let enum_name = LitStr::new(&ident_name.to_string(), Span::call_site());
+    let skip_warning =
+        skipped_enum_warning(ident_name, decoded_variants, data.variants.len());
+    variant_count_check(ident_name, decoded_variants, encoding.count_assert)?;
+    let enum_name =
+        LitStr::new(&ident_name.unraw().to_string(), ident_name.span());
+    let unknown_impl = match encoding.default_variant {
+        Some(default) => {
+            let variant = data
+                .variants
+                .iter()
+                .find(|variant| variant.ident == default)
+                .ok_or_else(|| {
+                    Error::new(
+                        default.span(),
+                        "`default_variant` must name a variant of the enum",
+                    )
+                })?;
+            if !matches!(variant.fields, Fields::Unit) {
+                return Err(Error::new(
+                    default.span(),
+                    "`default_variant` requires variant without fields",
+                ));
+            }
+            let allow_deprecated = if variant
+                .attrs
+                .iter()
+                .any(|attr| attr.path.is_ident("deprecated"))
+            {
+                quote! { #[allow(deprecated)] }
+            } else {
+                TokenStream2::new()
+            };
+            quote! {
+                #allow_deprecated
+                _ => Self::#default
+            }
+        }
+        None => quote! {
+            unknown => Err(#import::Error::EnumValueNotKnown(#enum_name, unknown as usize))?
+        },
+    };
 
-    Ok(quote! {
-        #[allow(unused_qualifications)]
-        impl #impl_generics #import::StrictDecode for #ident_name #ty_generics #where_clause {
-            fn strict_decode<D: ::std::io::Read>(mut d: D) -> Result<Self, #import::Error> {
-                use #import::StrictDecode;
-                Ok(match #repr::strict_decode(&mut d)? {
+    if no_tag {
+        let body = decode_limited(
+            quote! {
+                #skip_warning
+                #type_tag_impl
+                #consts
+                Ok(match __strict_tag {
                     #inner_impl
-                    unknown => Err(#import::Error::EnumValueNotKnown(#enum_name, unknown as usize))?
+                    #unknown_impl
                 })
+            },
+            quote! { Self },
+            encoding.max_encoded_len,
+            ident_name,
+            import,
+        );
+        return Ok(quote! {
+            #[allow(clippy::all, unused_qualifications, unreachable_code)]
+            impl #impl_generics #ident_name #ty_generics #where_clause {
+                /// Decodes enum variant with the given tag, which is not
+                /// written by `StrictEncode::strict_encode` and must be provided
+                /// by the enclosing data structure.
+                #inline
+                pub fn #decode_variant<D: ::std::io::Read>(__strict_tag: #repr, mut __strict_d: D) -> Result<Self, #import::Error> {
+                    #body
+                }
+            }
+        });
+    }
+
+    let body = decode_limited(
+        quote! {
+            #skip_warning
+            #type_tag_impl
+            #consts
+            Ok(match <#repr as #decode_trait>::#decode(&mut __strict_d)? {
+                #inner_impl
+                #unknown_impl
+            })
+        },
+        quote! { Self },
+        encoding.max_encoded_len,
+        ident_name,
+        import,
+    );
+    Ok(quote! {
+        #[allow(clippy::all, unused_qualifications, unreachable_code)]
+        impl #impl_generics #decode_trait for #ident_name #ty_generics #where_clause {
+            #inline
+            fn #decode<D: ::std::io::Read>(mut __strict_d: D) -> Result<Self, #import::Error> {
+                #body
+            }
+        }
+    })
+}
+
+fn decode_union_impl(
+    data: DataUnion,
+    ident_name: &Ident,
+    import: &Import,
+    mut global_param: ParametrizedAttr,
+    generics: &Generics,
+    type_tag: Option<TokenStream2>,
+) -> Result<TokenStream2> {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let decode_trait = &import.decode_trait;
+    let decode = import.method("decode");
+    let encoding = EncodingDerive::try_from(&mut global_param, true, true)?;
+    let inline = encoding.inline_attr();
+    let repr = encoding.repr;
+    let type_tag_impl = decode_type_tag(type_tag, import);
+    if encoding.union_tag.is_none() {
+        return Err(Error::new(
+            Span::call_site(),
+            "unions require `union_tag` attribute naming the method returning tag of the active field",
+        ));
+    }
+
+    let mut inner_impl = TokenStream2::new();
+    let mut known_values = Vec::with_capacity(data.fields.named.len());
+    for (field, value) in union_fields(&data)? {
+        let name = &field.ident;
+        inner_impl.append_all(quote_spanned! { field.span() =>
+            x if x == (#value) as #repr => Self {
+                #name: #decode_trait::#decode(&mut __strict_d)?,
+            },
+        });
+        known_values.push(quote! { (#value) as #repr });
+    }
+
+    let union_name =
+        LitStr::new(&ident_name.unraw().to_string(), ident_name.span());
+    let body = decode_limited(
+        quote! {
+            #type_tag_impl
+            Ok(match <#repr as #decode_trait>::#decode(&mut __strict_d)? {
+                #inner_impl
+                unknown => {
+                    let __strict_known: &[#repr] = &[#( #known_values ),*];
+                    let __strict_known = __strict_known
+                        .iter()
+                        .map(::std::string::ToString::to_string)
+                        .collect::<Vec<_>>()
+                        .join(", ");
+                    Err(#import::Error::DataIntegrityError(format!(
+                        "unknown value {} for union {}; known values are: {}",
+                        unknown, #union_name, __strict_known
+                    )))?
+                }
+            })
+        },
+        quote! { Self },
+        encoding.max_encoded_len,
+        ident_name,
+        import,
+    );
+    Ok(quote! {
+        #[allow(clippy::all, unused_qualifications)]
+        impl #impl_generics #decode_trait for #ident_name #ty_generics #where_clause {
+            #inline
+            fn #decode<D: ::std::io::Read>(mut __strict_d: D) -> Result<Self, #import::Error> {
+                #body
             }
         }
     })
 }
 
+/// Returns field initializers for the structure or enum variant literal. If
+/// fields are not encoded in the order of their declaration, their values are
+/// decoded into local variables first, and the statements binding them are
+/// returned as the first item of the tuple.
 fn decode_fields_impl<'a>(
     fields: impl IntoIterator<Item = &'a Field>,
     mut parent_param: ParametrizedAttr,
+    import: &Import,
     is_enum: bool,
-) -> Result<TokenStream2> {
+    version: Option<u64>,
+    tail_start: Option<usize>,
+    bitpack: bool,
+) -> Result<(TokenStream2, TokenStream2)> {
+    let mut bindings = order_skew_note(&parent_param, is_enum)?;
     let mut stream = TokenStream2::new();
+    let field_prefix = field_prefix_repr(&parent_param, is_enum)?;
+    let max_len = container_max_len(&parent_param)?;
+
+    let fields = wire_order(fields, &parent_param, is_enum, OrderSide::Decode)?;
+    EncodingDerive::strip_global_args(&mut parent_param);
 
-    parent_param.args.remove("crate");
-    let parent_attr =
-        EncodingDerive::try_from(&mut parent_param.clone(), false, is_enum)?;
-    let import = parent_attr.use_crate;
+    // Reading packed bits requires statements, so all the fields are bound
+    // to local variables
+    let reordered = bitpack
+        || fields
+            .iter()
+            .enumerate()
+            .any(|(pos, (index, _))| pos != *index);
+    let mut bitpack = BitPack::new(bitpack);
 
-    for (index, field) in fields.into_iter().enumerate() {
-        let mut local_param = ParametrizedAttr::with(ATTR_NAME, &field.attrs)?;
+    for (index, field) in fields {
+        let mut local_param = field_param(field)?;
 
         // First, test individual attribute
         let _ = EncodingDerive::try_from(&mut local_param, false, is_enum)?;
         // Second, combine global and local together
-        let mut combined = parent_param.clone().merged(local_param)?;
-        let encoding = EncodingDerive::try_from(&mut combined, false, is_enum)?;
+        let mut combined = EncodingDerive::merged(
+            &parent_param,
+            local_param,
+            field.span(),
+            "field",
+            if is_enum { "variant" } else { "type" },
+        )?;
+        let mut encoding =
+            EncodingDerive::try_from(&mut combined, false, is_enum)?;
+        if encoding.max_len.is_none()
+            && (encoding.collection.is_some() || is_std_collection(&field.ty))
+        {
+            encoding.max_len = max_len;
+        }
 
         let name = field
             .ident
@@ -194,12 +1043,654 @@ fn decode_fields_impl<'a>(
             stream.append_all(quote_spanned! { field.span() =>
                 #name: Default::default(),
             });
+        } else if bitpack.packs(field, &encoding)? {
+            let (prelude, value) = bitpack.decode_bool(import);
+            let binding = format_ident!("__strict_field_{}", index);
+            bindings.append_all(quote_spanned! { field.span() =>
+                #prelude
+                let #binding = #value;
+            });
+            stream.append_all(quote_spanned! { field.span() =>
+                #name: #binding,
+            });
         } else {
+            bindings.append_all(bitpack.decode_flush(import));
+            let value = if matches!(tail_start, Some(start) if index >= start) {
+                decode_optional_tail(&encoding, field, import)
+            } else {
+                let value = decode_field_value(&encoding, field, import)?;
+                let value = confined(value, &encoding, field, import);
+                asserted(value, &encoding, index, field, import)
+            };
+            let value = prefix_checked(
+                value,
+                field_prefix.as_ref(),
+                index,
+                field,
+                import,
+            )?;
+            let value = version_gated(value, &encoding, version)?;
+            let value = const_gated(value, &encoding);
+            if reordered {
+                let binding = format_ident!("__strict_field_{}", index);
+                bindings.append_all(quote_spanned! { field.span() =>
+                    let #binding = #value;
+                });
+                stream.append_all(quote_spanned! { field.span() =>
+                    #name: #binding,
+                });
+            } else {
+                stream.append_all(quote_spanned! { field.span() =>
+                    #name: #value,
+                });
+            }
+        }
+    }
+    bindings.append_all(bitpack.decode_flush(import));
+
+    Ok((bindings, stream))
+}
+
+fn decode_into_fields_impl(
+    fields: &Fields,
+    mut parent_param: ParametrizedAttr,
+    import: &Import,
+    version: Option<u64>,
+    tail_start: Option<usize>,
+    bitpack: bool,
+) -> Result<TokenStream2> {
+    let mut stream = order_skew_note(&parent_param, false)?;
+    let mut bitpack = BitPack::new(bitpack);
+    let field_prefix = field_prefix_repr(&parent_param, false)?;
+    let max_len = container_max_len(&parent_param)?;
+
+    let fields = wire_order(fields, &parent_param, false, OrderSide::Decode)?;
+    EncodingDerive::strip_global_args(&mut parent_param);
+
+    for (index, field) in fields {
+        let mut local_param = field_param(field)?;
+
+        // First, test individual attribute
+        let _ = EncodingDerive::try_from(&mut local_param, false, false)?;
+        // Second, combine global and local together
+        let mut combined = EncodingDerive::merged(
+            &parent_param,
+            local_param,
+            field.span(),
+            "field",
+            "type",
+        )?;
+        let mut encoding =
+            EncodingDerive::try_from(&mut combined, false, false)?;
+        if encoding.max_len.is_none()
+            && (encoding.collection.is_some() || is_std_collection(&field.ty))
+        {
+            encoding.max_len = max_len;
+        }
+
+        if encoding.skip {
+            continue;
+        }
+
+        let name = field
+            .ident
+            .as_ref()
+            .map(Ident::to_token_stream)
+            .unwrap_or_else(|| Index::from(index).to_token_stream());
+
+        if bitpack.packs(field, &encoding)? {
+            let (prelude, value) = bitpack.decode_bool(import);
             stream.append_all(quote_spanned! { field.span() =>
-                #name: #import::StrictDecode::strict_decode(&mut d)?,
+                #prelude
+                self.#name = #value;
             });
+            continue;
         }
+        stream.append_all(bitpack.decode_flush(import));
+
+        let value = if matches!(tail_start, Some(start) if index >= start) {
+            decode_optional_tail(&encoding, field, import)
+        } else {
+            let value = decode_field_value(&encoding, field, import)?;
+            let value = confined(value, &encoding, field, import);
+            asserted(value, &encoding, index, field, import)
+        };
+        let value =
+            prefix_checked(value, field_prefix.as_ref(), index, field, import)?;
+        let value = version_gated(value, &encoding, version)?;
+        let value = const_gated(value, &encoding);
+        stream.append_all(quote_spanned! { field.span() =>
+            self.#name = #value;
+        });
     }
+    stream.append_all(bitpack.decode_flush(import));
 
     Ok(stream)
 }
+
+/// Decodes fields of `#[repr(C)]` structure matching their memory layout,
+/// skipping padding bytes. Padding between the fields never exceeds alignment
+/// of primitive types, which is at most 16 bytes, while the trailing padding
+/// of `align(N)` structures is read in chunks.
+fn decode_c_layout_fields_impl(
+    fields: &Fields,
+    import: &Import,
+) -> Result<(TokenStream2, TokenStream2)> {
+    let mut bindings = quote! {
+        let mut __strict_offset = 0usize;
+        let mut __strict_padding = [0u8; 16];
+    };
+    let mut stream = TokenStream2::new();
+    for (index, field) in fields.iter().enumerate() {
+        let mut param = field_param(field)?;
+        let encoding = EncodingDerive::try_from(&mut param, false, false)?;
+        let name = field
+            .ident
+            .as_ref()
+            .map(Ident::to_token_stream)
+            .unwrap_or_else(|| Index::from(index).to_token_stream());
+        let padding = c_layout_padding(&field.ty);
+        let value = decode_field_value(&encoding, field, import)?;
+        let binding = format_ident!("__strict_field_{}", index);
+        bindings.append_all(quote_spanned! { field.span() =>
+            #padding
+            ::std::io::Read::read_exact(&mut __strict_d, &mut __strict_padding[..__strict_pad])?;
+            let #binding = #value;
+        });
+        stream.append_all(quote_spanned! { field.span() =>
+            #name: #binding,
+        });
+    }
+    // Trailing padding of structures with `align(N)` representation may be
+    // longer than the padding buffer
+    bindings.append_all(quote! {
+        let mut __strict_pad = ::core::mem::size_of::<Self>() - __strict_offset;
+        while __strict_pad > 0 {
+            let __strict_chunk = __strict_pad.min(__strict_padding.len());
+            ::std::io::Read::read_exact(&mut __strict_d, &mut __strict_padding[..__strict_chunk])?;
+            __strict_pad -= __strict_chunk;
+        }
+    });
+    Ok((bindings, stream))
+}
+
+/// Decodes all non-skipped fields into local variables and passes them, in
+/// the order of their declaration, to the `construct_with` constructor.
+fn decode_constructor_fields_impl(
+    fields: &Fields,
+    mut parent_param: ParametrizedAttr,
+    constructor: Path,
+    import: &Import,
+    version: Option<u64>,
+    tail_start: Option<usize>,
+    bitpack: bool,
+) -> Result<TokenStream2> {
+    let mut stream = order_skew_note(&parent_param, false)?;
+    let mut args = Vec::new();
+    let mut bitpack = BitPack::new(bitpack);
+    let field_prefix = field_prefix_repr(&parent_param, false)?;
+    let max_len = container_max_len(&parent_param)?;
+
+    let fields = wire_order(fields, &parent_param, false, OrderSide::Decode)?;
+    EncodingDerive::strip_global_args(&mut parent_param);
+
+    for (index, field) in fields {
+        let mut local_param = field_param(field)?;
+
+        // First, test individual attribute
+        let _ = EncodingDerive::try_from(&mut local_param, false, false)?;
+        // Second, combine global and local together
+        let mut combined = EncodingDerive::merged(
+            &parent_param,
+            local_param,
+            field.span(),
+            "field",
+            "type",
+        )?;
+        let mut encoding =
+            EncodingDerive::try_from(&mut combined, false, false)?;
+        if encoding.max_len.is_none()
+            && (encoding.collection.is_some() || is_std_collection(&field.ty))
+        {
+            encoding.max_len = max_len;
+        }
+
+        if encoding.skip {
+            continue;
+        }
+
+        let name = field
+            .ident
+            .as_ref()
+            .map(|ident| format_ident!("_{}", ident))
+            .unwrap_or_else(|| format_ident!("_{}", index));
+        let ty = &field.ty;
+
+        if bitpack.packs(field, &encoding)? {
+            let (prelude, value) = bitpack.decode_bool(import);
+            stream.append_all(quote_spanned! { field.span() =>
+                #prelude
+                let #name: #ty = #value;
+            });
+            args.push((index, name));
+            continue;
+        }
+        stream.append_all(bitpack.decode_flush(import));
+
+        let value = if matches!(tail_start, Some(start) if index >= start) {
+            decode_optional_tail(&encoding, field, import)
+        } else {
+            let value = decode_field_value(&encoding, field, import)?;
+            let value = confined(value, &encoding, field, import);
+            asserted(value, &encoding, index, field, import)
+        };
+        let value =
+            prefix_checked(value, field_prefix.as_ref(), index, field, import)?;
+        let value = version_gated(value, &encoding, version)?;
+        let value = const_gated(value, &encoding);
+        stream.append_all(quote_spanned! { field.span() =>
+            let #name: #ty = #value;
+        });
+        args.push((index, name));
+    }
+    stream.append_all(bitpack.decode_flush(import));
+
+    // Constructor takes arguments in the order of field declaration
+    args.sort_by_key(|(index, _)| *index);
+    let args = args.into_iter().map(|(_, name)| name);
+    stream.append_all(quote! {
+        #constructor( #( #args ),* ).map_err(|err| {
+            #import::Error::DataIntegrityError(err.to_string())
+        })
+    });
+
+    Ok(stream)
+}
+
+/// Reads magic bytes preceding the encoded data and checks that they match the
+/// expected ones.
+fn decode_magic(
+    magic: Vec<u8>,
+    ident_name: &Ident,
+    import: &Import,
+) -> TokenStream2 {
+    let magic_len = magic.len();
+    let type_name =
+        LitStr::new(&ident_name.unraw().to_string(), ident_name.span());
+    quote! {
+        let mut __strict_magic = [0u8; #magic_len];
+        ::std::io::Read::read_exact(&mut __strict_d, &mut __strict_magic)?;
+        if __strict_magic != [ #( #magic ),* ] {
+            return Err(#import::Error::DataIntegrityError(format!(
+                "invalid magic bytes for {}: expected {:02x?}, got {:02x?}",
+                #type_name,
+                [ #( #magic ),* ],
+                __strict_magic,
+            )));
+        }
+    }
+}
+
+/// Reads version of the encoded structure and checks that it is not newer than
+/// the version supported by the structure definition.
+fn decode_version(
+    version: u64,
+    repr: &Ident,
+    ident_name: &Ident,
+    import: &Import,
+) -> TokenStream2 {
+    let decode_trait = &import.decode_trait;
+    let decode = import.method("decode");
+    let type_name =
+        LitStr::new(&ident_name.unraw().to_string(), ident_name.span());
+    let max = Literal::u128_unsuffixed(version as u128 + 1);
+    let version = Literal::u64_unsuffixed(version);
+    quote! {
+        let __strict_version = <#repr as #decode_trait>::#decode(&mut __strict_d)?;
+        if __strict_version > #version {
+            return Err(#import::Error::ValueOutOfRange(
+                #type_name,
+                0..#max,
+                __strict_version as u128,
+            ));
+        }
+    }
+}
+
+/// Wraps expression decoding field value with the check that the field is
+/// present in the decoded version of the structure; absent fields are set to
+/// their default values.
+fn version_gated(
+    value: TokenStream2,
+    encoding: &EncodingDerive,
+    version: Option<u64>,
+) -> Result<TokenStream2> {
+    encoding.is_present_in(version)?;
+    let since = encoding.since.filter(|since| *since > 0);
+    let condition = match (since, encoding.until) {
+        (None, None) => return Ok(value),
+        (Some(since), Some(until)) if since == until => {
+            let since = Literal::u64_unsuffixed(since);
+            quote! { __strict_version == #since }
+        }
+        (Some(since), Some(until)) => {
+            let since = Literal::u64_unsuffixed(since);
+            let until = Literal::u64_unsuffixed(until);
+            quote! { (#since..=#until).contains(&__strict_version) }
+        }
+        (Some(since), None) => {
+            let since = Literal::u64_unsuffixed(since);
+            quote! { __strict_version >= #since }
+        }
+        (None, Some(until)) => {
+            let until = Literal::u64_unsuffixed(until);
+            quote! { __strict_version <= #until }
+        }
+    };
+    Ok(quote! {
+        if #condition {
+            #value
+        } else {
+            Default::default()
+        }
+    })
+}
+
+/// Prepends reading of the field prefix, which must be equal to the field
+/// index, to the expression decoding field value.
+fn prefix_checked(
+    value: TokenStream2,
+    field_prefix: Option<&Ident>,
+    index: usize,
+    field: &Field,
+    import: &Import,
+) -> Result<TokenStream2> {
+    let decode_trait = &import.decode_trait;
+    let decode = import.method("decode");
+    let repr = match field_prefix {
+        Some(repr) => repr,
+        None => return Ok(value),
+    };
+    let prefix = field_prefix_lit(repr, index, field)?;
+    Ok(quote_spanned! { field.span() =>
+        {
+            let __strict_prefix = <#repr as #decode_trait>::#decode(&mut __strict_d)?;
+            if __strict_prefix != #prefix {
+                return Err(#import::Error::DataIntegrityError(format!(
+                    "field prefix {} does not match expected field index {}",
+                    __strict_prefix, #prefix
+                )));
+            }
+            #value
+        }
+    })
+}
+
+/// Fails for structures having fields of uninhabited `Infallible` type, which
+/// can never be decoded. Unlike enum variants with such fields, which are
+/// just unreachable, the whole structure can't have any value.
+fn uninhabited_check(fields: &Fields) -> Result<()> {
+    for field in fields {
+        if is_infallible(&field.ty) {
+            return Err(Error::new(
+                field.span(),
+                "structure with a field of uninhabited `Infallible` type can never be decoded; move the field into an enum variant (which can be marked with `skip`) or derive `StrictEncode` only",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Checks that the decoded field does not have more items than allowed with
+/// `max_len` argument; collections decoded with `collection` argument are
+/// checked before reading their items instead.
+fn confined(
+    value: TokenStream2,
+    encoding: &EncodingDerive,
+    field: &Field,
+    import: &Import,
+) -> TokenStream2 {
+    let max_len = match (encoding.max_len, &encoding.collection) {
+        (Some(max_len), None) => max_len as usize,
+        _ => return value,
+    };
+    let ty = &field.ty;
+    quote_spanned! { field.span() =>
+        {
+            let __strict_value: #ty = #value;
+            if __strict_value.len() > #max_len {
+                return Err(#import::Error::ExceedMaxItems(__strict_value.len()));
+            }
+            __strict_value
+        }
+    }
+}
+
+/// Checks the decoded field value (or the number of items, for non-primitive
+/// types) against the expected one given with `assert_eq` argument.
+fn asserted(
+    value: TokenStream2,
+    encoding: &EncodingDerive,
+    index: usize,
+    field: &Field,
+    import: &Import,
+) -> TokenStream2 {
+    let expected = match encoding.assert_eq {
+        Some(ref expected) => expected,
+        None => return value,
+    };
+    let name = field
+        .ident
+        .as_ref()
+        .map(Ident::to_string)
+        .unwrap_or_else(|| index.to_string());
+    let actual = if primitive_size(&field.ty).is_some() {
+        quote! { __strict_value as usize }
+    } else {
+        quote! { __strict_value.len() }
+    };
+    let ty = &field.ty;
+    quote_spanned! { field.span() =>
+        {
+            let __strict_value: #ty = #value;
+            let __strict_expected = (#expected) as usize;
+            if #actual != __strict_expected {
+                return Err(#import::Error::DataIntegrityError(format!(
+                    "field `{}` has value {} instead of expected {}",
+                    #name, #actual, __strict_expected
+                )));
+            }
+            __strict_value
+        }
+    }
+}
+
+/// Wraps expression decoding field value with the check of the `skip_if_const`
+/// condition; fields skipped by the condition are set to their default values.
+fn const_gated(value: TokenStream2, encoding: &EncodingDerive) -> TokenStream2 {
+    match encoding.skip_if_const {
+        Some(ref condition) => quote! {
+            if #condition {
+                Default::default()
+            } else {
+                #value
+            }
+        },
+        None => value,
+    }
+}
+
+/// Returns expression decoding value of a non-skipped field. Requires
+/// `StrictDecode` trait to be in scope of the generated code.
+fn decode_field_value(
+    encoding: &EncodingDerive,
+    field: &Field,
+    import: &Import,
+) -> Result<TokenStream2> {
+    let decode_trait = &import.decode_trait;
+    let decode = import.method("decode");
+    count_type_check(field, encoding)?;
+    Ok(if is_infallible(&field.ty) {
+        quote_spanned! { field.span() =>
+            return Err(#import::Error::UnsupportedDataStructure(
+                "value of `Infallible` type can't be decoded",
+            ))
+        }
+    } else if let Some(ref collection) = encoding.collection {
+        let max_len_check = encoding.max_len.map(|max_len| {
+            let max_len = max_len as usize;
+            quote! {
+                if __strict_count > #max_len {
+                    return Err(#import::Error::ExceedMaxItems(__strict_count));
+                }
+            }
+        });
+        let count_type = match encoding.count_type {
+            Some(ref count_type) => count_type.to_token_stream(),
+            None => quote! { usize },
+        };
+        quote_spanned! { field.span() =>
+            {
+                let __strict_count = <#count_type as #decode_trait>::#decode(&mut __strict_d)? as usize;
+                #max_len_check
+                let mut __strict_items = #collection;
+                for _ in 0..__strict_count {
+                    __strict_items.push(#decode_trait::#decode(&mut __strict_d)?);
+                }
+                __strict_items
+            }
+        }
+    } else if encoding.tuple_fields {
+        let elems = tuple_elems(field, encoding)?.into_iter().map(|elem| {
+            quote_spanned! { elem.span() =>
+                #decode_trait::#decode(&mut __strict_d)?,
+            }
+        });
+        quote! { ( #( #elems )* ) }
+    } else if encoding.byte_blob {
+        decode_byte_blob(import)
+    } else if encoding.sorted_map && !is_btree_map(&field.ty) {
+        decode_sorted_map(&field.ty, import)
+    } else if let Some(ref count_type) = encoding.count_type {
+        quote_spanned! { field.span() =>
+            {
+                let __strict_count = <#count_type as #decode_trait>::#decode(&mut __strict_d)?;
+                (0..__strict_count)
+                    .map(|_| #decode_trait::#decode(&mut __strict_d))
+                    .collect::<Result<_, #import::Error>>()?
+            }
+        }
+    } else if let Some(array) = elementwise_array(&field.ty) {
+        let len = &array.len;
+        quote_spanned! { field.span() =>
+            {
+                let mut __strict_items = Vec::with_capacity(#len);
+                for _ in 0..(#len) {
+                    __strict_items.push(#decode_trait::#decode(&mut __strict_d)?);
+                }
+                match ::core::convert::TryFrom::try_from(__strict_items) {
+                    Ok(array) => array,
+                    Err(_) => unreachable!("array has exactly the decoded number of items"),
+                }
+            }
+        }
+    } else if is_result(&field.ty) {
+        quote_spanned! { field.span() =>
+            match <u8 as #decode_trait>::#decode(&mut __strict_d)? {
+                0 => Ok(#decode_trait::#decode(&mut __strict_d)?),
+                1 => Err(#decode_trait::#decode(&mut __strict_d)?),
+                tag => return Err(#import::Error::EnumValueNotKnown("Result", tag as usize)),
+            }
+        }
+    } else if cow_borrowed(&field.ty).is_some() {
+        quote_spanned! { field.span() =>
+            ::std::borrow::Cow::Owned(#decode_trait::#decode(&mut __strict_d)?)
+        }
+    } else {
+        quote_spanned! { field.span() =>
+            #decode_trait::#decode(&mut __strict_d)?
+        }
+    })
+}
+
+/// Decodes field of the optional tail, which is absent if the data end right
+/// before it or before any of the preceding optional tail fields. Absent
+/// `default_on_eof` fields take their default value, other fields are `None`.
+fn decode_optional_tail(
+    encoding: &EncodingDerive,
+    field: &Field,
+    import: &Import,
+) -> TokenStream2 {
+    let decode_trait = &import.decode_trait;
+    let decode = import.method("decode");
+    let value = quote! {
+        #decode_trait::#decode(::std::io::Read::chain(
+            &__strict_byte[..],
+            &mut __strict_d,
+        ))?
+    };
+    let (absent, present) = if encoding.default_on_eof {
+        let default = encoding
+            .default
+            .as_ref()
+            .map(Expr::to_token_stream)
+            .unwrap_or_else(|| quote! { Default::default() });
+        (default, value)
+    } else {
+        (quote! { None }, quote! { Some(#value) })
+    };
+    quote_spanned! { field.span() =>
+        {
+            let mut __strict_byte = [0u8; 1];
+            if __strict_tail_eof
+                || ::std::io::Read::read(&mut __strict_d, &mut __strict_byte)? == 0
+            {
+                __strict_tail_eof = true;
+                #absent
+            } else {
+                #present
+            }
+        }
+    }
+}
+
+/// Reads `u32` length prefix followed by the raw bytes of a `byte_blob` field.
+fn decode_byte_blob(import: &Import) -> TokenStream2 {
+    let decode_trait = &import.decode_trait;
+    let decode = import.method("decode");
+    quote! {
+        {
+            let __strict_blob_len = <u32 as #decode_trait>::#decode(&mut __strict_d)? as usize;
+            let mut __strict_blob = Vec::new();
+            ::std::io::Read::read_to_end(
+                &mut ::std::io::Read::take(&mut __strict_d, __strict_blob_len as u64),
+                &mut __strict_blob,
+            )?;
+            if __strict_blob.len() != __strict_blob_len {
+                return Err(::std::io::Error::from(
+                    ::std::io::ErrorKind::UnexpectedEof,
+                ).into());
+            }
+            __strict_blob
+        }
+    }
+}
+
+/// Reads map entries written with `sorted_map` attribute, accepting them in
+/// any order, so maps written by the encoders not sorting the entries are read
+/// as well.
+fn decode_sorted_map(ty: &Type, import: &Import) -> TokenStream2 {
+    let decode_trait = &import.decode_trait;
+    let decode = import.method("decode");
+    quote! {
+        {
+            let __strict_count = <usize as #decode_trait>::#decode(&mut __strict_d)?;
+            let mut __strict_map: #ty = Default::default();
+            for _ in 0..__strict_count {
+                let __strict_key = #decode_trait::#decode(&mut __strict_d)?;
+                __strict_map.insert(__strict_key, #decode_trait::#decode(&mut __strict_d)?);
+            }
+            __strict_map
+        }
+    }
+}
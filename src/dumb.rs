@@ -0,0 +1,137 @@
+// LNP/BP client-side-validation library implementing respective LNPBP
+// specifications & standards (LNPBP-7, 8, 9, 42)
+//
+// Written in 2019-2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the Apache 2.0 License along with this
+// software. If not, see <https://opensource.org/licenses/Apache-2.0>.
+
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Error, Fields, Result, Type};
+
+use amplify::proc_attr::ParametrizedAttr;
+
+use crate::bound::bounded_generics;
+use crate::param::{field_param, EncodingDerive};
+use crate::ATTR_NAME;
+
+pub(crate) fn dumb_derive(input: DeriveInput) -> Result<TokenStream2> {
+    let ident_name = &input.ident;
+
+    let mut global_param = ParametrizedAttr::with(ATTR_NAME, &input.attrs)?;
+    let is_enum = matches!(input.data, Data::Enum(_));
+    let _ = EncodingDerive::try_from(&mut global_param, true, is_enum)?;
+
+    // Types of the fields constructed with `Default::default()`, which are
+    // the only ones requiring bounds
+    let mut defaulted = vec![];
+    let value = match input.data {
+        Data::Struct(ref data) => {
+            let fields = dumb_fields(&data.fields, false, &mut defaulted)?;
+            quote! { Self #fields }
+        }
+        Data::Enum(ref data) => {
+            let mut marked = None;
+            let mut first = None;
+            for variant in &data.variants {
+                let mut param =
+                    ParametrizedAttr::with(ATTR_NAME, &variant.attrs)?;
+                let encoding =
+                    EncodingDerive::try_from(&mut param, false, true)?;
+                match encoding.dumb {
+                    Some(Some(_)) => {
+                        return Err(Error::new(
+                            variant.span(),
+                            "enum variants can be only marked with `dumb` without a value",
+                        ))
+                    }
+                    Some(None) if marked.is_some() => {
+                        return Err(Error::new(
+                            variant.span(),
+                            "only one enum variant can be marked with `dumb`",
+                        ))
+                    }
+                    Some(None) => marked = Some(variant),
+                    None if first.is_none() && !encoding.skip => {
+                        first = Some(variant)
+                    }
+                    None => {}
+                }
+            }
+            let variant = marked.or(first).ok_or_else(|| {
+                Error::new(
+                    Span::call_site(),
+                    "`StrictDumb` requires enum to have a non-skipped variant or a variant marked with `dumb`",
+                )
+            })?;
+            let ident = &variant.ident;
+            let fields = dumb_fields(&variant.fields, true, &mut defaulted)?;
+            quote! { Self::#ident #fields }
+        }
+        Data::Union(_) => {
+            return Err(Error::new(
+                Span::call_site(),
+                "`StrictDumb` can't be derived for unions",
+            ))
+        }
+    };
+
+    let generics =
+        bounded_generics(&input, quote! { Default }, None, &[], &defaulted)?;
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+
+    Ok(quote! {
+        #[allow(clippy::all, unused_qualifications, deprecated)]
+        impl #impl_generics #ident_name #ty_generics #where_clause {
+            /// Returns placeholder value of the type, constructed from the
+            /// default values of its fields.
+            pub fn strict_dumb() -> Self {
+                #value
+            }
+        }
+    })
+}
+
+/// Constructs fields of the placeholder value from the expressions provided
+/// with `dumb` field arguments or from the field default values, collecting
+/// types of the non-skipped fields using the default values into `defaulted`.
+fn dumb_fields(
+    fields: &Fields,
+    is_enum: bool,
+    defaulted: &mut Vec<Type>,
+) -> Result<TokenStream2> {
+    let mut values = Vec::with_capacity(fields.len());
+    for field in fields.iter() {
+        let mut param = field_param(field)?;
+        let encoding = EncodingDerive::try_from(&mut param, false, is_enum)?;
+        let value = match encoding.dumb {
+            Some(Some(expr)) => quote! { #expr },
+            Some(None) => return Err(Error::new(
+                field.span(),
+                "`dumb` field argument requires placeholder value expression",
+            )),
+            None => {
+                if !encoding.skip {
+                    defaulted.push(field.ty.clone());
+                }
+                quote! { Default::default() }
+            }
+        };
+        values.push(match field.ident {
+            Some(ref ident) => quote! { #ident: #value },
+            None => value,
+        });
+    }
+    Ok(match fields {
+        Fields::Named(_) => quote! { { #( #values ),* } },
+        Fields::Unnamed(_) => quote! { ( #( #values ),* ) },
+        Fields::Unit => TokenStream2::new(),
+    })
+}
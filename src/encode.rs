@@ -12,82 +12,675 @@
 // You should have received a copy of the Apache 2.0 License along with this
 // software. If not, see <https://opensource.org/licenses/Apache-2.0>.
 
-use proc_macro2::{Span, TokenStream as TokenStream2};
+use proc_macro2::{Literal, Span, TokenStream as TokenStream2};
 use quote::{ToTokens, TokenStreamExt};
+use syn::ext::IdentExt;
 use syn::spanned::Spanned;
 use syn::{
-    Data, DataEnum, DataStruct, DeriveInput, Error, Field, Fields, Ident,
-    ImplGenerics, Index, Result, TypeGenerics, WhereClause,
+    Data, DataEnum, DataStruct, DataUnion, DeriveInput, Error, Expr, ExprLit,
+    Field, Fields, Generics, Ident, ImplGenerics, Index, Lit, LitStr, Result,
+    Type, TypeGenerics, WhereClause,
 };
 
 use amplify::proc_attr::ParametrizedAttr;
 
-use crate::param::EncodingDerive;
-use crate::ATTR_NAME;
+use crate::bitpack::BitPack;
+use crate::bound::{bounded_generics, encoded_types};
+use crate::checksum::crc32_adapter;
+use crate::flavor::{Flavor, Import};
+use crate::layout::{c_layout_check, c_layout_padding};
+use crate::limit::encode_limited;
+use crate::param::{
+    container_max_len, count_type_check, cow_borrowed, elementwise_array,
+    field_param, field_prefix_lit, field_prefix_repr, is_btree_map,
+    is_infallible, is_result, is_std_collection, optional_tail_start,
+    order_skew_note, transparent_field, tuple_elems, union_fields,
+    via_deref_check, wire_order, EncodingDerive, OrderSide,
+};
+use crate::proptest::roundtrip_test;
+use crate::size::{
+    encoded_size_impl, lower_bound_size, primitive_size, size_hint_impl,
+};
+use crate::type_tag::{encode_type_tag, type_tag};
+use crate::{
+    compile_warning, skipped_enum_warning, variant_count_check, ATTR_NAME,
+};
 
-pub(crate) fn encode_derive(input: DeriveInput) -> Result<TokenStream2> {
-    let (impl_generics, ty_generics, where_clause) =
-        input.generics.split_for_impl();
+pub(crate) fn encode_derive(
+    input: DeriveInput,
+    flavor: &Flavor,
+) -> Result<TokenStream2> {
+    let input = flavor.input(input);
     let ident_name = &input.ident;
 
     let global_param = ParametrizedAttr::with(ATTR_NAME, &input.attrs)?;
 
-    match input.data {
-        Data::Struct(data) => encode_struct_impl(
-            data,
-            ident_name,
-            global_param,
-            impl_generics,
-            ty_generics,
-            where_clause,
-        ),
-        Data::Enum(data) => encode_enum_impl(
-            data,
+    let is_enum = matches!(input.data, Data::Enum(_) | Data::Union(_));
+    let encoding =
+        EncodingDerive::try_from(&mut global_param.clone(), true, is_enum)?;
+    let import = flavor.import(encoding.use_crate.as_ref());
+    let encode_trait = &import.encode_trait;
+    if encoding.c_layout {
+        c_layout_check(&input)?;
+    }
+    let roundtrip_test = if encoding.proptest {
+        roundtrip_test(&input, &import, encoding.len_helper)?
+    } else {
+        TokenStream2::new()
+    };
+
+    let tag = type_tag(&input, &encoding);
+    let inline = encoding.inline_attr();
+    let vis = encoding.helper_vis();
+
+    let dyn_impl = if encoding.dyn_safe {
+        encode_dyn_impl(&input, &import, &inline, &vis)
+    } else {
+        TokenStream2::new()
+    };
+
+    let append_impl = if encoding.append {
+        encode_append_impl(&input, &import, &inline, &vis)
+    } else {
+        TokenStream2::new()
+    };
+
+    let serialize_impl = if encoding.helpers {
+        encode_serialize_impl(&input, &import, &vis)
+    } else {
+        TokenStream2::new()
+    };
+
+    let len_impl = if encoding.len_helper {
+        encode_len_impl(&input, &import, &vis)
+    } else {
+        TokenStream2::new()
+    };
+
+    let to_vec_impl = if encoding.to_vec {
+        encode_to_vec_impl(
+            &input,
+            global_param.clone(),
+            &import,
+            encoding.len_helper,
+            &vis,
+        )?
+    } else {
+        TokenStream2::new()
+    };
+
+    let conversion_impl = if encoding.conversions {
+        encode_conversion_impl(&input, &import)
+    } else {
+        TokenStream2::new()
+    };
+
+    let canonical_impl = if encoding.canonical {
+        encode_canonical_impl(&input, &import, &vis)
+    } else {
+        TokenStream2::new()
+    };
+
+    let size_impl = if encoding.const_size {
+        encoded_size_impl(&input, global_param.clone(), &vis)?
+    } else {
+        TokenStream2::new()
+    };
+    let max_len_impl = encoding
+        .max_encoded_len
+        .map(|limit| encode_max_len_impl(&input, limit, &vis))
+        .unwrap_or_default();
+    let size_hint_impl = if encoding.size_hint {
+        size_hint_impl(&input, global_param.clone(), &import, &vis)?
+    } else {
+        TokenStream2::new()
+    };
+
+    let helpers = encoding.scoped(quote! {
+        #dyn_impl
+        #append_impl
+        #serialize_impl
+        #len_impl
+        #to_vec_impl
+        #canonical_impl
+        #size_impl
+        #max_len_impl
+        #size_hint_impl
+    });
+
+    let impl_encode = if let Some(surrogate) = encoding.into {
+        let (impl_generics, ty_generics, where_clause) =
+            input.generics.split_for_impl();
+        encode_surrogate_impl(
+            surrogate,
             ident_name,
-            global_param,
+            &import,
+            inline,
             impl_generics,
             ty_generics,
             where_clause,
-        ),
-        //strict_encode_inner_enum(&input, &data),
-        Data::Union(_) => Err(Error::new_spanned(
+        )?
+    } else {
+        let generics = bounded_generics(
             &input,
-            "Deriving StrictEncode is not supported in unions",
-        )),
+            quote! { #encode_trait },
+            encoding.encode_bound.as_ref(),
+            &encoding.encode_no_bound,
+            &encoded_types(&input)?,
+        )?;
+
+        match input.data {
+            Data::Struct(data) => encode_struct_impl(
+                data,
+                ident_name,
+                &import,
+                global_param,
+                &generics,
+                tag,
+            )?,
+            Data::Enum(data) => encode_enum_impl(
+                data,
+                ident_name,
+                &import,
+                global_param,
+                &generics,
+                tag,
+            )?,
+            //strict_encode_inner_enum(&input, &data),
+            Data::Union(data) => encode_union_impl(
+                data,
+                ident_name,
+                &import,
+                global_param,
+                &generics,
+                tag,
+            )?,
+        }
+    };
+
+    Ok(quote! {
+        #impl_encode
+        #conversion_impl
+        #helpers
+        #roundtrip_test
+    })
+}
+
+fn encode_dyn_impl(
+    input: &DeriveInput,
+    import: &Import,
+    inline: &TokenStream2,
+    vis: &TokenStream2,
+) -> TokenStream2 {
+    let encode_trait = &import.encode_trait;
+    let encode_dyn = import.method("encode_dyn");
+    let encode = import.method("encode");
+    let ident_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+
+    quote! {
+        #[allow(clippy::all, unused_qualifications)]
+        impl #impl_generics #ident_name #ty_generics #where_clause {
+            /// Object-safe version of `StrictEncode::strict_encode`, which
+            /// can be called through a trait object.
+            #inline
+            #vis fn #encode_dyn(&self, e: &mut dyn ::std::io::Write) -> Result<usize, #import::Error>
+            where
+                Self: #encode_trait,
+            {
+                #encode_trait::#encode(self, e)
+            }
+        }
     }
 }
 
-fn encode_struct_impl(
-    data: DataStruct,
+fn encode_append_impl(
+    input: &DeriveInput,
+    import: &Import,
+    inline: &TokenStream2,
+    vis: &TokenStream2,
+) -> TokenStream2 {
+    let encode_trait = &import.encode_trait;
+    let encode_append = import.method("encode_append");
+    let encode = import.method("encode");
+    let ident_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+
+    quote! {
+        #[allow(clippy::all, unused_qualifications)]
+        impl #impl_generics #ident_name #ty_generics #where_clause {
+            /// Appends strict encoding of the value to the end of the existing
+            /// buffer, returning the number of bytes written.
+            #inline
+            #vis fn #encode_append(&self, buf: &mut Vec<u8>) -> Result<usize, #import::Error>
+            where
+                Self: #encode_trait,
+            {
+                #encode_trait::#encode(self, buf)
+            }
+        }
+    }
+}
+
+fn encode_serialize_impl(
+    input: &DeriveInput,
+    import: &Import,
+    vis: &TokenStream2,
+) -> TokenStream2 {
+    let encode_trait = &import.encode_trait;
+    let serialize = import.method("serialize");
+    let ident_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+
+    quote! {
+        #[allow(clippy::all, unused_qualifications)]
+        impl #impl_generics #ident_name #ty_generics #where_clause {
+            /// Serializes the value into a newly allocated byte vector using
+            /// its strict encoding.
+            #vis fn #serialize(&self) -> Result<Vec<u8>, #import::Error>
+            where
+                Self: #encode_trait,
+            {
+                #encode_trait::#serialize(self)
+            }
+        }
+    }
+}
+
+/// Generates `From<&T> for Vec<u8>` conversion serializing the value. Encoding
+/// into a `Vec` can fail only if the value itself refuses to be encoded, in
+/// which case the conversion panics.
+fn encode_conversion_impl(
+    input: &DeriveInput,
+    import: &Import,
+) -> TokenStream2 {
+    let encode_trait = &import.encode_trait;
+    let serialize = import.method("serialize");
+    let ident_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+    let mut where_clause =
+        where_clause.cloned().unwrap_or_else(|| WhereClause {
+            where_token: Default::default(),
+            predicates: Default::default(),
+        });
+    where_clause
+        .predicates
+        .push(parse_quote! { #ident_name #ty_generics: #encode_trait });
+
+    quote! {
+        #[allow(clippy::all, unused_qualifications)]
+        impl #impl_generics ::core::convert::From<&#ident_name #ty_generics> for ::std::vec::Vec<u8> #where_clause {
+            fn from(value: &#ident_name #ty_generics) -> Self {
+                #encode_trait::#serialize(value)
+                    .expect("strict encoding into a Vec<u8> must not fail")
+            }
+        }
+    }
+}
+
+fn encode_max_len_impl(
+    input: &DeriveInput,
+    limit: u64,
+    vis: &TokenStream2,
+) -> TokenStream2 {
+    let ident_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+    let limit = Literal::u64_unsuffixed(limit);
+
+    quote! {
+        #[allow(clippy::all, unused_qualifications)]
+        impl #impl_generics #ident_name #ty_generics #where_clause {
+            /// Maximal length of the strict encoding of the value in bytes,
+            /// enforced both on encoding and decoding.
+            #vis const STRICT_MAX_LEN: usize = #limit;
+        }
+    }
+}
+
+fn encode_len_impl(
+    input: &DeriveInput,
+    import: &Import,
+    vis: &TokenStream2,
+) -> TokenStream2 {
+    let encode_trait = &import.encode_trait;
+    let serialized_len = import.method("serialized_len");
+    let encode = import.method("encode");
+    let ident_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+
+    quote! {
+        #[allow(clippy::all, unused_qualifications)]
+        impl #impl_generics #ident_name #ty_generics #where_clause {
+            /// Returns length of the strict encoding of the value, which is
+            /// computed by encoding it into a counting writer, without
+            /// allocating a buffer.
+            ///
+            /// # Panics
+            ///
+            /// If the value can't be strict encoded.
+            #vis fn #serialized_len(&self) -> usize
+            where
+                Self: #encode_trait,
+            {
+                struct __StrictCounter(usize);
+                impl ::std::io::Write for __StrictCounter {
+                    fn write(&mut self, buf: &[u8]) -> ::std::io::Result<usize> {
+                        self.0 += buf.len();
+                        Ok(buf.len())
+                    }
+                    fn flush(&mut self) -> ::std::io::Result<()> {
+                        Ok(())
+                    }
+                }
+
+                let mut counter = __StrictCounter(0);
+                let len = #encode_trait::#encode(self, &mut counter)
+                    .expect("value can't be strict encoded");
+                debug_assert_eq!(len, counter.0);
+                counter.0
+            }
+        }
+    }
+}
+
+/// Generates inherent `strict_encode_to_vec` method encoding the value into a
+/// vector pre-allocated with the exact length of the encoding, counted with an
+/// additional encoding pass if `len_helper` is given, or with the lower bound
+/// of the length known at compile time otherwise.
+fn encode_to_vec_impl(
+    input: &DeriveInput,
+    global_param: ParametrizedAttr,
+    import: &Import,
+    exact: bool,
+    vis: &TokenStream2,
+) -> Result<TokenStream2> {
+    let encode_trait = &import.encode_trait;
+    let encode = import.method("encode");
+    let encode_to_vec = import.method("encode_to_vec");
+    let ident_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+    let capacity = if exact {
+        quote! { #encode_trait::#encode(self, ::std::io::sink())? }
+    } else {
+        lower_bound_size(input, global_param)?
+    };
+
+    Ok(quote! {
+        #[allow(clippy::all, unused_qualifications)]
+        impl #impl_generics #ident_name #ty_generics #where_clause {
+            /// Serializes the value into a byte vector pre-allocated for its
+            /// strict encoding.
+            #vis fn #encode_to_vec(&self) -> Result<Vec<u8>, #import::Error>
+            where
+                Self: #encode_trait,
+            {
+                let mut __strict_buf = Vec::with_capacity(#capacity);
+                #encode_trait::#encode(self, &mut __strict_buf)?;
+                Ok(__strict_buf)
+            }
+        }
+    })
+}
+
+fn encode_canonical_impl(
+    input: &DeriveInput,
+    import: &Import,
+    vis: &TokenStream2,
+) -> TokenStream2 {
+    let encode_trait = &import.encode_trait;
+    let decode_trait = &import.decode_trait;
+    let is_canonical = import.method("is_canonical");
+    let deserialize = import.method("deserialize");
+    let serialize = import.method("serialize");
+    let ident_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+
+    quote! {
+        #[allow(clippy::all, unused_qualifications)]
+        impl #impl_generics #ident_name #ty_generics #where_clause {
+            /// Checks that the data are the canonical encoding of a value,
+            /// i.e. that the value decoded from them is encoded back into
+            /// exactly the same bytes. Fails if the data can't be decoded.
+            #vis fn #is_canonical(bytes: &[u8]) -> Result<bool, #import::Error>
+            where
+                Self: #encode_trait + #decode_trait,
+            {
+                let value = <Self as #decode_trait>::#deserialize(bytes)?;
+                Ok(#encode_trait::#serialize(&value)? == bytes)
+            }
+        }
+    }
+}
+
+fn encode_surrogate_impl(
+    surrogate: Type,
     ident_name: &Ident,
-    mut global_param: ParametrizedAttr,
+    import: &Import,
+    inline: TokenStream2,
     impl_generics: ImplGenerics,
     ty_generics: TypeGenerics,
     where_clause: Option<&WhereClause>,
 ) -> Result<TokenStream2> {
-    let encoding = EncodingDerive::try_from(&mut global_param, true, false)?;
+    let encode_trait = &import.encode_trait;
+    let encode = import.method("encode");
+    let mut where_clause =
+        where_clause.cloned().unwrap_or_else(|| WhereClause {
+            where_token: Default::default(),
+            predicates: Default::default(),
+        });
+    where_clause
+        .predicates
+        .push(parse_quote! { #surrogate: #encode_trait });
+    where_clause.predicates.push(parse_quote! {
+        Self: ::core::clone::Clone + ::core::convert::Into<#surrogate>
+    });
 
-    let inner_impl = match data.fields {
-        Fields::Named(ref fields) => {
-            encode_fields_impl(&fields.named, global_param, false)?
+    Ok(quote! {
+        #[allow(clippy::all, unused_qualifications)]
+        impl #impl_generics #encode_trait for #ident_name #ty_generics #where_clause {
+            #inline
+            fn #encode<E: ::std::io::Write>(&self, __strict_e: E) -> Result<usize, #import::Error> {
+                let surrogate: #surrogate = ::core::convert::Into::into(
+                    ::core::clone::Clone::clone(self),
+                );
+                #encode_trait::#encode(&surrogate, __strict_e)
+            }
         }
-        Fields::Unnamed(ref fields) => {
-            encode_fields_impl(&fields.unnamed, global_param, false)?
+    })
+}
+
+fn encode_struct_impl(
+    data: DataStruct,
+    ident_name: &Ident,
+    import: &Import,
+    mut global_param: ParametrizedAttr,
+    generics: &Generics,
+    type_tag: Option<TokenStream2>,
+) -> Result<TokenStream2> {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let encode_trait = &import.encode_trait;
+    let encode = import.method("encode");
+    let encoding = EncodingDerive::try_from(&mut global_param, true, false)?;
+    let inline = encoding.inline_attr();
+    let type_tag_impl = encode_type_tag(type_tag, import);
+
+    if encoding.transparent {
+        if encoding.via_deref {
+            via_deref_check(&data.fields)?;
         }
-        Fields::Unit => quote! { Ok(0) },
+        let (index, field) = transparent_field(&data.fields)?;
+        let name = field
+            .ident
+            .as_ref()
+            .map(Ident::to_token_stream)
+            .unwrap_or_else(|| Index::from(index).to_token_stream());
+        let value = if encoding.via_deref {
+            quote! { ::core::ops::Deref::deref(self) }
+        } else {
+            quote! { &self.#name }
+        };
+
+        return Ok(quote! {
+            #[allow(clippy::all, unused_qualifications)]
+            impl #impl_generics #encode_trait for #ident_name #ty_generics #where_clause {
+                #inline
+                fn #encode<E: ::std::io::Write>(&self, __strict_e: E) -> Result<usize, #import::Error> {
+                    #encode_trait::#encode(#value, __strict_e)
+                }
+            }
+        });
+    }
+
+    let tail_start = optional_tail_start(&data.fields, encoding.optional_tail)?;
+    if tail_start.is_some()
+        && (encoding.checksum.is_some()
+            || encoding.pad_to.is_some()
+            || encoding.align.is_some())
+    {
+        return Err(Error::new(
+            Span::call_site(),
+            "`checksum`, `pad_to` and `align` can't be used with optional tail fields",
+        ));
+    }
+    if tail_start.is_some() && encoding.field_prefix.is_some() {
+        return Err(Error::new(
+            Span::call_site(),
+            "`field_prefix` can't be used with optional tail fields",
+        ));
+    }
+    if tail_start.is_some() && encoding.bitpack {
+        return Err(Error::new(
+            Span::call_site(),
+            "`bitpack` can't be used with optional tail fields",
+        ));
+    }
+    let mut inner_impl = match tail_start {
+        Some(_) => quote! { let mut __strict_tail_none = false; },
+        None => TokenStream2::new(),
     };
+    inner_impl.append_all(match data.fields {
+        ref fields if encoding.batch_writes => {
+            encode_batched_fields_impl(fields, global_param, encoding.version)?
+        }
+        ref fields if encoding.c_layout => {
+            encode_c_layout_fields_impl(fields, import)
+        }
+        Fields::Named(ref fields) => encode_fields_impl(
+            &fields.named,
+            global_param,
+            import,
+            false,
+            encoding.version,
+            tail_start,
+            encoding.bitpack,
+        )?,
+        Fields::Unnamed(ref fields) => encode_fields_impl(
+            &fields.unnamed,
+            global_param,
+            import,
+            false,
+            encoding.version,
+            tail_start,
+            encoding.bitpack,
+        )?,
+        Fields::Unit => quote! {},
+    });
 
-    let import = encoding.use_crate;
+    let inner_impl = match (encoding.length_prefixed, encoding.length_suffix) {
+        (Some(width), _) => encode_length_prefixed(inner_impl, width, import),
+        (None, Some(width)) => {
+            encode_length_suffixed(inner_impl, width, import)
+        }
+        (None, None) => inner_impl,
+    };
+    let magic_impl = encoding.magic.map(encode_magic);
+    let version_repr = &encoding.version_repr;
+    let version_impl = encoding.version.map(|version| {
+        let version = Literal::u64_unsuffixed(version);
+        quote! {
+            __strict_len += <#version_repr as #encode_trait>::#encode(&#version, &mut __strict_e)?;
+        }
+    });
+    // Checksum covers all the data written before it
+    let (checksum_header, checksum_trailer) = match encoding.checksum {
+        Some(_) => {
+            let adapter = crc32_adapter();
+            (
+                quote! {
+                    #adapter
+                    let mut __strict_e = __StrictCrc32::new(__strict_e);
+                },
+                quote! {
+                    __strict_len += #encode_trait::#encode(
+                        &__strict_e.checksum(),
+                        &mut __strict_e.inner,
+                    )?;
+                },
+            )
+        }
+        None => (TokenStream2::new(), TokenStream2::new()),
+    };
+    // Padding follows all the other data, including checksum
+    let padding_impl = encoding.pad_to.map(|block_size| {
+        let type_name = ident_name.unraw().to_string();
+        quote! {
+            if __strict_len as u64 > #block_size {
+                return Err(#import::Error::DataIntegrityError(format!(
+                    "encoded {} data of {} bytes exceed `pad_to` block size of {} bytes",
+                    #type_name, __strict_len, #block_size,
+                )));
+            }
+            ::std::io::copy(
+                &mut ::std::io::Read::take(::std::io::repeat(0), #block_size - __strict_len as u64),
+                &mut __strict_e,
+            )?;
+            __strict_len = #block_size as usize;
+        }
+    });
+    let align_impl = encoding.align.map(|align| {
+        let align = align as usize;
+        quote! {
+            let __strict_pad = (#align - __strict_len % #align) % #align;
+            ::std::io::copy(
+                &mut ::std::io::Read::take(::std::io::repeat(0), __strict_pad as u64),
+                &mut __strict_e,
+            )?;
+            __strict_len += __strict_pad;
+        }
+    });
 
+    let body = encode_limited(
+        quote! {
+            let mut __strict_len = 0;
+            let __strict_data = self;
+            #type_tag_impl
+            #checksum_header
+            #magic_impl
+            #version_impl
+            #inner_impl
+            #checksum_trailer
+            #padding_impl
+            #align_impl
+            Ok(__strict_len)
+        },
+        encoding.max_encoded_len,
+        ident_name,
+        import,
+    );
     Ok(quote! {
-        #[allow(unused_qualifications)]
-        impl #impl_generics #import::StrictEncode for #ident_name #ty_generics #where_clause {
-            fn strict_encode<E: ::std::io::Write>(&self, mut e: E) -> Result<usize, #import::Error> {
-                use #import::StrictEncode;
-                let mut len = 0;
-                let data = self;
-                #inner_impl
-                Ok(len)
+        #[allow(clippy::all, unused_qualifications, unreachable_code)]
+        impl #impl_generics #encode_trait for #ident_name #ty_generics #where_clause {
+            #inline
+            fn #encode<E: ::std::io::Write>(&self, mut __strict_e: E) -> Result<usize, #import::Error> {
+                #body
             }
         }
     })
@@ -96,15 +689,31 @@ fn encode_struct_impl(
 fn encode_enum_impl(
     data: DataEnum,
     ident_name: &Ident,
+    import: &Import,
     mut global_param: ParametrizedAttr,
-    impl_generics: ImplGenerics,
-    ty_generics: TypeGenerics,
-    where_clause: Option<&WhereClause>,
+    generics: &Generics,
+    type_tag: Option<TokenStream2>,
 ) -> Result<TokenStream2> {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let encode_trait = &import.encode_trait;
+    let encode = import.method("encode");
+    let variant_tag = import.method("variant_tag");
     let encoding = EncodingDerive::try_from(&mut global_param, true, true)?;
+    let inline = encoding.inline_attr();
+    if encoding.union_tag.is_some() {
+        return Err(Error::new(
+            Span::call_site(),
+            "`union_tag` can be used with unions only",
+        ));
+    }
+    let repr_max = encoding.repr_max();
+    let no_tag = encoding.no_tag;
     let repr = encoding.repr;
+    let type_tag_impl = encode_type_tag(type_tag, import);
 
     let mut inner_impl = TokenStream2::new();
+    let mut tag_impl = TokenStream2::new();
+    let mut encoded_variants = 0usize;
 
     for (order, variant) in data.variants.iter().enumerate() {
         let mut local_param =
@@ -113,14 +722,44 @@ fn encode_enum_impl(
         // First, test individual attribute
         let _ = EncodingDerive::try_from(&mut local_param, false, true)?;
         // Second, combine global and local together
-        let mut combined = global_param.clone().merged(local_param.clone())?;
-        combined.args.remove("repr");
-        combined.args.remove("crate");
+        let mut combined = EncodingDerive::merged(
+            &global_param,
+            local_param.clone(),
+            variant.span(),
+            "variant",
+            "type",
+        )?;
+        EncodingDerive::strip_global_args(&mut combined);
         let encoding = EncodingDerive::try_from(&mut combined, false, true)?;
+        EncodingDerive::inherit_max_len(&global_param, &mut local_param);
+
+        let ident = &variant.ident;
+        // Explicit variant value always takes precedence over the type-level
+        // `by_order`/`by_value` strategy
+        let value = match (&encoding.value, encoding.by_order) {
+            (Some(val), _) => val.to_token_stream(),
+            (None, true) => Index::from(order).to_token_stream(),
+            (None, false) => quote! { Self::#ident },
+        };
+        tag_impl.append_all(quote_spanned! { variant.span() =>
+            #[allow(deprecated)]
+            Self::#ident { .. } => (#value) as #repr,
+        });
 
         if encoding.skip {
+            let msg = format!(
+                "enum variant `{}` is skipped and can't be encoded",
+                ident.unraw()
+            );
+            inner_impl.append_all(quote_spanned! { variant.span() =>
+                #[allow(deprecated)]
+                Self::#ident { .. } => {
+                    return Err(#import::Error::UnsupportedDataStructure(#msg));
+                }
+            });
             continue;
         }
+        encoded_variants += 1;
 
         let captures = variant
             .fields
@@ -138,11 +777,27 @@ fn encode_enum_impl(
 
         let (field_impl, bra_captures_ket) = match variant.fields {
             Fields::Named(ref fields) => (
-                encode_fields_impl(&fields.named, local_param, true)?,
+                encode_fields_impl(
+                    &fields.named,
+                    local_param,
+                    import,
+                    true,
+                    None,
+                    None,
+                    false,
+                )?,
                 quote! { { #( #captures ),* } },
             ),
             Fields::Unnamed(ref fields) => (
-                encode_fields_impl(&fields.unnamed, local_param, true)?,
+                encode_fields_impl(
+                    &fields.unnamed,
+                    local_param,
+                    import,
+                    true,
+                    None,
+                    None,
+                    false,
+                )?,
                 quote! { ( #( #captures ),* ) },
             ),
             Fields::Unit => (TokenStream2::new(), TokenStream2::new()),
@@ -150,38 +805,259 @@ fn encode_enum_impl(
 
         let captures = match captures.len() {
             0 => quote! {},
-            _ => quote! { let data = ( #( #captures ),* , ); },
+            _ => quote! { let __strict_data = ( #( #captures ),* , ); },
         };
 
-        let ident = &variant.ident;
-        let value = match (encoding.value, encoding.by_order) {
-            (Some(val), _) => val.to_token_stream(),
-            (None, true) => Index::from(order as usize).to_token_stream(),
-            (None, false) => quote! { Self::#ident },
+        // Values which do not fit into `repr` would be silently truncated by
+        // the `as` cast, so we must detect them at compile time
+        if let Some(max) = repr_max {
+            match (&encoding.value, encoding.by_order) {
+                (
+                    Some(Expr::Lit(ExprLit {
+                        lit: Lit::Int(val), ..
+                    })),
+                    _,
+                ) if val.base10_parse::<u64>()? > max => {
+                    return Err(Error::new(
+                        val.span(),
+                        format!(
+                            "enum variant value exceeds maximum value {} of `{}` repr",
+                            max,
+                            repr.to_token_stream()
+                        ),
+                    ))
+                }
+                (None, true) if order as u64 > max => {
+                    return Err(Error::new(
+                        ident_name.span(),
+                        format!(
+                            "number of enum variants exceeds capacity of `{}` repr; \
+                             use larger `repr` or explicit variant values",
+                            repr.to_token_stream()
+                        ),
+                    ))
+                }
+                _ => {}
+            }
+        }
+
+        // Referencing deprecated variants must not produce warnings in the
+        // user crate
+        let allow_deprecated = if variant
+            .attrs
+            .iter()
+            .any(|attr| attr.path.is_ident("deprecated"))
+        {
+            quote! { #[allow(deprecated)] }
+        } else {
+            TokenStream2::new()
         };
 
+        // Encoding of the variant marked with `strict_encoding(deprecated)` is
+        // reported with a warning at the place of the enum declaration
+        let deprecation_warning = if encoding.deprecated {
+            compile_warning(
+                "strict_encoding_of_deprecated_variant",
+                &format!(
+                    "strict encoding of enum variant `{}::{}` is deprecated",
+                    ident_name, ident
+                ),
+                variant.span(),
+            )
+        } else {
+            TokenStream2::new()
+        };
+
+        let tag_encode = if no_tag {
+            TokenStream2::new()
+        } else {
+            quote! {
+                __strict_len += #encode_trait::#encode(&((#value) as #repr), &mut __strict_e)?;
+            }
+        };
         inner_impl.append_all(quote_spanned! { variant.span() =>
+            #allow_deprecated
             Self::#ident #bra_captures_ket => {
-                len += (#value as #repr).strict_encode(&mut e)?;
+                #deprecation_warning
+                #tag_encode
                 #captures
                 #field_impl
             }
         });
     }
 
-    let import = encoding.use_crate;
+    let skip_warning =
+        skipped_enum_warning(ident_name, encoded_variants, data.variants.len());
+    variant_count_check(ident_name, encoded_variants, encoding.count_assert)?;
 
-    Ok(quote! {
-        #[allow(unused_qualifications)]
-        impl #impl_generics #import::StrictEncode for #ident_name #ty_generics #where_clause {
-            #[inline]
-            fn strict_encode<E: ::std::io::Write>(&self, mut e: E) -> Result<usize, #import::Error> {
-                use #import::StrictEncode;
-                let mut len = 0;
-                match self {
-                    #inner_impl
+    // Without the tag written, the enclosing data structure needs to know it
+    let tag_method = if no_tag {
+        quote! {
+            #[allow(clippy::all, unused_qualifications)]
+            impl #impl_generics #ident_name #ty_generics #where_clause {
+                /// Returns tag of the enum variant, which is not written by
+                /// `StrictEncode::strict_encode` and must be provided by the
+                /// enclosing data structure.
+                #inline
+                pub fn #variant_tag(&self) -> #repr {
+                    match self {
+                        #tag_impl
+                    }
                 }
-                Ok(len)
+            }
+        }
+    } else {
+        TokenStream2::new()
+    };
+
+    let body = encode_limited(
+        quote! {
+            let mut __strict_len = 0;
+            #skip_warning
+            #type_tag_impl
+            match self {
+                #inner_impl
+            }
+            Ok(__strict_len)
+        },
+        encoding.max_encoded_len,
+        ident_name,
+        import,
+    );
+    Ok(quote! {
+        #[allow(clippy::all, unused_qualifications, unreachable_code)]
+        impl #impl_generics #encode_trait for #ident_name #ty_generics #where_clause {
+            #inline
+            fn #encode<E: ::std::io::Write>(&self, mut __strict_e: E) -> Result<usize, #import::Error> {
+                #body
+            }
+        }
+
+        #tag_method
+    })
+}
+
+/// Writes magic bytes preceding the encoded data.
+fn encode_magic(magic: Vec<u8>) -> TokenStream2 {
+    let magic_len = magic.len();
+    quote! {
+        ::std::io::Write::write_all(&mut __strict_e, &[ #( #magic ),* ])?;
+        __strict_len += #magic_len;
+    }
+}
+
+/// Encodes data into a temporary buffer and writes it prefixed with its length
+/// represented by `width` integer type.
+fn encode_length_prefixed(
+    inner_impl: TokenStream2,
+    width: Ident,
+    import: &Import,
+) -> TokenStream2 {
+    let encode_trait = &import.encode_trait;
+    let encode = import.method("encode");
+    quote! {
+        let __strict_body = {
+            let mut __strict_e = Vec::<u8>::new();
+            let mut __strict_len = 0usize;
+            #inner_impl
+            debug_assert_eq!(__strict_len, __strict_e.len());
+            __strict_e
+        };
+        if __strict_body.len() > #width::MAX as usize {
+            return Err(#import::Error::ExceedMaxItems(__strict_body.len()));
+        }
+        __strict_len += #encode_trait::#encode(&(__strict_body.len() as #width), &mut __strict_e)?;
+        ::std::io::Write::write_all(&mut __strict_e, &__strict_body)?;
+        __strict_len += __strict_body.len();
+    }
+}
+
+/// Encodes data into a temporary buffer and writes it followed by its length
+/// represented by `width` integer type.
+fn encode_length_suffixed(
+    inner_impl: TokenStream2,
+    width: Ident,
+    import: &Import,
+) -> TokenStream2 {
+    let encode_trait = &import.encode_trait;
+    let encode = import.method("encode");
+    quote! {
+        let __strict_body = {
+            let mut __strict_e = Vec::<u8>::new();
+            let mut __strict_len = 0usize;
+            #inner_impl
+            debug_assert_eq!(__strict_len, __strict_e.len());
+            __strict_e
+        };
+        if __strict_body.len() > #width::MAX as usize {
+            return Err(#import::Error::ExceedMaxItems(__strict_body.len()));
+        }
+        ::std::io::Write::write_all(&mut __strict_e, &__strict_body)?;
+        __strict_len += __strict_body.len();
+        __strict_len += #encode_trait::#encode(&(__strict_body.len() as #width), &mut __strict_e)?;
+    }
+}
+
+fn encode_union_impl(
+    data: DataUnion,
+    ident_name: &Ident,
+    import: &Import,
+    mut global_param: ParametrizedAttr,
+    generics: &Generics,
+    type_tag: Option<TokenStream2>,
+) -> Result<TokenStream2> {
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let encode_trait = &import.encode_trait;
+    let encode = import.method("encode");
+    let encoding = EncodingDerive::try_from(&mut global_param, true, true)?;
+    let inline = encoding.inline_attr();
+    let repr = encoding.repr;
+    let type_tag_impl = encode_type_tag(type_tag, import);
+    let tag = encoding.union_tag.ok_or_else(|| {
+        Error::new(
+            Span::call_site(),
+            "unions require `union_tag` attribute naming the method returning tag of the active field",
+        )
+    })?;
+
+    let mut inner_impl = TokenStream2::new();
+    for (field, value) in union_fields(&data)? {
+        let name = &field.ident;
+        inner_impl.append_all(quote_spanned! { field.span() =>
+            x if x == (#value) as #repr => #encode_trait::#encode(
+                unsafe { &self.#name },
+                &mut __strict_e,
+            )?,
+        });
+    }
+
+    let union_name =
+        LitStr::new(&ident_name.unraw().to_string(), Span::call_site());
+    let body = encode_limited(
+        quote! {
+            let mut __strict_len = 0;
+            #type_tag_impl
+            let __strict_tag: #repr = unsafe { self.#tag() };
+            __strict_len += #encode_trait::#encode(&__strict_tag, &mut __strict_e)?;
+            __strict_len += match __strict_tag {
+                #inner_impl
+                unknown => return Err(#import::Error::DataIntegrityError(format!(
+                    "union {} has unknown tag value {}",
+                    #union_name, unknown
+                ))),
+            };
+            Ok(__strict_len)
+        },
+        encoding.max_encoded_len,
+        ident_name,
+        import,
+    );
+    Ok(quote! {
+        #[allow(clippy::all, unused_qualifications, unused_unsafe)]
+        impl #impl_generics #encode_trait for #ident_name #ty_generics #where_clause {
+            #inline
+            fn #encode<E: ::std::io::Write>(&self, mut __strict_e: E) -> Result<usize, #import::Error> {
+                #body
             }
         }
     })
@@ -189,24 +1065,57 @@ fn encode_enum_impl(
 
 fn encode_fields_impl<'a>(
     fields: impl IntoIterator<Item = &'a Field>,
-    parent_param: ParametrizedAttr,
+    mut parent_param: ParametrizedAttr,
+    import: &Import,
     is_enum: bool,
+    version: Option<u64>,
+    tail_start: Option<usize>,
+    bitpack: bool,
 ) -> Result<TokenStream2> {
-    let mut stream = TokenStream2::new();
+    let encode_trait = &import.encode_trait;
+    let encode = import.method("encode");
+    let serialize = import.method("serialize");
+    let mut stream = order_skew_note(&parent_param, is_enum)?;
+    let mut bitpack = BitPack::new(bitpack);
+    let field_prefix = field_prefix_repr(&parent_param, is_enum)?;
+    let max_len = container_max_len(&parent_param)?;
+
+    let fields = wire_order(fields, &parent_param, is_enum, OrderSide::Encode)?;
+    EncodingDerive::strip_global_args(&mut parent_param);
 
-    for (index, field) in fields.into_iter().enumerate() {
-        let mut local_param = ParametrizedAttr::with(ATTR_NAME, &field.attrs)?;
+    for (index, field) in fields {
+        let mut local_param = field_param(field)?;
 
         // First, test individual attribute
         let _ = EncodingDerive::try_from(&mut local_param, false, is_enum)?;
         // Second, combine global and local together
-        let mut combined = parent_param.clone().merged(local_param)?;
-        combined.args.remove("crate");
-        let encoding = EncodingDerive::try_from(&mut combined, false, is_enum)?;
+        let mut combined = EncodingDerive::merged(
+            &parent_param,
+            local_param,
+            field.span(),
+            "field",
+            if is_enum { "variant" } else { "type" },
+        )?;
+        let mut encoding =
+            EncodingDerive::try_from(&mut combined, false, is_enum)?;
+        if encoding.max_len.is_none()
+            && (encoding.collection.is_some() || is_std_collection(&field.ty))
+        {
+            encoding.max_len = max_len;
+        }
 
-        if encoding.skip {
+        if encoding.skip || !encoding.is_present_in(version)? {
             continue;
         }
+        count_type_check(field, &encoding)?;
+        let is_tail = matches!(tail_start, Some(start) if index >= start);
+        let mut field_stream = TokenStream2::new();
+        if let Some(ref repr) = field_prefix {
+            let prefix = field_prefix_lit(repr, index, field)?;
+            field_stream.append_all(quote_spanned! { field.span() =>
+                __strict_len += #encode_trait::#encode(&(#prefix as #repr), &mut __strict_e)?;
+            });
+        }
 
         let index = Index::from(index).to_token_stream();
         let name = if is_enum {
@@ -218,10 +1127,348 @@ fn encode_fields_impl<'a>(
                 .map(Ident::to_token_stream)
                 .unwrap_or(index)
         };
-        stream.append_all(quote_spanned! { field.span() =>
-            len += data.#name.strict_encode(&mut e)?;
-        })
+        // Enum variant fields are captured by reference
+        let value =
+            field_ref(&field.ty, quote! { __strict_data.#name }, is_enum);
+        if bitpack.packs(field, &encoding)? {
+            stream.append_all(
+                bitpack.encode_bool(quote! { __strict_data.#name }),
+            );
+            continue;
+        }
+        stream.append_all(bitpack.encode_flush());
+        if let (Some(max_len), None) = (encoding.max_len, &encoding.collection)
+        {
+            let max_len = max_len as usize;
+            field_stream.append_all(quote_spanned! { field.span() =>
+                if __strict_data.#name.len() > #max_len {
+                    return Err(#import::Error::ExceedMaxItems(__strict_data.#name.len()));
+                }
+            });
+        }
+        if is_infallible(&field.ty) {
+            // Values containing `Infallible` can't exist, so there is nothing
+            // to encode
+            let ty = &field.ty;
+            field_stream.append_all(quote_spanned! { field.span() =>
+                let __strict_never: &#ty =
+                    ::core::borrow::Borrow::borrow(&__strict_data.#name);
+                match *__strict_never {}
+            })
+        } else if is_tail && encoding.default_on_eof {
+            let msg = format!(
+                "field `{}` can't be present after an absent optional tail field",
+                name
+            );
+            field_stream.append_all(quote_spanned! { field.span() =>
+                if __strict_tail_none {
+                    return Err(#import::Error::DataIntegrityError(
+                        #msg.to_owned(),
+                    ));
+                }
+                __strict_len += #encode_trait::#encode(#value, &mut __strict_e)?;
+            })
+        } else if is_tail {
+            let msg = format!(
+                "optional tail field `{}` can't be present after an absent one",
+                name
+            );
+            field_stream.append_all(quote_spanned! { field.span() =>
+                match __strict_data.#name {
+                    Some(_) if __strict_tail_none => {
+                        return Err(#import::Error::DataIntegrityError(
+                            #msg.to_owned(),
+                        ));
+                    }
+                    Some(ref val) => {
+                        __strict_len += #encode_trait::#encode(val, &mut __strict_e)?;
+                    }
+                    None => __strict_tail_none = true,
+                }
+            })
+        } else if encoding.collection.is_some() || encoding.count_type.is_some()
+        {
+            let max_len_check = encoding.max_len.map(|max_len| {
+                let max_len = max_len as usize;
+                quote! {
+                    if __strict_count > #max_len {
+                        return Err(#import::Error::ExceedMaxItems(__strict_count));
+                    }
+                }
+            });
+            let count = match encoding.count_type {
+                Some(ref count_type) => quote! {
+                    if __strict_count as u64 > #count_type::MAX as u64 {
+                        return Err(#import::Error::ExceedMaxItems(__strict_count));
+                    }
+                    __strict_len += #encode_trait::#encode(
+                        &(__strict_count as #count_type),
+                        &mut __strict_e,
+                    )?;
+                },
+                None => quote! {
+                    __strict_len += #encode_trait::#encode(&__strict_count, &mut __strict_e)?;
+                },
+            };
+            field_stream.append_all(quote_spanned! { field.span() =>
+                let __strict_count = __strict_data.#name.len();
+                #max_len_check
+                #count
+                for __strict_item in __strict_data.#name.iter() {
+                    __strict_len += #encode_trait::#encode(__strict_item, &mut __strict_e)?;
+                }
+            })
+        } else if encoding.tuple_fields {
+            for (index, elem) in
+                tuple_elems(field, &encoding)?.into_iter().enumerate()
+            {
+                let index = Index::from(index);
+                field_stream.append_all(quote_spanned! { elem.span() =>
+                    __strict_len += #encode_trait::#encode(&__strict_data.#name.#index, &mut __strict_e)?;
+                })
+            }
+        } else if encoding.byte_blob {
+            field_stream.append_all(quote_spanned! { field.span() =>
+                let __strict_blob_len = __strict_data.#name.len();
+                if __strict_blob_len > u32::MAX as usize {
+                    return Err(#import::Error::ExceedMaxItems(__strict_blob_len));
+                }
+                __strict_len += #encode_trait::#encode(&(__strict_blob_len as u32), &mut __strict_e)?;
+                ::std::io::Write::write_all(&mut __strict_e, &__strict_data.#name)?;
+                __strict_len += __strict_blob_len;
+            })
+        } else if encoding.sorted_map && is_btree_map(&field.ty) {
+            // `BTreeMap` is encoded with its own implementation, which is
+            // already ordered and is understood by decoders of the fields not
+            // marked with `sorted_map`
+            field_stream.append_all(compile_warning(
+                "sorted_map_has_no_effect_on_btree_map",
+                "`sorted_map` (or `sort_keys`) is not required for `BTreeMap` fields, which are always sorted",
+                field.span(),
+            ));
+            field_stream.append_all(quote_spanned! { field.span() =>
+                __strict_len += #encode_trait::#encode(#value, &mut __strict_e)?;
+            })
+        } else if encoding.sorted_map {
+            field_stream.append_all(quote_spanned! { field.span() =>
+                let mut __strict_entries = Vec::with_capacity(__strict_data.#name.len());
+                for (__strict_key, __strict_val) in __strict_data.#name.iter() {
+                    __strict_entries.push((#encode_trait::#serialize(__strict_key)?, __strict_val));
+                }
+                __strict_entries.sort_by(|a, b| a.0.cmp(&b.0));
+                __strict_len += #encode_trait::#encode(&__strict_entries.len(), &mut __strict_e)?;
+                for (__strict_key, __strict_val) in __strict_entries {
+                    ::std::io::Write::write_all(&mut __strict_e, &__strict_key)?;
+                    __strict_len += __strict_key.len();
+                    __strict_len += #encode_trait::#encode(__strict_val, &mut __strict_e)?;
+                }
+            })
+        } else if elementwise_array(&field.ty).is_some() {
+            field_stream.append_all(quote_spanned! { field.span() =>
+                for __strict_item in __strict_data.#name.iter() {
+                    __strict_len += #encode_trait::#encode(__strict_item, &mut __strict_e)?;
+                }
+            })
+        } else if is_result(&field.ty) {
+            field_stream.append_all(quote_spanned! { field.span() =>
+                match __strict_data.#name {
+                    Ok(ref __strict_ok) => {
+                        __strict_len += #encode_trait::#encode(&0u8, &mut __strict_e)?;
+                        __strict_len += #encode_trait::#encode(__strict_ok, &mut __strict_e)?;
+                    }
+                    Err(ref __strict_err) => {
+                        __strict_len += #encode_trait::#encode(&1u8, &mut __strict_e)?;
+                        __strict_len += #encode_trait::#encode(__strict_err, &mut __strict_e)?;
+                    }
+                }
+            })
+        } else if let Some(borrowed) = cow_borrowed(&field.ty) {
+            // `str` implements strict encoding only by reference
+            let encoder = match borrowed {
+                Type::Path(path) if path.path.is_ident("str") => quote! {
+                    <&str as #encode_trait>::#encode(
+                        &::core::ops::Deref::deref(#value),
+                        &mut __strict_e,
+                    )
+                },
+                _ => quote! {
+                    <#borrowed as #encode_trait>::#encode(
+                        ::core::ops::Deref::deref(#value),
+                        &mut __strict_e,
+                    )
+                },
+            };
+            field_stream.append_all(quote_spanned! { field.span() =>
+                __strict_len += #encoder?;
+            })
+        } else {
+            field_stream.append_all(quote_spanned! { field.span() =>
+                __strict_len += #encode_trait::#encode(#value, &mut __strict_e)?;
+            })
+        }
+
+        match encoding.skip_if_const {
+            Some(condition) => {
+                stream.append_all(quote_spanned! { field.span() =>
+                    if !(#condition) {
+                        #field_stream
+                    }
+                })
+            }
+            None => stream.append_all(field_stream),
+        }
     }
 
+    stream.append_all(bitpack.encode_flush());
+
     Ok(stream)
 }
+
+/// Returns reference to the field value, which is passed to the
+/// `StrictEncode` implementation of the field type. Fields of reference types
+/// are encoded with the implementation of the referenced type, apart from
+/// `&str`, which implements strict encoding only by reference. Fields
+/// `captured` by reference, like the fields of enum variants, are already
+/// referenced once.
+pub(crate) fn field_ref(
+    ty: &Type,
+    value: TokenStream2,
+    captured: bool,
+) -> TokenStream2 {
+    match (ty, captured) {
+        (Type::Reference(reference), false) if !is_str(&reference.elem) => {
+            value
+        }
+        (Type::Reference(reference), true) if !is_str(&reference.elem) => {
+            quote! { *#value }
+        }
+        (_, false) => quote! { &#value },
+        (_, true) => value,
+    }
+}
+
+fn is_str(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident("str"))
+}
+
+/// Encodes fields of `#[repr(C)]` structure matching their memory layout, with
+/// zero bytes written in place of padding. Padding between the fields never
+/// exceeds alignment of primitive types, which is at most 16 bytes, while the
+/// trailing padding of `align(N)` structures is written in chunks.
+fn encode_c_layout_fields_impl(
+    fields: &Fields,
+    import: &Import,
+) -> TokenStream2 {
+    let encode_trait = &import.encode_trait;
+    let encode = import.method("encode");
+    let mut stream = quote! {
+        let mut __strict_offset = 0usize;
+        let __strict_zeros = [0u8; 16];
+    };
+    for (index, field) in fields.iter().enumerate() {
+        let name = field
+            .ident
+            .as_ref()
+            .map(Ident::to_token_stream)
+            .unwrap_or_else(|| Index::from(index).to_token_stream());
+        let padding = c_layout_padding(&field.ty);
+        let value = if let Type::Array(_) = field.ty {
+            quote_spanned! { field.span() =>
+                for __strict_item in __strict_data.#name.iter() {
+                    __strict_len += #encode_trait::#encode(__strict_item, &mut __strict_e)?;
+                }
+            }
+        } else {
+            quote_spanned! { field.span() =>
+                __strict_len += #encode_trait::#encode(&__strict_data.#name, &mut __strict_e)?;
+            }
+        };
+        stream.append_all(quote_spanned! { field.span() =>
+            #padding
+            ::std::io::Write::write_all(&mut __strict_e, &__strict_zeros[..__strict_pad])?;
+            __strict_len += __strict_pad;
+            #value
+        });
+    }
+    // Trailing padding of structures with `align(N)` representation may be
+    // longer than the buffer of zeros
+    stream.append_all(quote! {
+        let mut __strict_pad = ::core::mem::size_of::<Self>() - __strict_offset;
+        while __strict_pad > 0 {
+            let __strict_chunk = __strict_pad.min(__strict_zeros.len());
+            ::std::io::Write::write_all(&mut __strict_e, &__strict_zeros[..__strict_chunk])?;
+            __strict_len += __strict_chunk;
+            __strict_pad -= __strict_chunk;
+        }
+    });
+    stream
+}
+
+/// Encodes fields of primitive types into a single stack-allocated buffer,
+/// which is then written with a single `write_all` call.
+fn encode_batched_fields_impl(
+    fields: &Fields,
+    mut parent_param: ParametrizedAttr,
+    version: Option<u64>,
+) -> Result<TokenStream2> {
+    let mut stream = order_skew_note(&parent_param, false)?;
+    let mut offset = 0usize;
+
+    let fields = wire_order(fields, &parent_param, false, OrderSide::Encode)?;
+    EncodingDerive::strip_global_args(&mut parent_param);
+
+    for (index, field) in fields {
+        let mut local_param = field_param(field)?;
+
+        // First, test individual attribute
+        let _ = EncodingDerive::try_from(&mut local_param, false, false)?;
+        // Second, combine global and local together
+        let mut combined = EncodingDerive::merged(
+            &parent_param,
+            local_param,
+            field.span(),
+            "field",
+            "type",
+        )?;
+        let encoding = EncodingDerive::try_from(&mut combined, false, false)?;
+
+        if encoding.skip || !encoding.is_present_in(version)? {
+            continue;
+        }
+        if encoding.skip_if_const.is_some() {
+            return Err(Error::new(
+                field.span(),
+                "`batch_writes` can't be used with `skip_if_const` fields",
+            ));
+        }
+
+        let (size, is_bool) = primitive_size(&field.ty).ok_or_else(|| {
+            Error::new(
+                field.span(),
+                "`batch_writes` requires all encoded fields to be of integer, float or bool type",
+            )
+        })?;
+        let name = field
+            .ident
+            .as_ref()
+            .map(Ident::to_token_stream)
+            .unwrap_or_else(|| Index::from(index).to_token_stream());
+        let bytes = if is_bool {
+            quote_spanned! { field.span() => [__strict_data.#name as u8] }
+        } else {
+            quote_spanned! { field.span() => __strict_data.#name.to_le_bytes() }
+        };
+        let end = offset + size;
+        stream.append_all(quote_spanned! { field.span() =>
+            __strict_buf[#offset..#end].copy_from_slice(&#bytes);
+        });
+        offset = end;
+    }
+
+    Ok(quote! {
+        let mut __strict_buf = [0u8; #offset];
+        #stream
+        ::std::io::Write::write_all(&mut __strict_e, &__strict_buf)?;
+        __strict_len += #offset;
+    })
+}
@@ -0,0 +1,153 @@
+// LNP/BP client-side-validation library implementing respective LNPBP
+// specifications & standards (LNPBP-7, 8, 9, 42)
+//
+// Written in 2019-2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the Apache 2.0 License along with this
+// software. If not, see <https://opensource.org/licenses/Apache-2.0>.
+
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::ToTokens;
+use syn::{Attribute, Data, DeriveInput, Fields, Ident, Path};
+
+use crate::ATTR_NAME;
+
+/// Encoding derived with the strict encoding derivation machinery: either the
+/// strict encoding itself, or its flavor using its own traits and attribute.
+pub(crate) struct Flavor {
+    /// Name of the attribute configuring derivation
+    attr_name: &'static str,
+    /// Name of the crate providing the traits, used unless `crate` argument
+    /// is given
+    crate_name: &'static str,
+    /// Name of the encoding trait, like `StrictEncode`
+    encode_trait: &'static str,
+    /// Name of the decoding trait, like `StrictDecode`
+    decode_trait: &'static str,
+    /// Prefix of the trait methods and generated inherent methods, like
+    /// `strict_`
+    prefix: &'static str,
+}
+
+/// Strict encoding, configured with `#[strict_encoding(...)]` attribute.
+pub(crate) const STRICT: Flavor = Flavor {
+    attr_name: ATTR_NAME,
+    crate_name: "strict_encoding",
+    encode_trait: "StrictEncode",
+    decode_trait: "StrictDecode",
+    prefix: "strict_",
+};
+
+/// Network encoding, configured with `#[network_encoding(...)]` attribute.
+pub(crate) const NETWORK: Flavor = Flavor {
+    attr_name: "network_encoding",
+    crate_name: "network_encoding",
+    encode_trait: "NetworkEncode",
+    decode_trait: "NetworkDecode",
+    prefix: "network_",
+};
+
+/// Confined encoding, configured with `#[confined_encoding(...)]` attribute.
+pub(crate) const CONFINED: Flavor = Flavor {
+    attr_name: "confined_encoding",
+    crate_name: "confined_encoding",
+    encode_trait: "ConfinedEncode",
+    decode_trait: "ConfinedDecode",
+    prefix: "confined_",
+};
+
+impl Flavor {
+    /// Prepares type for the strict encoding derivation machinery by
+    /// replacing its `strict_encoding` attributes with the attributes of the
+    /// flavor, at all the type, enum variant and field levels.
+    pub(crate) fn input(&self, mut input: DeriveInput) -> DeriveInput {
+        if self.attr_name == ATTR_NAME {
+            return input;
+        }
+        self.swap_attrs(&mut input.attrs);
+        match input.data {
+            Data::Struct(ref mut data) => {
+                self.swap_field_attrs(&mut data.fields)
+            }
+            Data::Enum(ref mut data) => {
+                for variant in &mut data.variants {
+                    self.swap_attrs(&mut variant.attrs);
+                    self.swap_field_attrs(&mut variant.fields);
+                }
+            }
+            Data::Union(ref mut data) => {
+                for field in &mut data.fields.named {
+                    self.swap_attrs(&mut field.attrs);
+                }
+            }
+        }
+        input
+    }
+
+    fn swap_field_attrs(&self, fields: &mut Fields) {
+        for field in fields.iter_mut() {
+            self.swap_attrs(&mut field.attrs);
+        }
+    }
+
+    fn swap_attrs(&self, attrs: &mut Vec<Attribute>) {
+        attrs.retain(|attr| !attr.path.is_ident(ATTR_NAME));
+        for attr in attrs.iter_mut() {
+            if attr.path.is_ident(self.attr_name) {
+                attr.path =
+                    Ident::new(ATTR_NAME, attr.path.segments[0].ident.span())
+                        .into();
+            }
+        }
+    }
+
+    /// Returns paths and names used by the generated code, with the crate
+    /// path given with `crate` argument, if any.
+    pub(crate) fn import(&self, use_crate: Option<&Path>) -> Import {
+        let path = use_crate.cloned().unwrap_or_else(|| {
+            Ident::new(self.crate_name, Span::call_site()).into()
+        });
+        let encode_trait = Ident::new(self.encode_trait, Span::call_site());
+        let decode_trait = Ident::new(self.decode_trait, Span::call_site());
+        Import {
+            encode_trait: parse_quote! { #path::#encode_trait },
+            decode_trait: parse_quote! { #path::#decode_trait },
+            path,
+            prefix: self.prefix,
+        }
+    }
+}
+
+/// Paths and names of the flavor used by the generated code. Converts into
+/// tokens of the crate path, so the crate items are referenced in the
+/// generated code as `#import::Error`.
+#[derive(Clone)]
+pub(crate) struct Import {
+    /// Path to the crate providing the traits
+    pub path: Path,
+    /// Path to the encoding trait
+    pub encode_trait: Path,
+    /// Path to the decoding trait
+    pub decode_trait: Path,
+    prefix: &'static str,
+}
+
+impl Import {
+    /// Returns name of the trait method or of the generated inherent method
+    /// with the flavor prefix, like `strict_encode` for `encode`.
+    pub(crate) fn method(&self, name: &str) -> Ident {
+        Ident::new(&format!("{}{}", self.prefix, name), Span::call_site())
+    }
+}
+
+impl ToTokens for Import {
+    fn to_tokens(&self, tokens: &mut TokenStream2) {
+        self.path.to_tokens(tokens)
+    }
+}
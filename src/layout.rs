@@ -0,0 +1,86 @@
+// LNP/BP client-side-validation library implementing respective LNPBP
+// specifications & standards (LNPBP-7, 8, 9, 42)
+//
+// Written in 2019-2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the Apache 2.0 License along with this
+// software. If not, see <https://opensource.org/licenses/Apache-2.0>.
+
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Error, Meta, Result, Token, Type};
+
+use crate::param::field_param;
+use crate::size::primitive_size;
+
+/// Checks that the structure marked with `c_layout` is `#[repr(C)]` (possibly
+/// with `align(N)`, but not `packed`) and all its fields are of primitive
+/// integer, float or bool types or arrays of them, without field-level
+/// attribute arguments.
+pub(crate) fn c_layout_check(input: &DeriveInput) -> Result<()> {
+    let fields = match input.data {
+        Data::Struct(ref data) => &data.fields,
+        _ => unreachable!("`c_layout` is not allowed for enums and unions"),
+    };
+
+    let mut reprs = vec![];
+    for attr in input.attrs.iter().filter(|attr| attr.path.is_ident("repr")) {
+        reprs.extend(attr.parse_args_with(
+            Punctuated::<Meta, Token![,]>::parse_terminated,
+        )?);
+    }
+    if !reprs.iter().any(|repr| repr.path().is_ident("C")) {
+        return Err(Error::new(
+            Span::call_site(),
+            "`c_layout` requires the structure to be `#[repr(C)]`",
+        ));
+    }
+    if let Some(packed) =
+        reprs.iter().find(|repr| repr.path().is_ident("packed"))
+    {
+        return Err(Error::new(
+            packed.span(),
+            "`c_layout` can't be used with packed structures",
+        ));
+    }
+
+    for field in fields.iter() {
+        let param = field_param(field)?;
+        if !param.args.is_empty() || !param.paths.is_empty() {
+            return Err(Error::new(
+                field.span(),
+                "`c_layout` structures can't have field-level attribute arguments",
+            ));
+        }
+        let plain = match field.ty {
+            Type::Array(ref array) => primitive_size(&array.elem).is_some(),
+            ref ty => primitive_size(ty).is_some(),
+        };
+        if !plain {
+            return Err(Error::new(
+                field.ty.span(),
+                "`c_layout` requires all fields to be of integer, float or bool type or arrays of them",
+            ));
+        }
+    }
+    Ok(())
+}
+
+/// Returns statements computing number of padding bytes `__strict_pad`
+/// preceding the field of the given type in memory and advancing
+/// `__strict_offset` past the field.
+pub(crate) fn c_layout_padding(ty: &Type) -> TokenStream2 {
+    quote_spanned! { ty.span() =>
+        let __strict_align = ::core::mem::align_of::<#ty>();
+        let __strict_pad =
+            (__strict_align - __strict_offset % __strict_align) % __strict_align;
+        __strict_offset += __strict_pad + ::core::mem::size_of::<#ty>();
+    }
+}
@@ -24,6 +24,23 @@
 //! Library exports derivation macros `#[derive(`[`StrictEncode`]`)]` and
 //! `#[derive(`[`StrictDecode`]`)]`, which can be added on top of any structure
 //! you'd like to support string encoding (see Example section below).
+//! `#[derive(`[`StrictEncoding`]`)]` derives both implementations at once, so
+//! they are always generated from the same attributes; one of them can be
+//! omitted with `encode_only` or `decode_only` type-level argument.
+//!
+//! Additional `#[derive(`[`StrictDumb`]`)]` macro generates placeholder values
+//! of the types (see Placeholder values section below), and
+//! `#[derive(NetworkEncode, NetworkDecode)]` and
+//! `#[derive(ConfinedEncode, ConfinedDecode)]` macros derive network and
+//! confined encodings (see Network and confined encodings section below).
+//! `#[derive(CommitEncode)]` derives commitment encoding based on the strict
+//! encoding (see Commitment encoding section below).
+//!
+//! The generated code calls the trait methods with fully-qualified syntax and
+//! does not import the traits, so the macros can be used for types declared
+//! inside function bodies, next to local items named like the traits, and
+//! inherent methods of field types named like the trait methods (e.g.
+//! `strict_encode`) are never called instead of the trait implementations.
 //!
 //! Encoding/decoding implemented by both of these macros may be configured at
 //! type and individual field level using `#[strict_encoding(...)]` attribute
@@ -41,7 +58,10 @@
 //!
 //! ### `crate = ::path::to::strict_encoding_crate`
 //!
-//! Allows to specify custom path to `strict_encoding` crate
+//! Allows to specify custom path to `strict_encoding` crate. Any module path
+//! can be used, including relative ones, like `crate = super::strict_encoding`
+//! or `crate = crate::deps::strict_encoding`, which is useful when the crate is
+//! re-exported by some other module.
 //!
 //! ### `repr = <uint>`
 //!
@@ -50,10 +70,664 @@
 //! Specifies which unsigned integer type must represent enum variants during
 //! the encoding. Possible values are `u8`, `u16`, `u32` and `u64`.
 //!
+//! The argument also accepts a path to a type alias resolving to one of these
+//! integer types (like `repr = Tag` with `type Tag = u16;`). In this case the
+//! macro can't know the actual integer width, so the validation of the type
+//! and of the variant value range is disabled and left to the compiler.
+//!
 //! NB: This argument is not equal to the rust `#[repr(...)]` attribute, which
 //! defines C FFI representation of the enum type. For their combined usage
 //! pls check examples below
 //!
+//! ### `transparent`
+//!
+//! Can be used with structures only.
+//!
+//! Makes encoding of the structure fully delegated to its only non-skipped
+//! field, such that the structure is encoded exactly as the inner type, without
+//! any additional bytes. Works for both tuple (`struct Wrapper(Inner);`) and
+//! named (`struct Wrapper { inner: Inner }`) structures. Skipped fields, if
+//! present, are initialized with `Default::default()` upon decoding.
+//!
+//! The macro will error if the structure has more than one non-skipped field.
+//!
+//! ### `via_deref`
+//!
+//! Can be used with structures only.
+//!
+//! Variant of `transparent` for single-field wrappers implementing
+//! `Deref`: the value is encoded by calling `StrictEncode` on the
+//! `Deref::Target` returned by `deref()`, and the only field is decoded with
+//! the `StrictDecode` implementation of the same target type. The structure
+//! must have exactly one field, and must implement `Deref` with the type of
+//! that field as a target; otherwise the generated code will not compile.
+//! Has the same restrictions on combining with other attributes as
+//! `transparent`.
+//!
+//! ### `emit_decode_into`
+//!
+//! Can be used with structures only.
+//!
+//! Generates additional inherent method
+//! `fn strict_decode_into<D: Read>(&mut self, d: D) -> Result<(), Error>`,
+//! which decodes data into an existing value in place, field by field. This
+//! allows to re-use allocations of the existing value (like capacity of `Vec`
+//! fields). Skipped fields are left untouched.
+//!
+//! ### `into = "Type"`, `try_from = "Type"`
+//!
+//! Encode and decode the type through a surrogate type, similarly to serde
+//! `#[serde(into, try_from)]` attributes. This allows to keep rich in-memory
+//! representation (with caches etc.) while controlling exactly which data are
+//! serialized.
+//!
+//! With `into` encoding clones the value, converts it into the surrogate type
+//! and encodes the surrogate; requires `Self: Clone + Into<Type>` and
+//! `Type: StrictEncode`. With `try_from` decoding decodes the surrogate type
+//! and converts it into `Self` with `TryFrom`, reporting conversion errors as
+//! `Error::DataIntegrityError`; requires `Self: TryFrom<Type>` with error type
+//! implementing `Display`, and `Type: StrictDecode`. These requirements are
+//! added to the generated `where` clauses.
+//!
+//! ### `error_type = "path::to::Error"`
+//!
+//! Makes the `StrictDecode` derive generate inherent
+//! `fn strict_decode<D: Read>(d: D) -> Result<Self, path::to::Error>` method,
+//! which decodes the value with the `StrictDecode` implementation and converts
+//! decoding errors into the provided error type with `From`. Being inherent,
+//! the method takes precedence over the trait one when called as
+//! `Type::strict_decode`, while the trait implementation itself keeps returning
+//! `strict_encoding::Error`, as required by the trait. With `helpers` argument,
+//! the inherent `strict_deserialize` method returns the provided error type as
+//! well. The error type must implement `From<strict_encoding::Error>`, which is
+//! checked at compile time. Can't be used with `no_tag` and `length_suffix`
+//! arguments.
+//!
+//! ```compile_fail
+//! # #[macro_use] extern crate strict_encoding_derive;
+//! pub struct MyError;
+//!
+//! #[derive(StrictDecode)]
+//! #[strict_encoding(error_type = "MyError")]
+//! struct Ping {
+//!     nonce: u64,
+//! }
+//! ```
+//!
+//! ### `construct_with = "path::to::constructor"`
+//!
+//! Can be used with structures only.
+//!
+//! Instead of constructing decoded structure with a struct literal, decodes
+//! all non-skipped fields into local variables in the order of their
+//! declaration and passes them to the provided constructor, like
+//! `Self::with_parts`. The constructor must return `Result<Self, E>`, where
+//! `E: Display`; its errors are reported as `Error::DataIntegrityError`.
+//!
+//! Private fields by themselves do not require a constructor: the derived
+//! implementations are placed into the module declaring the type, so they can
+//! access its private fields. The constructor is needed when the decoded values
+//! have to be checked against the type invariants, which a struct literal
+//! would bypass.
+//!
+//! ### `magic = <integer or byte string>`
+//!
+//! Can be used with structures only.
+//!
+//! Prefixes encoded data with a fixed magic constant, such that misrouted data
+//! are rejected upon decoding immediately. The value may be given either as an
+//! integer literal with unsigned type suffix (like `magic = 0x5354_4B31u32`),
+//! which is written in its strict encoding, or as a byte string literal (like
+//! `magic = b"RGB\x01"`), which is written as is. Upon decoding, mismatching
+//! magic bytes result in `Error::DataIntegrityError` naming the type and
+//! showing both expected and actual bytes. Magic bytes are counted towards the
+//! encoded data length.
+//!
+//! ### `length_prefixed = <uint>`
+//!
+//! Can be used with structures only.
+//!
+//! Prefixes encoding of all structure fields with its length in bytes,
+//! represented with the provided unsigned integer type (`u8`, `u16`, `u32` or
+//! `u64`). Upon decoding, the fields are read strictly from the specified
+//! number of bytes: attempts to read past this boundary result in an I/O error,
+//! and bytes left unread result in `Error::DataNotEntirelyConsumed`. This
+//! allows parsers to skip records with unknown inner format, making streams
+//! forward-compatible. If `magic` is also specified, magic bytes precede the
+//! length prefix.
+//!
+//! This is the framing known as length delimiter in many network protocols,
+//! like `length_prefixed = u32` for 32-bit delimiters. The fields are encoded
+//! into a temporary buffer first, so the field types need no changes.
+//!
+//! ### `length_suffix = <uint>`
+//!
+//! Can be used with structures only.
+//!
+//! Writes encoding of all structure fields followed by its length in bytes,
+//! represented with the provided unsigned integer type. Such records can be
+//! appended to logs and read backwards, starting from the end of the data.
+//! Since the length can't be known before the record is read, the structure
+//! does not implement `StrictDecode`: decoding is done with generated
+//! `strict_decode_from_slice(&[u8])` method instead, which reads the record
+//! located at the end of the slice. Bytes preceding the record are not read,
+//! and bytes of the record left unread result in
+//! `Error::DataNotEntirelyConsumed`. Can't be combined with attributes which
+//! add data before or after the fields, with `length_prefixed` or with the
+//! attributes which require `StrictDecode` implementation.
+//!
+//! ```
+//! # #[macro_use] extern crate strict_encoding_derive;
+//! use strict_encoding::StrictEncode;
+//!
+//! #[derive(Debug, PartialEq, StrictEncode, StrictDecode)]
+//! #[strict_encoding(length_suffix = u16)]
+//! struct Entry {
+//!     id: u8,
+//!     payload: Vec<u8>,
+//! }
+//!
+//! let entry = Entry { id: 7, payload: vec![1, 2] };
+//! let mut log = vec![0xFF, 0xFF];
+//! log.extend(entry.strict_serialize().unwrap());
+//! assert_eq!(log, vec![0xFF, 0xFF, 7, 2, 0, 1, 2, 5, 0]);
+//! assert_eq!(Entry::strict_decode_from_slice(&log).unwrap(), entry);
+//! ```
+//!
+//! ### `field_prefix`, `field_prefix_type = u8|u16|u32|u64`
+//!
+//! Can be used with structures only.
+//!
+//! Writes zero-based index of each encoded field before its data, making the
+//! encoding self-describing, which allows detecting field misalignment when
+//! debugging protocols. Skipped fields and fields absent in the encoded version
+//! have no prefix. Upon decoding, each prefix is checked to match the index of
+//! the field, failing with `Error::DataIntegrityError` otherwise. Prefixes are
+//! `u8` values unless another unsigned integer type is provided with
+//! `field_prefix_type`. Can't be combined with `transparent`, `batch_writes`,
+//! `c_layout`, `bitpack` or optional tail fields.
+//!
+//! ### `ignore_trailing = <unsigned integer>`, `ignore_trailing_all`
+//!
+//! Can be used with structures only.
+//!
+//! After all structure fields are decoded, reads and discards up to the
+//! specified number of bytes (`ignore_trailing`) or all bytes until the end of
+//! the stream (`ignore_trailing_all`). This allows older clients to decode
+//! messages to which newer format versions had appended extra fields. Has no
+//! effect on encoding. When combined with `length_prefixed`, only bytes
+//! remaining within the length-prefixed body are discarded.
+//!
+//! ### `exact`
+//!
+//! Can be used with structures only.
+//!
+//! After all structure fields are decoded, checks that the data end right
+//! after them (or, with `length_prefixed`, that the length-prefixed body is
+//! consumed entirely), failing with `Error::DataNotEntirelyConsumed` otherwise.
+//! This guarantees that the parsed data have no unexpected extensions even if
+//! the type is decoded from a stream rather than with `strict_deserialize`.
+//! Can't be combined with `ignore_trailing` or `ignore_trailing_all`.
+//!
+//! ### `checksum = "crc32"`
+//!
+//! Can be used with structures only.
+//!
+//! Appends checksum computed over all the encoded structure data, including
+//! magic bytes, version and length prefix, if any. Upon decoding the checksum
+//! is computed over the read data and compared with the trailer, failing with
+//! `Error::DataIntegrityError` showing both values on mismatch. The only
+//! supported algorithm is CRC32 (IEEE 802.3), written as `u32` value. Can't be
+//! combined with `exact`, `ignore_trailing` or optional tail fields, since the
+//! checksum must directly follow the structure fields.
+//!
+//! ### `optional_checksum = "crc32"`
+//!
+//! Can be used with structures only.
+//!
+//! Same as `checksum`, but for formats where older writers did not append
+//! the checksum. The checksum is always written on encoding, while on
+//! decoding it is verified only if some data remain after the structure
+//! fields; otherwise the data are accepted as they are. To detect this, the
+//! decoding reads the reader to its end, so the argument works only when the
+//! structure is decoded from an exact-sized buffer (for instance, with
+//! `strict_deserialize`) and not from a stream containing other data after
+//! it. Remaining data which are not exactly four bytes long fail the decoding
+//! with `Error::DataIntegrityError`. Can't be combined with `checksum`,
+//! `pad_to` or `align`.
+//!
+//! ### `pad_to = <unsigned integer>`
+//!
+//! Can be used with structures only.
+//!
+//! Pads encoded structure data with zero bytes up to the fixed block size, such
+//! that each record occupies exactly the given number of bytes, as required for
+//! slotted storage. Padding follows all other data, including checksum; the
+//! length returned by `strict_encode` equals the block size. Encoding fails
+//! with `Error::DataIntegrityError` if the data don't fit into the block.
+//! Decoding reads exactly the block size bytes, failing if the padding contains
+//! non-zero bytes.
+//!
+//! ### `align = <unsigned integer>`
+//!
+//! Can be used with structures only.
+//!
+//! Pads encoded structure data with zero bytes up to the next multiple of the
+//! given number of bytes. Like with `pad_to`, padding follows all other data,
+//! including checksum, and is accounted in the length returned by
+//! `strict_encode`; decoding consumes the padding, failing if it contains
+//! non-zero bytes. Can't be combined with `pad_to`.
+//!
+//! ### `batch_writes`
+//!
+//! Can be used with structures only.
+//!
+//! Collects encoded structure fields into a single stack-allocated buffer and
+//! writes it with a single `write_all` call instead of writing each field
+//! separately, which reduces overhead of small writes to unbuffered network
+//! streams. Requires all non-skipped fields to be of primitive integer, float
+//! or `bool` types. The encoding itself is not affected.
+//!
+//! ### `version = <u8 or u16 integer>`
+//!
+//! Can be used with structures only.
+//!
+//! Declares version of the structure encoding, which is written right after
+//! magic bytes (if any) as `u8` or, if the literal has `u16` suffix, as `u16`
+//! value. Fields may be limited to a range of versions with field-level `since`
+//! and `until` arguments. Upon decoding, data of any version not exceeding the
+//! declared one are accepted, and fields absent in the decoded version are set
+//! to their default values. Data of newer versions are rejected with
+//! `Error::ValueOutOfRange` error.
+//!
+//! ```
+//! # #[macro_use] extern crate strict_encoding_derive;
+//! use strict_encoding::{StrictDecode, StrictEncode};
+//!
+//! #[derive(Debug, PartialEq, StrictEncode, StrictDecode)]
+//! #[strict_encoding(version = 2)]
+//! struct Channel {
+//!     id: u16,
+//!     #[strict_encoding(since = 2)]
+//!     capacity: u64,
+//! }
+//!
+//! // Data written by the first version of the format
+//! let v1 = [1u8, 0x07, 0x00];
+//! assert_eq!(
+//!     Channel::strict_deserialize(&v1).unwrap(),
+//!     Channel { id: 7, capacity: 0 }
+//! );
+//!
+//! let channel = Channel { id: 7, capacity: 10 };
+//! let v2 = channel.strict_serialize().unwrap();
+//! assert_eq!(v2, vec![2, 0x07, 0x00, 10, 0, 0, 0, 0, 0, 0, 0]);
+//! assert_eq!(Channel::strict_deserialize(&v2).unwrap(), channel);
+//! ```
+//!
+//! ### `optional_tail`
+//!
+//! Can be used with structures and their fields.
+//!
+//! Makes trailing `Option` fields of the structure optional at the end of the
+//! encoded data, such that newer message formats may append fields which older
+//! messages simply lack. At the structure level, applies to the trailing
+//! sequence of all `Option` fields; at the field level, applies to the field
+//! and requires all following fields to be optional tail fields as well.
+//!
+//! Present values of these fields are encoded without presence byte. Upon
+//! decoding, if the data end right at the start of an optional tail field,
+//! this field and all subsequent ones are set to `None`; data ending in the
+//! middle of a field still result in an error. Since the encoding of a present
+//! field after an absent one would be ambiguous, encoding of such values fails
+//! with `Error::DataIntegrityError`.
+//!
+//! ### `default_variant = "Variant"`
+//!
+//! Can be used with enums only.
+//!
+//! Names enum variant, which must have no fields, returned upon decoding of an
+//! unknown enum value instead of `Error::EnumValueNotKnown`. Unlike with
+//! keeping unknown values in a catch-all variant, the original value is lost,
+//! so the decoded data can't be re-encoded identically.
+//!
+//! ### `bitpack`
+//!
+//! Can be used with structures only.
+//!
+//! Packs consecutive `bool` fields into bytes, eight fields per byte, with the
+//! first field of the group taking the lowest bit. A group ends after its
+//! eighth field or when a non-bool field is encoded, so bool fields interleaved
+//! with other fields take a separate byte each, and packing never changes
+//! the order in which the fields are written. Unused high bits of the last
+//! byte of a group must be zero, otherwise decoding fails with
+//! `Error::DataIntegrityError`. Skipped fields do not interrupt the group.
+//!
+//! Can't be combined with `transparent`, `batch_writes` and optional tail
+//! fields, and with `since`/`until` arguments on bool fields.
+//!
+//! ### `type_tagged`, `name = "..."`
+//!
+//! Prefixes the encoding with a type tag, which is a string with the name of
+//! the type (or the name given with `name` argument, which must be ASCII)
+//! encoded in the same way as `String`, i.e. with 16-bit length prefix.
+//! Decoding checks that the tag matches and fails with
+//! `Error::DataIntegrityError` otherwise, so a reader may find out which type
+//! the data claim to be before decoding them.
+//!
+//! Generic parameters are not included into the tag, so all instances of a
+//! generic type share the same tag. The tag is stringified by the macro at
+//! compile time, so it is stable across compiler versions.
+//!
+//! The tag precedes all the other data, including magic number and version,
+//! and is not covered by a checksum. Can't be combined with `transparent`,
+//! `into` and `try_from` attributes.
+//!
+//! ### `const_size`
+//!
+//! Can be used with structures only, deriving `StrictEncode`.
+//!
+//! Generates inherent `pub const ENCODED_SIZE: usize` constant with the size
+//! of the structure encoding, which is computed from the sizes of its encoded
+//! fields: integer, float and bool types have their sizes known to the macro,
+//! fixed-size arrays take the size of their element multiplied by the array
+//! length, and for other types the `ENCODED_SIZE` constant of the field type
+//! is used, which is generated for the nested structures with `const_size`.
+//! Magic number, version, length prefix and checksum are accounted as well,
+//! while for the structures with `pad_to` the constant is equal to the padded
+//! size.
+//!
+//! The macro errors if some of the encoded fields has dynamically sized
+//! encoding, like `Vec`, `String` or `Option`, or if the structure has an
+//! optional tail.
+//!
+//! ### `c_layout`
+//!
+//! Can be used with `#[repr(C)]` structures only, which fields must be of
+//! primitive integer, float or bool types or arrays of them, without
+//! field-level attribute arguments. The representation may specify increased
+//! alignment with `#[repr(C, align(N))]`, but packed structures are rejected.
+//!
+//! Encodes the structure fields in such a way that the encoding matches the
+//! in-memory representation of the structure: zero bytes are written in place
+//! of the padding between the fields and at the end of the structure, as
+//! defined by the alignment of the field types on the target platform. Upon
+//! decoding, the padding bytes are skipped. Since strict encoding of the
+//! integers is little-endian, the encoding matches the memory representation
+//! only on little-endian platforms. Can't be combined with the arguments
+//! changing the order or the size of the encoded fields, like `bitpack`,
+//! `batch_writes` or `const_size`, nor with the arguments adding data around
+//! the fields, like `magic`, `version` or `checksum`.
+//!
+//! ### `size_hint`
+//!
+//! Can be used with structures only, deriving `StrictEncode`.
+//!
+//! Generates inherent `pub fn strict_encode_size_hint(&self) -> Option<usize>`
+//! method, which may be used for pre-allocating buffers. If all encoded fields
+//! are of primitive integer, float or bool types, or fixed-size arrays of them,
+//! the method returns `Some` with the exact size of the encoding, computed in
+//! the same way as for `const_size`. Otherwise it returns `None`.
+//!
+//! ### `union_tag = "method"`
+//!
+//! Can be used with unions only, and is required for them.
+//!
+//! Unions are encoded as a tag of the `repr` integer type (`u8` by default)
+//! followed by the encoding of the active field. Each non-skipped union field
+//! must be assigned a tag with `value` argument, and the argument names
+//! method of the union, which may be `unsafe`, returning the tag of the active
+//! field. Decoding reads the tag and initializes the corresponding field;
+//! unknown tags result in `Error::DataIntegrityError`.
+//!
+//! ```
+//! # #[macro_use] extern crate strict_encoding_derive;
+//! use strict_encoding::{StrictDecode, StrictEncode};
+//!
+//! #[derive(StrictEncode, StrictDecode)]
+//! #[strict_encoding(union_tag = "tag")]
+//! union Value {
+//!     #[strict_encoding(value = 0)]
+//!     int: u32,
+//!     #[strict_encoding(value = 1)]
+//!     bytes: [u8; 4],
+//! }
+//!
+//! impl Value {
+//!     // Real code would keep the tag of the active field next to the union
+//!     fn tag(&self) -> u8 {
+//!         0
+//!     }
+//! }
+//!
+//! let data = Value { int: 5 }.strict_serialize().unwrap();
+//! assert_eq!(data, vec![0, 5, 0, 0, 0]);
+//! assert_eq!(unsafe { Value::strict_deserialize(&data).unwrap().int }, 5);
+//! ```
+//!
+//! ### `no_tag`
+//!
+//! Can be used with enums only.
+//!
+//! Omits the tag (variant value or order index) from the enum encoding, for
+//! the enums embedded into data structures which provide the tag themselves.
+//! The derived `StrictEncode` implementation writes only the variant fields,
+//! and an inherent `fn strict_variant_tag(&self) -> Repr` method is generated
+//! returning the tag, which must be written by the enclosing data structure.
+//!
+//! Since the tag can't be read by the enum itself, `StrictDecode` trait is not
+//! implemented for such enums. Instead, the `StrictDecode` derive generates an
+//! inherent method
+//! `fn strict_decode_variant<D: Read>(tag: Repr, d: D) -> Result<Self, Error>`
+//! decoding the variant with the provided tag.
+//!
+//! ### `count_assert = <unsigned integer>`
+//!
+//! Can be used with enums only.
+//!
+//! Fails the derivation unless the enum has exactly the given number of
+//! variants, not counting skipped ones. This protects enums used as protocol
+//! discriminants from accidental addition or removal of variants, for instance
+//! when the tag is packed into a 3-bit field and must have exactly 8 values.
+//!
+//! ```compile_fail
+//! # #[macro_use] extern crate strict_encoding_derive;
+//! #[derive(StrictEncode)]
+//! #[strict_encoding(count_assert = 3)]
+//! enum Side {
+//!     Bid,
+//!     Ask,
+//! }
+//! ```
+//!
+//! ### `const_values`
+//!
+//! Can be used with enums only.
+//!
+//! Makes the derived decoding bind the value of each variant to a constant
+//! named `V_{Variant}` at the top of the `strict_decode` method body, with the
+//! match arms referencing the constants instead of repeating the value
+//! expressions. This keeps the generated code readable when the values are
+//! given with paths to constants or with discriminant expressions, like
+//! `Nop = BASE + 2 * STEP`, and makes each expression evaluated only once, at
+//! compile time. The expressions can't reference generic parameters of the
+//! enum.
+//!
+//! ### `dyn_safe`
+//!
+//! Generates additional inherent method
+//! `fn strict_encode_dyn(&self, e: &mut dyn Write) -> Result<usize, Error>`
+//! forwarding to `StrictEncode::strict_encode`. Unlike the generic trait
+//! method, it can be called through a trait object, for instance of a user
+//! trait implemented for the types to be kept in a `Vec<Box<dyn ...>>`.
+//!
+//! ### `emit_append`
+//!
+//! Generates additional inherent method
+//! `fn strict_encode_append(&self, buf: &mut Vec<u8>) -> Result<usize, Error>`
+//! appending the encoding of the value to the end of an existing buffer. This
+//! allows reusing the same allocation when encoding many values in a loop.
+//!
+//! ### `emit_canonical`
+//!
+//! Generates additional inherent method
+//! `fn strict_is_canonical(bytes: &[u8]) -> Result<bool, Error>` checking that
+//! the data decode into a value which is encoded back into exactly the same
+//! bytes, which is required for consensus-critical data. Fails with the
+//! decoding error if the data can't be decoded. The method is generated by
+//! `StrictEncode` derive, but requires the type to implement `StrictDecode` as
+//! well.
+//!
+//! ### `emit_to_vec`
+//!
+//! Generates additional inherent method
+//! `fn strict_encode_to_vec(&self) -> Result<Vec<u8>, Error>` encoding the
+//! value into a newly allocated vector, which is pre-allocated to avoid its
+//! repeated growth on hot paths. With `len_helper` argument the vector
+//! capacity is the exact length of the encoding, counted with an additional
+//! encoding pass into a sink in the same way as `strict_serialized_len` does
+//! (but failing instead of panicking), such that the vector is allocated
+//! only once. Otherwise the capacity is the lower bound of the length known at
+//! compile time: the sizes of prefixes, checksum and fields of primitive types
+//! for structures, or the tag size for enums and unions.
+//!
+//! ### `helpers`
+//!
+//! Generates inherent `fn strict_serialize(&self) -> Result<Vec<u8>, Error>`
+//! (with `StrictEncode` derive) and
+//! `fn strict_deserialize(data: impl AsRef<[u8]>) -> Result<Self, Error>`
+//! (with `StrictDecode` derive) methods, which can be called without importing
+//! the traits. Unlike `StrictDecode::strict_deserialize`, the inherent method
+//! fails with `Error::DataNotEntirelyConsumed` if some of the data remain
+//! unread. The decoding method is not generated for enums with `no_tag`.
+//!
+//! ### `len_helper`
+//!
+//! Generates inherent `fn strict_serialized_len(&self) -> usize` method
+//! returning the length of the strict encoding of the value, for instance for
+//! fee estimation. The value is encoded into a counting writer, so the result
+//! matches the bytes written by `StrictEncode::strict_encode` exactly,
+//! including enum tags, prefixes and padding, but no buffer is allocated. The
+//! method panics if the value can't be encoded. When combined with
+//! `proptest`, the generated property test also checks the method against the
+//! length of the encoded data.
+//!
+//! ### `conversions`
+//!
+//! Generates `impl TryFrom<&[u8]> for T` (with `StrictDecode` derive), which
+//! deserializes the value failing with `Error::DataNotEntirelyConsumed` if
+//! some of the data remain unread, and `impl From<&T> for Vec<u8>` (with
+//! `StrictEncode` derive), which serializes the value. Since writing into a
+//! `Vec` never fails, the latter panics only if the value itself can't be
+//! encoded (for instance, if a collection exceeds its `max_len`). The
+//! conversion error is the `Error` type of the crate specified with `crate`
+//! argument. The conversions are opt-in to avoid conflicts with the ones
+//! defined by the user, and can't be used with `no_tag` or `length_suffix`.
+//!
+//! ```
+//! # #[macro_use] extern crate strict_encoding_derive;
+//! use std::convert::TryFrom;
+//!
+//! #[derive(Debug, PartialEq, StrictEncode, StrictDecode)]
+//! #[strict_encoding(conversions)]
+//! struct Ping {
+//!     nonce: u32,
+//! }
+//!
+//! let bytes: Vec<u8> = (&Ping { nonce: 7 }).into();
+//! assert_eq!(bytes, vec![7, 0, 0, 0]);
+//! assert_eq!(Ping::try_from(&bytes[..]).unwrap(), Ping { nonce: 7 });
+//! assert!(Ping::try_from(&[7u8, 0, 0, 0, 0][..]).is_err());
+//! ```
+//!
+//! ### `scoped_helpers`
+//!
+//! Places the auxiliary inherent items generated by the derives (see
+//! `dyn_safe`, `emit_append`, `emit_canonical`, `emit_to_vec`, `helpers`,
+//! `len_helper`, `const_size`, `max_encoded_len` and `size_hint`) into an
+//! anonymous `const _: () = { ... };` scope and makes them `pub(crate)`, so
+//! they can be used by the crate itself but do not become a part of the public
+//! API of the type.
+//!
+//! ### `encode_only`/`decode_only`
+//!
+//! Used with `StrictEncoding` derive only, which then generates just the
+//! `StrictEncode` or just the `StrictDecode` implementation respectively. This
+//! keeps the single derive for types which can be only encoded or only
+//! decoded. The arguments can't be combined, and separate `StrictEncode` and
+//! `StrictDecode` derives reject them.
+//!
+//! ```compile_fail
+//! # #[macro_use] extern crate strict_encoding_derive;
+//! #[derive(StrictEncode)]
+//! #[strict_encoding(encode_only)]
+//! struct Report(u8);
+//! ```
+//!
+//! ### `max_len = <unsigned integer>`
+//!
+//! Limits the number of items in all fields of standard library collection
+//! types (`Vec`, `VecDeque`, `LinkedList`, `String`, `HashMap`, `HashSet`,
+//! `BTreeMap` and `BTreeSet`) and fields having `collection` argument, in the
+//! same way as the field-level `max_len` argument (see below), which overrides
+//! the type-level limit for a specific field. For enums the limit applies to
+//! the fields of all variants. Can't be combined with `transparent`,
+//! `batch_writes` or `c_layout` arguments.
+//!
+//! ### `max_encoded_len = <unsigned integer>`
+//!
+//! Limits the total length of the strict encoding of the value in bytes,
+//! declaring the size limit of a protocol message in a single place. The
+//! `StrictEncode` derive generates inherent `const STRICT_MAX_LEN: usize`
+//! holding the limit. Encoding fails with `Error::DataIntegrityError` before
+//! writing any byte beyond the limit (the preceding bytes are still written),
+//! and decoding fails with the same error once more bytes than the limit are
+//! requested from the reader, instead of trying to read them. Unlike
+//! type-level `max_len`, which limits the number of collection items, the
+//! argument limits the encoding as a whole. The limit must fit into `usize`,
+//! and applies to `strict_decode_into` and `strict_decode_variant` methods
+//! generated by `emit_decode_into` and `no_tag` arguments as well. Can't be
+//! combined with `transparent`, `into`, `try_from` or `length_suffix`
+//! arguments.
+//!
+//! ### `sequence = "field, ..."`
+//!
+//! Can be used with structures only.
+//!
+//! Encodes and decodes the fields in the listed order instead of the order of
+//! their declaration, for protocols specifying fields in an order which does
+//! not match the Rust structure, like `sequence = "kind, id, payload"`. Tuple
+//! structure fields are listed by their indexes (`sequence = "1, 0"`). All
+//! non-skipped fields must be listed exactly once, and skipped fields can't be
+//! listed. Can't be combined with field-level `order`, `encode_order` or
+//! `decode_order` arguments, optional tail fields, `transparent` or `c_layout`
+//! arguments.
+//!
+//! ### `allow_order_skew`
+//!
+//! Can be used with structures only.
+//!
+//! Acknowledges that fields of the structure have different encoding and
+//! decoding order given with field-level `encode_order` and `decode_order`
+//! arguments, which is an error otherwise.
+//!
+//! ### `inline = "always"|"never"`
+//!
+//! By default all generated encoding and decoding methods are marked with
+//! `#[inline]`. This argument replaces it with `#[inline(always)]` or
+//! `#[inline(never)]` respectively, which may be used to keep hot paths of
+//! small types fully inlined or to reduce code size for large ones.
+//!
+//! ### `proptest`
+//!
+//! Generates property test checking that arbitrary values of the type are
+//! decoded back from their strict encoding unchanged. The test is compiled only
+//! when testing the crate which uses the derive with its `arbitrary` feature
+//! enabled, so the crate must declare such a feature and have `proptest` among
+//! its dev dependencies. The type must have no generic parameters and, under
+//! the same conditions, implement `proptest::arbitrary::Arbitrary`,
+//! `Debug` and `PartialEq`.
+//!
 //! ### `bu_order`/`by_velue`
 //!
 //! Can be used with enum types only, where they define which encoding strategy
@@ -66,6 +740,22 @@
 //! If neither of these two arguments is provided, the macro defaults to
 //! `by_order` encoding.
 //!
+//! When one of these arguments is given at the type level and the other at
+//! the enum variant level, the macro fails naming both conflicting arguments
+//! and pointing to the variant. The same applies to any other argument
+//! repeated at the type and the variant or field level with a different value.
+//!
+//! The macro errors if an order index of some `by_order`-encoded variant or
+//! an explicit variant `value` can't be represented with the `repr` integer
+//! type (unless `repr` is given as a type alias).
+//!
+//! Decoding of a value not matching any of the enum variants fails with
+//! `Error::EnumValueNotKnown`, carrying the enum name and the unknown value.
+//! The values known to the decoder are exactly the ones written by the
+//! encoder: explicit variant `value`s, then variant order for `by_order` enums
+//! or variant discriminants for `by_value` enums; values of skipped variants
+//! are known only if they have `replace_with` argument.
+//!
 //!
 //! ## Attribute arguments at field and enum variant level
 //!
@@ -77,22 +767,475 @@
 //! Skips field during serialization and initialize field value with
 //! `Default::default()` on type deserialization.
 //!
-//! Allowed only for named and unnamed (tuple) structure fields and enum variant
-//! associated value fields.
+//! Allowed only for named and unnamed (tuple) structure fields, enum variant
+//! associated value fields and enum variants.
 //!
-//! ### `value = <unsigned integer>`
+//! Skipped enum variants keep their value or order index, so the values of
+//! other variants are not changed, but can't be encoded: an attempt to encode
+//! them fails with `Error::UnsupportedDataStructure`. Upon decoding, the value
+//! of a skipped variant is treated as unknown, unless the variant has
+//! `replace_with = "expression"` argument, like
+//! `replace_with = "Self::Fallback"`, providing the value to be decoded
+//! instead. This allows retiring variants while still being able to read the
+//! data containing them.
+//! Skipping all variants of an enum produces a compile-time warning, since
+//! none of its values can be encoded or decoded then. The warning is reported
+//! as a deprecation, so it fails the build denying `deprecated` lint:
 //!
-//! Allowed only for enum variants.
+//! ```compile_fail
+//! # #![deny(deprecated)]
+//! # #[macro_use] extern crate strict_encoding_derive;
+//! #[derive(StrictEncode, StrictDecode)]
+//! enum Retired {
+//!     #[strict_encoding(skip)]
+//!     Legacy,
+//! }
+//! ```
+//!
+//! Fields of `PhantomData` type are skipped automatically, so they do not
+//! produce any bytes and do not add trait bounds for the type parameters they
+//! mention. This can be disabled for a field with `no_skip` argument, in which
+//! case the field is encoded with the `StrictEncode` implementation of its
+//! type.
+//!
+//! Separate `#[strict_skip]` attribute is a shorthand for
+//! `#[strict_encoding(skip)]`, which is convenient for skipping many fields.
+//! It takes no arguments and can be combined with `#[strict_encoding(...)]`
+//! attribute providing other arguments, like `replace_with`.
+//!
+//! ```
+//! # #[macro_use] extern crate strict_encoding_derive;
+//! use strict_encoding::StrictEncode;
+//!
+//! #[derive(StrictEncode, StrictDecode)]
+//! struct Session {
+//!     id: u32,
+//!     #[strict_skip]
+//!     cache: Vec<u8>,
+//!     #[strict_skip]
+//!     hits: usize,
+//! }
+//!
+//! let session = Session { id: 1, cache: vec![1, 2], hits: 3 };
+//! assert_eq!(session.strict_serialize().unwrap(), vec![1, 0, 0, 0]);
+//! ```
+//!
+//! ### `byte_blob`
+//!
+//! Allowed only for fields of `Vec<u8>` or `&[u8]` type.
+//!
+//! Encodes the field as a raw byte blob prefixed with its length encoded as
+//! `u32`, instead of the generic encoding of a collection of individually
+//! encoded `u8` values. Encoding fails with `Error::ExceedMaxItems` if the
+//! blob is longer than `u32::MAX` bytes.
+//!
+//! ### `sorted_map`
+//!
+//! Allowed only for fields of map types (`HashMap`, `BTreeMap` etc).
+//!
+//! Makes encoding of the map deterministic by writing the map entries sorted by
+//! the strict encoding of their keys, prefixed with the number of entries.
+//! Decoding is unchanged and accepts the entries in any order, so the field
+//! reads maps written without the argument as well.
+//!
+//! `sort_keys` is an alias of this argument.
+//!
+//! Since `BTreeMap` is already ordered, the argument is not needed for it: a
+//! `BTreeMap` field is encoded and decoded with its own strict encoding
+//! implementation, exactly as without the argument, and a compiler warning is
+//! emitted.
+//!
+//! ### `count_type = u8|u16|u32|u64`
+//!
+//! Allowed only for fields having `collection` argument and for fields of
+//! `Vec`, `VecDeque`, `LinkedList`, `HashSet` and `BTreeSet` types.
+//!
+//! Encodes the number of items of the collection, which is written as `u16`
+//! by default, with the given unsigned integer type, followed by the items,
+//! which are decoded by collecting them into the field type. Encoding fails
+//! with `Error::ExceedMaxItems` if the number of items doesn't fit into the
+//! type. Since both `StrictEncode` and `StrictDecode` derives read the count
+//! type from the same field attribute, the two sides of the encoding always
+//! agree on it.
+//!
+//! ```
+//! # #[macro_use] extern crate strict_encoding_derive;
+//! use strict_encoding::StrictEncode;
+//!
+//! #[derive(StrictEncode, StrictDecode)]
+//! struct Batch {
+//!     #[strict_encoding(count_type = u8)]
+//!     items: Vec<u16>,
+//! }
+//!
+//! let batch = Batch { items: vec![1, 2] };
+//! assert_eq!(batch.strict_serialize().unwrap(), vec![2, 1, 0, 2, 0]);
+//! ```
+//!
+//! ### `since = <version>`, `until = <version>`
+//!
+//! Allowed only for fields of structures having `version` argument.
+//!
+//! Specify the first and the last (inclusive) versions of the structure
+//! encoding containing the field. The field is encoded only if the structure
+//! `version` falls into the range; upon decoding, the field is read only if
+//! the decoded version falls into the range and is set to its default value
+//! otherwise, thus requiring field type to implement `Default`.
+//!
+//! ### `skip_if_const = "CONSTANT"`
+//!
+//! Allowed only for structure fields.
+//!
+//! Skips the field if the given constant `bool` expression is `true`, in
+//! which case the field is not encoded and is set to its default value upon
+//! decoding, thus requiring field type to implement `Default`. Otherwise the
+//! field is encoded and decoded as usual. Since the condition changes the
+//! byte layout of the structure, it must be a constant which is evaluated to
+//! the same value by both the encoding and the decoding side.
+//!
+//! ### `collection = "expression"`, `max_len = <unsigned integer>`
+//!
+//! Allowed only for structure fields and enum variant associated value fields.
+//!
+//! Encodes field of a collection type lacking strict encoding implementation,
+//! like `SmallVec` or `ArrayVec`, in the same way as `Vec`: number of items
+//! followed by the items. The collection must provide `len` and `iter` methods
+//! for encoding; upon decoding, an empty collection is constructed with the
+//! provided expression, like `collection = "SmallVec::new()"`, and the items
+//! are added to it with `push` method. With `max_len`, both encoding and
+//! decoding fail with `Error::ExceedMaxItems` if the collection has more
+//! items, which also prevents panics of fixed-capacity collections. The number
+//! of items may be encoded with a different integer type with `count_type`
+//! argument.
+//!
+//! `max_len` can be also given without `collection` for fields of any type
+//! having `len` method, like `Vec` or `String`. Such fields are checked before
+//! encoding and after decoding, failing with the same error. The argument
+//! overrides the type-level `max_len`, if any.
+//!
+//! ### `assert_eq = <unsigned integer or constant>`
+//!
+//! Allowed only for structure fields and enum variant associated value fields.
+//!
+//! Checks the field value right after it is decoded, failing the decoding with
+//! `Error::DataIntegrityError` if it differs from the provided one, like
+//! `assert_eq = 32` or `assert_eq = KEY_LEN`. Fields of primitive numeric
+//! types are compared after conversion into `usize`, which allows validating
+//! protocol constants and declared lengths; for other types the number of
+//! items returned by their `len` method is compared, so a collection and the
+//! field declaring its length can be both checked against the same constant.
+//! The encoding is not affected.
+//!
+//! ### `default_on_eof`, `default = "expression"`
+//!
+//! Allowed only for structure fields.
+//!
+//! Makes the field part of the structure optional tail (see `optional_tail`),
+//! such that data written by the older format versions, which end right before
+//! the field, can still be decoded. In this case the field, and all subsequent
+//! tail fields, take their default value: either `Default::default()` or the
+//! value of the expression provided with `default` argument. Unlike optional
+//! tail `Option` fields, the field is always encoded.
+//!
+//! ### `order = <unsigned integer>`
+//!
+//! Allowed only for structure fields.
+//!
+//! Pins position of the field in the encoded data independently from the
+//! order of field declaration, such that refactoring of the structure can't
+//! change its encoding. Fields are encoded in the ascending order of the
+//! provided values. If used, must be specified for all non-skipped fields of
+//! the structure; repeated values result in compilation error. Can't be
+//! combined with optional tail fields. The same can be achieved with
+//! type-level `sequence` argument listing all fields in one place.
+//!
+//! ### `encode_order = <unsigned integer>`, `decode_order = <unsigned integer>`
+//!
+//! Allowed only for structure fields.
+//!
+//! Work like `order`, but apply only to encoding or decoding, overriding
+//! `order` (if any) for that direction. This is needed only in rare migration
+//! scenarios, like fixing an erratum in a protocol specification, when the
+//! data are written in the order different from the one expected by the
+//! readers. Since such structures can't decode data they have encoded, the
+//! arguments require type-level `allow_order_skew` acknowledgment, and the
+//! generated code carries a warning about the skew.
+//!
+//! ```
+//! # #[macro_use] extern crate strict_encoding_derive;
+//! use strict_encoding::{StrictDecode, StrictEncode};
+//!
+//! #[derive(Debug, PartialEq, StrictEncode, StrictDecode)]
+//! #[strict_encoding(allow_order_skew)]
+//! struct Header {
+//!     #[strict_encoding(encode_order = 1, decode_order = 0)]
+//!     kind: u8,
+//!     #[strict_encoding(encode_order = 0, decode_order = 1)]
+//!     flags: u8,
+//! }
+//!
+//! let header = Header { kind: 1, flags: 2 };
+//! let data = header.strict_serialize().unwrap();
+//! assert_eq!(data, vec![2, 1]);
+//! assert_eq!(
+//!     Header::strict_deserialize(&data).unwrap(),
+//!     Header { kind: 2, flags: 1 }
+//! );
+//! ```
+//!
+//! ### `tuple_fields`
+//!
+//! Allowed only for fields of tuple types with up to 12 elements, unless a
+//! different limit is given with `tuple_arity` argument.
+//!
+//! Encodes the tuple elements one after another, each with its own
+//! `StrictEncode` implementation, without requiring the tuple type itself to
+//! implement the traits. The encoding is identical to the encoding of the
+//! same elements given as separate fields.
+//!
+//! ### `tuple_arity = <unsigned integer>`
+//!
+//! Allowed only together with `tuple_fields` argument.
+//!
+//! Sets the maximal number of the tuple elements accepted by `tuple_fields`.
+//! The default limit of 12 elements matches the arity of tuples implementing
+//! standard library traits; since the elements are encoded one by one, the
+//! limit may be safely raised for longer tuples.
+//!
+//! ### `value = <unsigned integer or constant>`
+//!
+//! Allowed only for enum variants and union fields.
 //!
 //! Assigns custom value for a given enum variant, overriding `by_value` and
 //! `by_order` directives defined at type level and the actual variant value, if
-//! any.
+//! any. The value can be given as an integer literal or as a path to a
+//! constant, like `value = TAG_PING` or `value = Self::TAG_PING`, which is cast
+//! to the `repr` type with `as`. Range validation against `repr` is done only
+//! for the integer literals.
 //!
 //! NB: If the value conflicts with the values of other enum variants, taken
 //! from either their assigned value (for `by_value`-encoded enums), order
 //! index (for `by_order`-encoded enums) or other variant's value from with
 //! explicit `value` argument the compiler will error.
 //!
+//! Using `value` anywhere else, for instance with a structure field, is an
+//! error:
+//!
+//! ```compile_fail
+//! # #[macro_use] extern crate strict_encoding_derive;
+//! #[derive(StrictEncode)]
+//! struct Version {
+//!     #[strict_encoding(value = 5)]
+//!     major: u8,
+//! }
+//! ```
+//!
+//! ### `deprecated`
+//!
+//! Allowed only for enum variants.
+//!
+//! Marks the variant as deprecated for strict encoding without deprecating the
+//! variant itself with Rust `#[deprecated]` attribute. The derived
+//! `StrictEncode` implementation produces a compiler warning pointing to the
+//! variant, so the code still encoding it can be spotted and migrated. Decoding
+//! of the variant is not affected.
+//!
+//! ```
+//! # #[macro_use] extern crate strict_encoding_derive;
+//! use strict_encoding::{StrictDecode, StrictEncode};
+//!
+//! #[derive(Debug, PartialEq, StrictEncode, StrictDecode)]
+//! enum Payment {
+//!     Invoice(u64),
+//!     // Emits warning upon compilation of `StrictEncode` implementation
+//!     #[strict_encoding(deprecated)]
+//!     Legacy(u32),
+//! }
+//!
+//! let data = Payment::Legacy(5).strict_serialize().unwrap();
+//! assert_eq!(data, vec![1, 5, 0, 0, 0]);
+//! assert_eq!(Payment::strict_deserialize(&data).unwrap(), Payment::Legacy(5));
+//! ```
+//!
+//!
+//! # Generic types
+//!
+//! For generic data types the derivation macros add `T: StrictEncode` (or
+//! `T: StrictDecode`) bounds to the generated implementations for each type
+//! parameter `T` which is used by the non-skipped structure fields or by the
+//! fields of non-skipped enum variants. Type parameters used only by skipped
+//! fields or variants do not get any bounds, and types of skipped fields (like
+//! `Box<dyn Handler<T>>`) are not required to implement the traits. Since
+//! `PhantomData` is encoded to nothing, type parameters mentioned only inside
+//! `PhantomData` (including `PhantomData<fn() -> T>`, `PhantomData<&'a T>` and
+//! `PhantomData` nested into other field types) do not get bounds either.
+//! Associated types of type parameters used by the fields, like `T::Message` or
+//! `<T as Protocol>::Message`, are bounded on their own
+//! (`T::Message: StrictEncode`) without requiring any bound on `T`.
+//!
+//! Where the inferred bounds are wrong or insufficient (for instance, when the
+//! implementation for some field type requires more bounds than the type
+//! parameters it uses, or stricter ones), they can be replaced with a manually
+//! provided list of where predicates using `bound = "..."` type-level attribute
+//! argument. The predicates are used as is, without any inferred bounds; an
+//! empty string results in no additional bounds at all. To provide different
+//! predicates for `StrictEncode` and `StrictDecode` implementations, use
+//! `encode_bound` and `decode_bound` arguments, which take precedence over
+//! `bound`.
+//!
+//! Alternatively, particular type parameters can be excluded from the inferred
+//! bounds with `no_bound = "T, U"` argument, or, for a single derive, with
+//! `no_encode_bound` and `no_decode_bound` arguments. Each of the listed names
+//! must be a type parameter of the type; associated types of the excluded
+//! parameters are not bounded as well.
+//!
+//! ```
+//! # #[macro_use] extern crate strict_encoding_derive;
+//! use strict_encoding::{StrictDecode, StrictEncode};
+//!
+//! trait Schema {
+//!     type Item;
+//! }
+//!
+//! #[derive(StrictEncode, StrictDecode)]
+//! #[strict_encoding(
+//!     encode_bound = "S::Item: StrictEncode",
+//!     decode_bound = "S::Item: StrictDecode"
+//! )]
+//! struct Items<S: Schema> {
+//!     items: Vec<S::Item>,
+//! }
+//! ```
+//!
+//! Fields of `Infallible` type (usually coming from instantiating generic
+//! protocol types with a type parameter which can't have a value) are
+//! supported without implementing the traits for them: encoding of a value
+//! containing such a field is unreachable, while decoding of an enum variant
+//! with such a field always fails with `Error::UnsupportedDataStructure`.
+//! Since a structure with such a field can't have any value at all, deriving
+//! `StrictDecode` for it is a compile-time error.
+//!
+//! ```compile_fail
+//! # #[macro_use] extern crate strict_encoding_derive;
+//! #[derive(StrictDecode)]
+//! struct Reply {
+//!     code: u16,
+//!     error: std::convert::Infallible,
+//! }
+//! ```
+//!
+//! Const generic parameters are supported as well. Fields of `[u8; N]` type
+//! use the encoding provided by `strict_encoding` crate, while arrays of other
+//! element types (including generic ones, like `[T; N]`) are encoded element
+//! by element, without length prefix.
+//!
+//! # Borrowed data
+//!
+//! `StrictEncode` can be derived for structures with lifetime parameters
+//! borrowing their data, like `&'a [u8]` fields, which are written without
+//! intermediate allocations using the same encoding as the respective owned
+//! types (`Vec<u8>` for `&[u8]`). Since such fields can't be decoded from a
+//! reader, `StrictDecode` should be derived for an owned counterpart of the
+//! structure instead.
+//!
+//! Fields of `Cow<'a, B>` type support both traits: they are encoded from the
+//! borrowed data (requiring `B: StrictEncode`, or `&str: StrictEncode` for
+//! `Cow<'a, str>`) and always decoded into `Cow::Owned` (requiring
+//! `B::Owned: StrictDecode`), so structures and enums having lifetime
+//! parameters used only in `Cow` fields can derive `StrictDecode` for any
+//! lifetime. Inferred trait bounds are put on these types as well, and not on
+//! the `Cow` type parameter itself. Only `Cow`, `std::borrow::Cow` and
+//! `alloc::borrow::Cow` paths are treated in this way; other types named `Cow`
+//! are encoded with their own `StrictEncode` implementation. The encoding of
+//! such fields is identical to the encoding of the owned type, like `String`
+//! for `Cow<'a, str>` and `Vec<u8>` for `Cow<'a, [u8]>`, so the data written
+//! with them can be read into the owned counterpart of the type and vice versa.
+//!
+//! # Standard library types
+//!
+//! Apart from `Cow` and arrays of non-byte types (see above), fields of
+//! `Result<T, E>` type are supported without strict encoding implementation
+//! for them: the value is encoded as `0u8` followed by the `Ok` value or as
+//! `1u8` followed by the `Err` value, so both `T` and `E` must be encodable.
+//! Only `Result`, `std::result::Result` and `core::result::Result` paths are
+//! recognized, so aliases of `Result` with a single type parameter (like
+//! `io::Result<T>`) and other types named `Result` are encoded with their own
+//! strict encoding implementation.
+//!
+//! # Error types
+//!
+//! Generated `strict_encode` and `strict_decode` methods always return
+//! `strict_encoding::Error`, since their signatures are defined by
+//! `StrictEncode` and `StrictDecode` traits and can't be changed by the derive.
+//! Crates using their own error type should implement
+//! `From<strict_encoding::Error>` for it, so the results of the encoding and
+//! decoding methods can be converted with `?` operator at the call site.
+//! Type-level `error_type` argument generates inherent decoding methods
+//! returning such error type directly.
+//!
+//! # Network and confined encodings
+//!
+//! `#[derive(NetworkEncode, NetworkDecode)]` macros generate implementations
+//! of the network encoding traits of the same shape, using the same code
+//! generation and the same attribute arguments as strict encoding derivation,
+//! but given with `#[network_encoding(...)]` attribute. The attributes of both
+//! encodings are independent, so one type can derive both of them. Generated
+//! code refers to `NetworkEncode` and `NetworkDecode` traits of
+//! `network_encoding` crate (unless `crate` argument is given) and to their
+//! methods, with `strict_` prefix of the trait and generated method names
+//! replaced with `network_` one. Names used in the attribute expressions and
+//! the type's own field names are left as they are.
+//!
+//! In the same way, `#[derive(ConfinedEncode, ConfinedDecode)]` macros
+//! generate implementations of `ConfinedEncode` and `ConfinedDecode` traits of
+//! `confined_encoding` crate, with `confined_` prefix of the trait and
+//! generated method names, configured with `#[confined_encoding(...)]`
+//! attribute. Size limits of the confined encoding are given with type-level
+//! `max_len` argument, which can be overridden for individual fields, like
+//! `#[confined_encoding(max_len = 255)]`.
+//!
+//! # Placeholder values
+//!
+//! `#[derive(StrictDumb)]` generates inherent `fn strict_dumb() -> Self`
+//! method returning placeholder value of the type. Structure fields and fields
+//! of enum variants take their `Default::default()` values unless another
+//! expression is provided with `dumb = "expression"` field argument, like
+//! `dumb = "Inner::strict_dumb()"` for nested types. Enums use the first
+//! non-skipped variant, or the variant marked with `dumb` argument. Type
+//! parameters get `Default` bounds only if they are used by the non-skipped
+//! fields taking their default values, so the fields provided with `dumb`
+//! expressions and the fields of other enum variants do not require them. The
+//! derive shares `strict_encoding` attribute with `StrictEncode` and
+//! `StrictDecode`, so all three can be used with the same type.
+//!
+//! # Commitment encoding
+//!
+//! `#[derive(CommitEncode)]` generates implementation of `CommitEncode` trait
+//! of `commit_verify` crate (the path can be changed with
+//! `#[commit_encoding(crate = path)]`), writing strict encoding of each
+//! structure field in the order of their declaration. Fields can be
+//! configured with `#[commit_encoding(...)]` attribute taking the following
+//! arguments:
+//! - `skip` - excludes the field from the commitment;
+//! - `conceal` - commits to the value returned by the field `conceal` method
+//!   instead of the field itself;
+//! - `hash = sha256` - commits to the hash of the field strict encoding
+//!   instead of the encoding itself; other hash functions of `bitcoin_hashes`
+//!   crate (`sha256d`, `sha512`, `hash160`, `ripemd160` and `sha1`) can be
+//!   used as well, and the crate must be a dependency of the crate using the
+//!   derive. Combined with `conceal`, hashes the concealed value.
+//!
+//! Type parameters get `StrictEncode` bounds if they are used by the fields
+//! committed with their own strict encoding, including the fields skipped by
+//! `strict_encoding` attribute, which is not taken into account by the derive.
+//! Fields with `skip` and `conceal` arguments add no bounds: the concealed
+//! values are required to be strict encodable by the `conceal` method calls
+//! themselves.
+//!
+//! Strict encoding is taken from `strict_encoding` crate, unless another path
+//! is given with `strict_crate` argument. Since commitments are written into
+//! in-memory buffers or hashers, strict encoding errors result in a panic.
+//! The derive can be used with structures only.
 //!
 //! # Examples
 //!
@@ -178,29 +1321,178 @@ extern crate quote;
 #[macro_use]
 extern crate syn;
 
+mod bitpack;
+mod bound;
+mod checksum;
+mod combined;
+mod commit;
 mod decode;
+mod dumb;
 mod encode;
+mod flavor;
+mod layout;
+mod limit;
 mod param;
+mod proptest;
+mod shorthand;
+mod size;
+mod type_tag;
 
 use proc_macro::TokenStream;
 use syn::DeriveInput;
 
 pub(crate) const ATTR_NAME: &str = "strict_encoding";
 
+/// Generates code producing compiler warning with the given message at the
+/// provided span, which is done by referencing a deprecated constant (stable
+/// proc macro API does not allow emitting warnings directly). The `name` must
+/// be a unique snake-case identifier describing the warning.
+pub(crate) fn compile_warning(
+    name: &str,
+    message: &str,
+    span: proc_macro2::Span,
+) -> proc_macro2::TokenStream {
+    let name = syn::Ident::new(name, span);
+    quote_spanned! { span =>
+        {
+            #[deprecated(note = #message)]
+            #[allow(non_upper_case_globals)]
+            const #name: () = ();
+            let _ = #name;
+        }
+    }
+}
+
+/// Returns a warning for enums having variants, all of which are skipped, so
+/// none of them can be encoded or decoded.
+pub(crate) fn skipped_enum_warning(
+    ident_name: &syn::Ident,
+    present: usize,
+    total: usize,
+) -> proc_macro2::TokenStream {
+    if present > 0 || total == 0 {
+        return proc_macro2::TokenStream::new();
+    }
+    compile_warning(
+        "all_enum_variants_are_skipped",
+        &format!(
+            "all variants of enum `{}` are skipped, so it can't be strict encoded or decoded",
+            ident_name
+        ),
+        ident_name.span(),
+    )
+}
+
+/// Checks that the number of non-skipped enum variants matches the one
+/// required with `count_assert` argument. The error is the same for both
+/// derives, so the compiler reports it only once.
+pub(crate) fn variant_count_check(
+    ident_name: &syn::Ident,
+    present: usize,
+    expected: Option<u64>,
+) -> syn::Result<()> {
+    match expected {
+        Some(expected) if present as u64 != expected => Err(syn::Error::new(
+            ident_name.span(),
+            format!(
+                "enum `{}` must have exactly {} non-skipped variants, but has {}",
+                ident_name, expected, present
+            ),
+        )),
+        _ => Ok(()),
+    }
+}
+
 /// Derives [`::strict_encoding::StrictEncode`] implementation for the type.
-#[proc_macro_derive(StrictEncode, attributes(strict_encoding))]
+#[proc_macro_derive(StrictEncode, attributes(strict_encoding, strict_skip))]
 pub fn derive_strict_encode(input: TokenStream) -> TokenStream {
     let derive_input = parse_macro_input!(input as DeriveInput);
-    encode::encode_derive(derive_input)
+    shorthand::expand(derive_input)
+        .and_then(combined::separate_derive_check)
+        .and_then(|input| encode::encode_derive(input, &flavor::STRICT))
         .unwrap_or_else(|e| e.to_compile_error())
         .into()
 }
 
 /// Derives [`::strict_encoding::StrictDncode`] implementation for the type.
-#[proc_macro_derive(StrictDecode, attributes(strict_encoding))]
+#[proc_macro_derive(StrictDecode, attributes(strict_encoding, strict_skip))]
 pub fn derive_strict_decode(input: TokenStream) -> TokenStream {
     let derive_input = parse_macro_input!(input as DeriveInput);
-    decode::decode_derive(derive_input)
+    shorthand::expand(derive_input)
+        .and_then(combined::separate_derive_check)
+        .and_then(|input| decode::decode_derive(input, &flavor::STRICT))
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Derives both [`::strict_encoding::StrictEncode`] and
+/// [`::strict_encoding::StrictDecode`] implementations for the type.
+#[proc_macro_derive(StrictEncoding, attributes(strict_encoding, strict_skip))]
+pub fn derive_strict_encoding(input: TokenStream) -> TokenStream {
+    let derive_input = parse_macro_input!(input as DeriveInput);
+    shorthand::expand(derive_input)
+        .and_then(combined::combined_derive)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Derives `CommitEncode` implementation for the type, configured with
+/// `#[commit_encoding(...)]` attribute.
+#[proc_macro_derive(CommitEncode, attributes(commit_encoding))]
+pub fn derive_commit_encode(input: TokenStream) -> TokenStream {
+    let derive_input = parse_macro_input!(input as DeriveInput);
+    commit::commit_derive(derive_input)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Derives inherent `strict_dumb` constructor of the placeholder value of the
+/// type.
+#[proc_macro_derive(StrictDumb, attributes(strict_encoding, strict_skip))]
+pub fn derive_strict_dumb(input: TokenStream) -> TokenStream {
+    let derive_input = parse_macro_input!(input as DeriveInput);
+    shorthand::expand(derive_input)
+        .and_then(dumb::dumb_derive)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Derives `NetworkEncode` implementation for the type, configured with
+/// `#[network_encoding(...)]` attribute.
+#[proc_macro_derive(NetworkEncode, attributes(network_encoding))]
+pub fn derive_network_encode(input: TokenStream) -> TokenStream {
+    let derive_input = parse_macro_input!(input as DeriveInput);
+    encode::encode_derive(derive_input, &flavor::NETWORK)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Derives `NetworkDecode` implementation for the type, configured with
+/// `#[network_encoding(...)]` attribute.
+#[proc_macro_derive(NetworkDecode, attributes(network_encoding))]
+pub fn derive_network_decode(input: TokenStream) -> TokenStream {
+    let derive_input = parse_macro_input!(input as DeriveInput);
+    decode::decode_derive(derive_input, &flavor::NETWORK)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Derives `ConfinedEncode` implementation for the type, configured with
+/// `#[confined_encoding(...)]` attribute.
+#[proc_macro_derive(ConfinedEncode, attributes(confined_encoding))]
+pub fn derive_confined_encode(input: TokenStream) -> TokenStream {
+    let derive_input = parse_macro_input!(input as DeriveInput);
+    encode::encode_derive(derive_input, &flavor::CONFINED)
+        .unwrap_or_else(|e| e.to_compile_error())
+        .into()
+}
+
+/// Derives `ConfinedDecode` implementation for the type, configured with
+/// `#[confined_encoding(...)]` attribute.
+#[proc_macro_derive(ConfinedDecode, attributes(confined_encoding))]
+pub fn derive_confined_decode(input: TokenStream) -> TokenStream {
+    let derive_input = parse_macro_input!(input as DeriveInput);
+    decode::decode_derive(derive_input, &flavor::CONFINED)
         .unwrap_or_else(|e| e.to_compile_error())
         .into()
 }
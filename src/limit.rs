@@ -0,0 +1,162 @@
+// LNP/BP client-side-validation library implementing respective LNPBP
+// specifications & standards (LNPBP-7, 8, 9, 42)
+//
+// Written in 2019-2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the Apache 2.0 License along with this
+// software. If not, see <https://opensource.org/licenses/Apache-2.0>.
+
+use proc_macro2::TokenStream as TokenStream2;
+use syn::ext::IdentExt;
+use syn::Ident;
+
+use crate::flavor::Import;
+
+/// Returns definition of `__StrictLimit` adapter type, which fails all reads
+/// and writes going beyond the given number of bytes, remembering that the
+/// limit was exceeded. Since proc macro crates can't export regular items, the
+/// adapter is generated inside the body of the function using it.
+fn limit_adapter() -> TokenStream2 {
+    quote! {
+        struct __StrictLimit<T> {
+            inner: T,
+            remaining: u64,
+            exceeded: bool,
+        }
+
+        impl<T> __StrictLimit<T> {
+            fn new(inner: T, limit: u64) -> Self {
+                __StrictLimit {
+                    inner,
+                    remaining: limit,
+                    exceeded: false,
+                }
+            }
+
+            fn exceed(&mut self) -> ::std::io::Error {
+                self.exceeded = true;
+                ::std::io::Error::new(
+                    ::std::io::ErrorKind::Other,
+                    "maximum encoded length exceeded",
+                )
+            }
+        }
+
+        impl<T: ::std::io::Write> ::std::io::Write for __StrictLimit<T> {
+            fn write(&mut self, buf: &[u8]) -> ::std::io::Result<usize> {
+                if buf.len() as u64 > self.remaining {
+                    return Err(self.exceed());
+                }
+                let len = self.inner.write(buf)?;
+                self.remaining -= len as u64;
+                Ok(len)
+            }
+
+            fn flush(&mut self) -> ::std::io::Result<()> {
+                self.inner.flush()
+            }
+        }
+
+        impl<T: ::std::io::Read> ::std::io::Read for __StrictLimit<T> {
+            fn read(&mut self, buf: &mut [u8]) -> ::std::io::Result<usize> {
+                if buf.is_empty() {
+                    return Ok(0);
+                }
+                // Once the limit is reached, a single byte is probed to tell
+                // the end of data from the data exceeding the limit
+                if self.remaining == 0 {
+                    return match self.inner.read(&mut [0u8])? {
+                        0 => Ok(0),
+                        _ => Err(self.exceed()),
+                    };
+                }
+                let max = buf.len().min(self.remaining as usize);
+                let len = self.inner.read(&mut buf[..max])?;
+                self.remaining -= len as u64;
+                Ok(len)
+            }
+        }
+    }
+}
+
+/// Wraps the body of `strict_encode` method, such that writing more than
+/// `max_encoded_len` bytes fails with `Error::DataIntegrityError`. The body is
+/// returned unchanged if no limit is given.
+pub(crate) fn encode_limited(
+    body: TokenStream2,
+    limit: Option<u64>,
+    ident_name: &Ident,
+    import: &Import,
+) -> TokenStream2 {
+    let limit = match limit {
+        Some(limit) => limit,
+        None => return body,
+    };
+    let adapter = limit_adapter();
+    let error = limit_error(limit, ident_name, import);
+    quote! {
+        #adapter
+        let mut __strict_limit = __StrictLimit::new(&mut __strict_e, #limit);
+        let __strict_result = (|| -> Result<usize, #import::Error> {
+            #[allow(unused_mut)]
+            let mut __strict_e = &mut __strict_limit;
+            #body
+        })();
+        match __strict_result {
+            Err(_) if __strict_limit.exceeded => #error,
+            result => result,
+        }
+    }
+}
+
+/// Wraps the body of `strict_decode` method (or other decoding method
+/// returning `Result<output, Error>`), such that reading more than
+/// `max_encoded_len` bytes fails with `Error::DataIntegrityError`. The body is
+/// returned unchanged if no limit is given.
+pub(crate) fn decode_limited(
+    body: TokenStream2,
+    output: TokenStream2,
+    limit: Option<u64>,
+    ident_name: &Ident,
+    import: &Import,
+) -> TokenStream2 {
+    let limit = match limit {
+        Some(limit) => limit,
+        None => return body,
+    };
+    let adapter = limit_adapter();
+    let error = limit_error(limit, ident_name, import);
+    quote! {
+        #adapter
+        let mut __strict_limit = __StrictLimit::new(&mut __strict_d, #limit);
+        let __strict_result = (|| -> Result<#output, #import::Error> {
+            #[allow(unused_mut)]
+            let mut __strict_d = &mut __strict_limit;
+            #body
+        })();
+        match __strict_result {
+            Err(_) if __strict_limit.exceeded => #error,
+            result => result,
+        }
+    }
+}
+
+fn limit_error(
+    limit: u64,
+    ident_name: &Ident,
+    import: &Import,
+) -> TokenStream2 {
+    let type_name = ident_name.unraw().to_string();
+    quote! {
+        Err(#import::Error::DataIntegrityError(format!(
+            "{} data exceed `max_encoded_len` of {} bytes",
+            #type_name, #limit,
+        )))
+    }
+}
@@ -12,24 +12,350 @@
 // You should have received a copy of the Apache 2.0 License along with this
 // software. If not, see <https://opensource.org/licenses/Apache-2.0>.
 
-use proc_macro2::Span;
+use proc_macro2::{Literal, Span, TokenStream as TokenStream2};
+use quote::ToTokens;
 use std::convert::TryInto;
-use syn::{Error, Ident, LitInt, Path, Result};
+use syn::parse::Parse;
+use syn::punctuated::Punctuated;
+use syn::spanned::Spanned;
+use syn::{
+    DataUnion, Error, Expr, Field, Fields, GenericArgument, Ident, Lit, LitInt,
+    LitStr, Member, Path, PathArguments, Result, Token, Type, TypeArray,
+    WherePredicate,
+};
 
 use amplify::proc_attr::{
-    ArgValue, ArgValueReq, AttrReq, LiteralClass, ParametrizedAttr, ValueClass,
+    ArgValue, ArgValueReq, AttrReq, LiteralClass, ParametrizedAttr, TypeClass,
+    ValueClass,
 };
 
+use crate::checksum::CHECKSUMS;
+use crate::ATTR_NAME;
+
+/// Default maximal number of tuple elements supported by `tuple_fields`
+/// attribute argument, matching the arity of tuple trait implementations in
+/// `std`; may be changed for a field with `tuple_arity` argument.
+const MAX_TUPLE_ARITY: u64 = 12;
+
+/// Pairs of attribute arguments which can't be used together, including the
+/// case when one of them is inherited from the type level.
+const EXCLUSIVE_ARGS: &[(&str, &str)] = &[("by_value", "by_order")];
+
+/// Attribute arguments which are allowed only at the type declaration level and
+/// must not be propagated down to enum variants and fields.
+const GLOBAL_ARGS: &[&str] = &[
+    "crate",
+    "repr",
+    "transparent",
+    "via_deref",
+    "emit_decode_into",
+    "into",
+    "try_from",
+    "error_type",
+    "construct_with",
+    "magic",
+    "length_prefixed",
+    "length_suffix",
+    "field_prefix",
+    "field_prefix_type",
+    "max_len",
+    "max_encoded_len",
+    "sequence",
+    "allow_order_skew",
+    "ignore_trailing",
+    "ignore_trailing_all",
+    "version",
+    "optional_tail",
+    "exact",
+    "default_variant",
+    "union_tag",
+    "no_tag",
+    "count_assert",
+    "const_values",
+    "checksum",
+    "optional_checksum",
+    "proptest",
+    "dyn_safe",
+    "emit_append",
+    "emit_canonical",
+    "emit_to_vec",
+    "scoped_helpers",
+    "helpers",
+    "len_helper",
+    "conversions",
+    "encode_only",
+    "decode_only",
+    "pad_to",
+    "align",
+    "batch_writes",
+    "c_layout",
+    "const_size",
+    "size_hint",
+    "bitpack",
+    "type_tagged",
+    "name",
+    "inline",
+    "bound",
+    "encode_bound",
+    "decode_bound",
+    "no_bound",
+    "no_encode_bound",
+    "no_decode_bound",
+];
+
+/// Standard library collection types, which can be confined with type-level
+/// `max_len` argument.
+const STD_COLLECTIONS: &[&str] = &[
+    "Vec",
+    "VecDeque",
+    "LinkedList",
+    "String",
+    "HashMap",
+    "HashSet",
+    "BTreeMap",
+    "BTreeSet",
+];
+
+/// Arguments of enum variants which are not inherited by the variant fields.
+const NON_INHERITED_ARGS: &[&str] = &["dumb"];
+
+/// Encoding parameters parsed from `#[strict_encoding]` attributes, constructed
+/// only with [`EncodingDerive::try_from`].
+///
+/// The type is not a part of the crate API: being a `proc-macro` crate, it can
+/// export nothing but the derive macros, so extension crates have to parse the
+/// attributes on their own, and new parameters can be added here freely.
 #[derive(Clone)]
 pub(crate) struct EncodingDerive {
-    pub use_crate: Path,
+    pub use_crate: Option<Path>,
     pub skip: bool,
+    pub byte_blob: bool,
+    pub sorted_map: bool,
+    pub tuple_fields: bool,
+    pub tuple_arity: Option<u64>,
+    pub collection: Option<Expr>,
+    pub dumb: Option<Option<Expr>>,
+    pub max_len: Option<u64>,
+    pub count_type: Option<Ident>,
+    pub max_encoded_len: Option<u64>,
+    pub assert_eq: Option<Expr>,
+    pub transparent: bool,
+    pub via_deref: bool,
+    pub decode_into: bool,
     pub by_order: bool,
-    pub value: Option<LitInt>,
-    pub repr: Ident,
+    pub value: Option<Expr>,
+    pub deprecated: bool,
+    pub repr: Path,
+    pub default_variant: Option<Ident>,
+    pub union_tag: Option<Ident>,
+    pub no_tag: bool,
+    pub count_assert: Option<u64>,
+    pub const_values: bool,
+    pub into: Option<Type>,
+    pub try_from: Option<Type>,
+    pub error_type: Option<Type>,
+    pub construct_with: Option<Path>,
+    pub magic: Option<Vec<u8>>,
+    pub length_prefixed: Option<Ident>,
+    pub length_suffix: Option<Ident>,
+    pub field_prefix: Option<Ident>,
+    pub ignore_trailing: Option<u64>,
+    pub ignore_trailing_all: bool,
+    pub exact: bool,
+    pub checksum: Option<String>,
+    pub optional_checksum: bool,
+    pub proptest: bool,
+    pub dyn_safe: bool,
+    pub append: bool,
+    pub canonical: bool,
+    pub to_vec: bool,
+    pub scoped_helpers: bool,
+    pub helpers: bool,
+    pub len_helper: bool,
+    pub conversions: bool,
+    pub encode_only: bool,
+    pub decode_only: bool,
+    pub pad_to: Option<u64>,
+    pub align: Option<u64>,
+    pub batch_writes: bool,
+    pub c_layout: bool,
+    pub const_size: bool,
+    pub size_hint: bool,
+    pub bitpack: bool,
+    pub type_tagged: bool,
+    pub name: Option<String>,
+    pub inline: Option<String>,
+    pub encode_bound: Option<Vec<WherePredicate>>,
+    pub decode_bound: Option<Vec<WherePredicate>>,
+    pub encode_no_bound: Vec<Ident>,
+    pub decode_no_bound: Vec<Ident>,
+    pub version: Option<u64>,
+    pub version_repr: Ident,
+    pub since: Option<u64>,
+    pub until: Option<u64>,
+    pub optional_tail: bool,
+    pub default_on_eof: bool,
+    pub default: Option<Expr>,
+    pub skip_if_const: Option<Expr>,
+    pub replace_with: Option<Expr>,
+    pub order: Option<u64>,
+    pub encode_order: Option<u64>,
+    pub decode_order: Option<u64>,
+    pub sequence: Option<Vec<Member>>,
+    pub allow_order_skew: bool,
 }
 
 impl EncodingDerive {
+    /// Returns maximal value which can be represented by the enum `repr`
+    /// type, or `None` if the type is given by an alias and is not known.
+    pub(crate) fn repr_max(&self) -> Option<u64> {
+        match self.repr.get_ident()?.to_string().as_str() {
+            "u8" => Some(u8::MAX as u64),
+            "u16" => Some(u16::MAX as u64),
+            "u32" => Some(u32::MAX as u64),
+            "u64" => Some(u64::MAX),
+            _ => None,
+        }
+    }
+
+    /// Removes arguments which are allowed only at the type declaration level
+    /// (or apply only to the enum variant itself), such that the attribute can
+    /// be merged with variant- or field-level attributes.
+    pub(crate) fn strip_global_args(attr: &mut ParametrizedAttr) {
+        for name in GLOBAL_ARGS.iter().chain(NON_INHERITED_ARGS) {
+            attr.args.remove(*name);
+        }
+    }
+
+    /// Passes type-level `max_len` argument of an enum to the attribute of its
+    /// variant, from which the argument is taken by the variant fields.
+    pub(crate) fn inherit_max_len(
+        global: &ParametrizedAttr,
+        variant: &mut ParametrizedAttr,
+    ) {
+        if let Some(max_len) = global.args.get("max_len") {
+            variant
+                .args
+                .entry("max_len".to_owned())
+                .or_insert_with(|| max_len.clone());
+        }
+    }
+
+    /// Merges arguments of a field or enum variant attribute into the
+    /// arguments inherited from the enclosing declaration (`parent_level`).
+    /// Unlike [`ParametrizedAttr::merged`], reports the argument which caused
+    /// the conflict by its name and levels, using the span of the field or
+    /// variant.
+    pub(crate) fn merged(
+        parent: &ParametrizedAttr,
+        local: ParametrizedAttr,
+        span: Span,
+        level: &str,
+        parent_level: &str,
+    ) -> Result<ParametrizedAttr> {
+        for (name, value) in &local.args {
+            if let Some(inherited) = parent.args.get(name) {
+                if inherited.to_token_stream().to_string()
+                    != value.to_token_stream().to_string()
+                {
+                    return Err(Error::new(
+                        span,
+                        format!(
+                            "`{}` argument is given both at the {} level and \
+                             at the {} level with different values",
+                            name, parent_level, level
+                        ),
+                    ));
+                }
+            }
+        }
+        for (local_name, parent_name) in
+            EXCLUSIVE_ARGS.iter().flat_map(|(a, b)| [(a, b), (b, a)])
+        {
+            if has_arg(&local, local_name) && has_arg(parent, parent_name) {
+                return Err(Error::new(
+                    span,
+                    format!(
+                        "`{}` argument at the {} level conflicts with `{}` \
+                         argument given at the {} level",
+                        local_name, level, parent_name, parent_level
+                    ),
+                ));
+            }
+        }
+        parent.clone().merged(local).map_err(|err| {
+            Error::new(
+                span,
+                format!(
+                    "conflicting `{}` attribute values at the {} and {} \
+                     levels: {}",
+                    ATTR_NAME, parent_level, level, err
+                ),
+            )
+        })
+    }
+
+    /// Returns `#[inline]` attribute for the generated encoding and decoding
+    /// methods, adjusted with `inline` argument.
+    pub(crate) fn inline_attr(&self) -> TokenStream2 {
+        match self.inline.as_deref() {
+            Some("always") => quote! { #[inline(always)] },
+            Some("never") => quote! { #[inline(never)] },
+            _ => quote! { #[inline] },
+        }
+    }
+
+    /// Returns visibility of the generated auxiliary inherent helpers, which
+    /// are kept crate-private when `scoped_helpers` argument is given.
+    pub(crate) fn helper_vis(&self) -> TokenStream2 {
+        if self.scoped_helpers {
+            quote! { pub(crate) }
+        } else {
+            quote! { pub }
+        }
+    }
+
+    /// Wraps auxiliary items generated for the type into an anonymous `const`
+    /// scope if `scoped_helpers` argument is given.
+    pub(crate) fn scoped(&self, helpers: TokenStream2) -> TokenStream2 {
+        if self.scoped_helpers && !helpers.is_empty() {
+            quote! {
+                const _: () = {
+                    #helpers
+                };
+            }
+        } else {
+            helpers
+        }
+    }
+
+    /// Checks that `since`/`until` field attributes are consistent with the
+    /// `version` of the structure and returns whether the field is present in
+    /// the encoding of that version.
+    pub(crate) fn is_present_in(&self, version: Option<u64>) -> Result<bool> {
+        if self.since.is_none() && self.until.is_none() {
+            return Ok(true);
+        }
+        let version = version.ok_or_else(|| {
+            Error::new(
+                Span::call_site(),
+                "`since` and `until` attributes require `version` attribute on the structure",
+            )
+        })?;
+        match (self.since, self.until) {
+            (Some(since), Some(until)) if since > until => Err(Error::new(
+                Span::call_site(),
+                "`since` version must not be greater than `until` version",
+            )),
+            (Some(since), _) if since > version => Err(Error::new(
+                Span::call_site(),
+                "`since` version must not be greater than the structure `version`",
+            )),
+            (_, Some(until)) => Ok(version <= until),
+            _ => Ok(true),
+        }
+    }
+
     pub(crate) fn try_from(
         attr: &mut ParametrizedAttr,
         is_global: bool,
@@ -37,29 +363,211 @@ impl EncodingDerive {
     ) -> Result<EncodingDerive> {
         let mut map = if is_global {
             map! {
-                "crate" => ArgValueReq::with_default(ident!(strict_encoding))
+                "crate" => ArgValueReq::Optional(ValueClass::Type(TypeClass::Path)),
+                "into" => ArgValueReq::Optional(ValueClass::str()),
+                "try_from" => ArgValueReq::Optional(ValueClass::str()),
+                "error_type" => ArgValueReq::Optional(ValueClass::str()),
+                "proptest" => ArgValueReq::Prohibited,
+                "dyn_safe" => ArgValueReq::Prohibited,
+                "emit_append" => ArgValueReq::Prohibited,
+                "emit_canonical" => ArgValueReq::Prohibited,
+                "emit_to_vec" => ArgValueReq::Prohibited,
+                "scoped_helpers" => ArgValueReq::Prohibited,
+                "helpers" => ArgValueReq::Prohibited,
+                "len_helper" => ArgValueReq::Prohibited,
+                "conversions" => ArgValueReq::Prohibited,
+                "max_len" => ArgValueReq::Optional(ValueClass::Literal(LiteralClass::Int)),
+                "max_encoded_len" => ArgValueReq::Optional(ValueClass::Literal(LiteralClass::Int)),
+                "encode_only" => ArgValueReq::Prohibited,
+                "decode_only" => ArgValueReq::Prohibited,
+                "type_tagged" => ArgValueReq::Prohibited,
+                "name" => ArgValueReq::Optional(ValueClass::str()),
+                "inline" => ArgValueReq::Optional(ValueClass::str()),
+                "bound" => ArgValueReq::Optional(ValueClass::str()),
+                "encode_bound" => ArgValueReq::Optional(ValueClass::str()),
+                "decode_bound" => ArgValueReq::Optional(ValueClass::str()),
+                "no_bound" => ArgValueReq::Optional(ValueClass::str()),
+                "no_encode_bound" => ArgValueReq::Optional(ValueClass::str()),
+                "no_decode_bound" => ArgValueReq::Optional(ValueClass::str())
             }
         } else {
             map! {
-                "skip" => ArgValueReq::Prohibited
+                "skip" => ArgValueReq::Prohibited,
+                "no_skip" => ArgValueReq::Prohibited,
+                "byte_blob" => ArgValueReq::Prohibited,
+                "sorted_map" => ArgValueReq::Prohibited,
+                "sort_keys" => ArgValueReq::Prohibited,
+                "tuple_fields" => ArgValueReq::Prohibited,
+                "tuple_arity" => ArgValueReq::Optional(ValueClass::Literal(LiteralClass::Int)),
+                "collection" => ArgValueReq::Optional(ValueClass::str()),
+                "max_len" => ArgValueReq::Optional(ValueClass::Literal(LiteralClass::Int)),
+                "count_type" => ArgValueReq::Optional(ValueClass::Type(TypeClass::Path))
             }
         };
 
+        if !is_global {
+            // Fields take placeholder value expression, while enum variants
+            // are just marked as the placeholder one
+            let dumb_req = match attr.args.get("dumb") {
+                Some(ArgValue::Literal(_)) => {
+                    ArgValueReq::Optional(ValueClass::str())
+                }
+                _ => ArgValueReq::Prohibited,
+            };
+            map.insert("dumb", dumb_req);
+            // Expected value can be given either as an integer literal or as
+            // a path to a constant
+            let assert_class = match attr.args.get("assert_eq") {
+                Some(ArgValue::Type(_)) => ValueClass::Type(TypeClass::Path),
+                _ => ValueClass::Literal(LiteralClass::Int),
+            };
+            map.insert("assert_eq", ArgValueReq::Optional(assert_class));
+        }
+
+        if !is_enum {
+            map.insert("optional_tail", ArgValueReq::Prohibited);
+        }
+
+        if !is_global && !is_enum {
+            map.insert("default_on_eof", ArgValueReq::Prohibited);
+            map.insert("default", ArgValueReq::Optional(ValueClass::str()));
+            map.insert(
+                "skip_if_const",
+                ArgValueReq::Optional(ValueClass::str()),
+            );
+            map.insert(
+                "order",
+                ArgValueReq::Optional(ValueClass::Literal(LiteralClass::Int)),
+            );
+            map.insert(
+                "encode_order",
+                ArgValueReq::Optional(ValueClass::Literal(LiteralClass::Int)),
+            );
+            map.insert(
+                "decode_order",
+                ArgValueReq::Optional(ValueClass::Literal(LiteralClass::Int)),
+            );
+            map.insert(
+                "since",
+                ArgValueReq::Optional(ValueClass::Literal(LiteralClass::Int)),
+            );
+            map.insert(
+                "until",
+                ArgValueReq::Optional(ValueClass::Literal(LiteralClass::Int)),
+            );
+        }
+
+        if is_global && !is_enum {
+            map.insert("transparent", ArgValueReq::Prohibited);
+            map.insert("via_deref", ArgValueReq::Prohibited);
+            map.insert("emit_decode_into", ArgValueReq::Prohibited);
+            map.insert(
+                "construct_with",
+                ArgValueReq::Optional(ValueClass::str()),
+            );
+            // Magic can be given either as an integer or a byte string
+            let magic_class = match attr.args.get("magic") {
+                Some(ArgValue::Literal(Lit::ByteStr(_))) => {
+                    LiteralClass::ByteStr
+                }
+                _ => LiteralClass::Int,
+            };
+            map.insert(
+                "magic",
+                ArgValueReq::Optional(ValueClass::Literal(magic_class)),
+            );
+            map.insert(
+                "length_prefixed",
+                ArgValueReq::Optional(ValueClass::Type(TypeClass::Path)),
+            );
+            map.insert(
+                "length_suffix",
+                ArgValueReq::Optional(ValueClass::Type(TypeClass::Path)),
+            );
+            map.insert("field_prefix", ArgValueReq::Prohibited);
+            map.insert("sequence", ArgValueReq::Optional(ValueClass::str()));
+            map.insert("allow_order_skew", ArgValueReq::Prohibited);
+            map.insert(
+                "field_prefix_type",
+                ArgValueReq::Optional(ValueClass::Type(TypeClass::Path)),
+            );
+            map.insert(
+                "ignore_trailing",
+                ArgValueReq::Optional(ValueClass::Literal(LiteralClass::Int)),
+            );
+            map.insert("ignore_trailing_all", ArgValueReq::Prohibited);
+            map.insert("exact", ArgValueReq::Prohibited);
+            map.insert("checksum", ArgValueReq::Optional(ValueClass::str()));
+            map.insert(
+                "optional_checksum",
+                ArgValueReq::Optional(ValueClass::str()),
+            );
+            map.insert("batch_writes", ArgValueReq::Prohibited);
+            map.insert("c_layout", ArgValueReq::Prohibited);
+            map.insert("const_size", ArgValueReq::Prohibited);
+            map.insert("size_hint", ArgValueReq::Prohibited);
+            map.insert("bitpack", ArgValueReq::Prohibited);
+            map.insert(
+                "pad_to",
+                ArgValueReq::Optional(ValueClass::Literal(LiteralClass::Int)),
+            );
+            map.insert(
+                "align",
+                ArgValueReq::Optional(ValueClass::Literal(LiteralClass::Int)),
+            );
+            map.insert(
+                "version",
+                ArgValueReq::Optional(ValueClass::Literal(LiteralClass::Int)),
+            );
+        }
+
         if is_enum {
             map.insert("by_order", ArgValueReq::Prohibited);
             map.insert("by_value", ArgValueReq::Prohibited);
             if is_global {
                 map.insert("repr", ArgValueReq::with_default(ident!(u8)));
-            } else {
                 map.insert(
-                    "value",
+                    "default_variant",
+                    ArgValueReq::Optional(ValueClass::str()),
+                );
+                map.insert(
+                    "union_tag",
+                    ArgValueReq::Optional(ValueClass::str()),
+                );
+                map.insert("no_tag", ArgValueReq::Prohibited);
+                map.insert("const_values", ArgValueReq::Prohibited);
+                map.insert(
+                    "count_assert",
                     ArgValueReq::Optional(ValueClass::Literal(
                         LiteralClass::Int,
                     )),
                 );
+            } else {
+                map.insert("deprecated", ArgValueReq::Prohibited);
+                map.insert(
+                    "replace_with",
+                    ArgValueReq::Optional(ValueClass::str()),
+                );
+                // Value can be given either as an integer literal or as a
+                // path to a constant
+                let value_class = match attr.args.get("value") {
+                    Some(ArgValue::Type(_)) => {
+                        ValueClass::Type(TypeClass::Path)
+                    }
+                    _ => ValueClass::Literal(LiteralClass::Int),
+                };
+                map.insert("value", ArgValueReq::Optional(value_class));
             }
         }
 
+        if (is_global || !is_enum) && attr.args.contains_key("value") {
+            return Err(Error::new(
+                Span::call_site(),
+                "`value` attribute is only valid on enum variants and union \
+                 fields",
+            ));
+        }
+
         attr.check(AttrReq::with(map))?;
 
         if attr.args.contains_key("by_value")
@@ -71,50 +579,1381 @@ impl EncodingDerive {
             ));
         }
 
-        let repr: Ident = attr
+        let repr: Path = attr
             .args
             .get("repr")
             .cloned()
             .map(|arg| arg.try_into())
             .transpose()
             .expect(
-                "amplify_syn is broken: attribute `repr` required to be Ident",
+                "amplify_syn is broken: attribute `repr` required to be Path",
+            )
+            .unwrap_or_else(|| ident!(u8).into());
+
+        // Single-identifier paths naming a primitive type must be one of the
+        // supported unsigned integers; any other path is treated as a type
+        // alias resolving to an integer type and is not validated here.
+        if let Some(ident) = repr.get_ident() {
+            match ident.to_string().as_str() {
+                "u8" | "u16" | "u32" | "u64" => {}
+                "u128" | "usize" | "i8" | "i16" | "i32" | "i64" | "i128"
+                | "isize" | "f32" | "f64" | "bool" | "char" | "str" => {
+                    return Err(Error::new(
+                        Span::call_site(),
+                        "`repr` requires integer type identifier",
+                    ))
+                }
+                _ => {}
+            }
+        }
+
+        let use_crate = attr.args.get("crate").cloned().map(|arg| {
+            arg.try_into().expect(
+                "amplify_syn is broken: requirements for crate arg are not satisfied",
             )
-            .unwrap_or_else(|| ident!(u8));
+        });
+
+        let value = match attr.args.get("value") {
+            None => None,
+            Some(ArgValue::Literal(lit)) => Some(parse_quote! { #lit }),
+            Some(ArgValue::Type(ty)) => Some(parse_quote! { #ty }),
+            Some(ArgValue::None) => unreachable!(
+                "amplify_syn is broken: requirements for value arg are not satisfied"
+            ),
+        };
+
+        let skip = attr.args.contains_key("skip");
+        if skip && attr.args.contains_key("no_skip") {
+            return Err(Error::new(
+                Span::call_site(),
+                "`skip` and `no_skip` attributes can't be present together",
+            ));
+        }
+
+        let dumb = match attr.args.get("dumb") {
+            Some(ArgValue::Literal(_)) => Some(parsed_arg(attr, "dumb")?),
+            Some(_) => Some(None),
+            None => None,
+        };
+
+        let replace_with = parsed_arg(attr, "replace_with")?;
+        if replace_with.is_some() && !skip {
+            return Err(Error::new(
+                Span::call_site(),
+                "`replace_with` requires `skip` attribute",
+            ));
+        }
+
+        let skip_if_const = parsed_arg(attr, "skip_if_const")?;
+        if skip && skip_if_const.is_some() {
+            return Err(Error::new(
+                Span::call_site(),
+                "`skip` and `skip_if_const` attributes can't be present together",
+            ));
+        }
+
+        let deprecated = attr.args.contains_key("deprecated");
+
+        let proptest = attr.args.contains_key("proptest");
+
+        let dyn_safe = attr.args.contains_key("dyn_safe");
+        let append = attr.args.contains_key("emit_append");
+        let canonical = attr.args.contains_key("emit_canonical");
+        let to_vec = attr.args.contains_key("emit_to_vec");
+        let scoped_helpers = attr.args.contains_key("scoped_helpers");
+        let helpers = attr.args.contains_key("helpers");
+        let len_helper = attr.args.contains_key("len_helper");
+        let conversions = attr.args.contains_key("conversions");
+        let encode_only = attr.args.contains_key("encode_only");
+        let decode_only = attr.args.contains_key("decode_only");
+        if encode_only && decode_only {
+            return Err(Error::new(
+                Span::call_site(),
+                "`encode_only` and `decode_only` arguments can't be present together",
+            ));
+        }
+
+        let byte_blob = attr.args.contains_key("byte_blob");
 
-        match repr.to_string().as_str() {
-            "u8" | "u16" | "u32" | "u64" => {}
-            _ => {
+        // `sort_keys` is an alias of `sorted_map`
+        let sorted_map = attr.args.contains_key("sorted_map")
+            || attr.args.contains_key("sort_keys");
+
+        let tuple_fields = attr.args.contains_key("tuple_fields");
+        if tuple_fields && (byte_blob || sorted_map) {
+            return Err(Error::new(
+                Span::call_site(),
+                "`tuple_fields` can't be combined with `byte_blob`, `sorted_map` or `sort_keys` attributes",
+            ));
+        }
+        let tuple_arity = uint_lit_arg(attr, "tuple_arity")?;
+        if tuple_arity.is_some() && !tuple_fields {
+            return Err(Error::new(
+                Span::call_site(),
+                "`tuple_arity` requires `tuple_fields` attribute",
+            ));
+        }
+
+        let collection = parsed_arg(attr, "collection")?;
+        if collection.is_some() && (tuple_fields || byte_blob || sorted_map) {
+            return Err(Error::new(
+                Span::call_site(),
+                "`collection` can't be combined with `tuple_fields`, `byte_blob`, `sorted_map` or `sort_keys` attributes",
+            ));
+        }
+        let count_type = match attr.args.get("count_type") {
+            None => None,
+            Some(arg) => Some(uint_arg(arg.clone(), "count_type")?),
+        };
+        if count_type.is_some() && (tuple_fields || byte_blob || sorted_map) {
+            return Err(Error::new(
+                Span::call_site(),
+                "`count_type` can't be combined with `tuple_fields`, `byte_blob`, `sorted_map` or `sort_keys` attributes",
+            ));
+        }
+        let max_len = uint_lit_arg(attr, "max_len")?;
+        let max_encoded_len = uint_lit_arg(attr, "max_encoded_len")?;
+
+        let assert_eq = match attr.args.get("assert_eq") {
+            None => None,
+            Some(ArgValue::Literal(lit)) => Some(parse_quote! { #lit }),
+            Some(ArgValue::Type(ty)) => Some(parse_quote! { #ty }),
+            Some(ArgValue::None) => unreachable!(
+                "amplify_syn is broken: requirements for assert_eq arg are not satisfied"
+            ),
+        };
+        if assert_eq.is_some() && (skip || skip_if_const.is_some()) {
+            return Err(Error::new(
+                Span::call_site(),
+                "`assert_eq` can't be combined with `skip` or `skip_if_const` attributes",
+            ));
+        }
+
+        // Encoding through `Deref` target is a special case of transparent
+        // encoding and shares all of its restrictions
+        let via_deref = attr.args.contains_key("via_deref");
+        let transparent = attr.args.contains_key("transparent") || via_deref;
+
+        let decode_into = attr.args.contains_key("emit_decode_into");
+
+        let by_order = !attr.args.contains_key("by_value");
+
+        let optional_tail = attr.args.contains_key("optional_tail");
+
+        let default_on_eof = attr.args.contains_key("default_on_eof");
+        let default = parsed_arg(attr, "default")?;
+        if default.is_some() && !default_on_eof {
+            return Err(Error::new(
+                Span::call_site(),
+                "`default` requires `default_on_eof` attribute",
+            ));
+        }
+
+        let default_variant = parsed_arg(attr, "default_variant")?;
+        let union_tag = parsed_arg(attr, "union_tag")?;
+        let no_tag = attr.args.contains_key("no_tag");
+        let count_assert = uint_lit_arg(attr, "count_assert")?;
+        let const_values = attr.args.contains_key("const_values");
+        if no_tag && (union_tag.is_some() || proptest) {
+            return Err(Error::new(
+                Span::call_site(),
+                "`no_tag` can't be combined with `union_tag` or `proptest` attributes",
+            ));
+        }
+
+        let into = parsed_arg(attr, "into")?;
+        let try_from = parsed_arg(attr, "try_from")?;
+        let error_type = parsed_arg(attr, "error_type")?;
+        let construct_with = parsed_arg(attr, "construct_with")?;
+
+        let type_tagged = attr.args.contains_key("type_tagged");
+        let name = match attr.args.get("name").cloned() {
+            None => None,
+            Some(arg) => {
+                let lit: LitStr = arg.try_into().expect(
+                    "amplify_syn is broken: requirements for name arg are not satisfied",
+                );
+                if !lit.value().is_ascii() {
+                    return Err(Error::new(
+                        lit.span(),
+                        "type tag `name` must be an ASCII string",
+                    ));
+                }
+                Some(lit.value())
+            }
+        };
+
+        let inline = match attr.args.get("inline").cloned() {
+            None => None,
+            Some(arg) => {
+                let lit: LitStr = arg.try_into().expect(
+                    "amplify_syn is broken: requirements for inline arg are not satisfied",
+                );
+                if !["always", "never"].contains(&&*lit.value()) {
+                    return Err(Error::new(
+                        lit.span(),
+                        "`inline` must be either \"always\" or \"never\"",
+                    ));
+                }
+                Some(lit.value())
+            }
+        };
+
+        let bound = where_predicates(attr, "bound")?;
+        let encode_bound =
+            where_predicates(attr, "encode_bound")?.or_else(|| bound.clone());
+        let decode_bound = where_predicates(attr, "decode_bound")?.or(bound);
+
+        let no_bound = ident_list(attr, "no_bound")?;
+        let mut encode_no_bound = ident_list(attr, "no_encode_bound")?;
+        encode_no_bound.extend(no_bound.iter().cloned());
+        let mut decode_no_bound = ident_list(attr, "no_decode_bound")?;
+        decode_no_bound.extend(no_bound);
+
+        if name.is_some() && !type_tagged {
+            return Err(Error::new(
+                Span::call_site(),
+                "`name` requires `type_tagged` attribute",
+            ));
+        }
+        if type_tagged && (transparent || into.is_some() || try_from.is_some())
+        {
+            return Err(Error::new(
+                Span::call_site(),
+                "`type_tagged` can't be combined with `transparent`, `into` or `try_from` attributes",
+            ));
+        }
+
+        let magic = match attr.args.get("magic") {
+            None => None,
+            Some(ArgValue::Literal(Lit::ByteStr(bytes))) => Some(bytes.value()),
+            Some(ArgValue::Literal(Lit::Int(int))) => Some(magic_int(int)?),
+            Some(_) => {
+                return Err(Error::new(
+                    Span::call_site(),
+                    "`magic` requires integer or byte string literal value",
+                ))
+            }
+        };
+
+        let length_prefixed = match attr.args.get("length_prefixed") {
+            None => None,
+            Some(arg) => Some(uint_arg(arg.clone(), "length_prefixed")?),
+        };
+        let length_suffix = match attr.args.get("length_suffix") {
+            None => None,
+            Some(arg) => Some(uint_arg(arg.clone(), "length_suffix")?),
+        };
+
+        let field_prefix = match attr.args.get("field_prefix_type") {
+            Some(_) if !attr.args.contains_key("field_prefix") => {
                 return Err(Error::new(
                     Span::call_site(),
-                    "`repr` requires integer type identifier",
+                    "`field_prefix_type` requires `field_prefix` attribute",
                 ))
             }
+            Some(arg) => Some(uint_arg(arg.clone(), "field_prefix_type")?),
+            None if attr.args.contains_key("field_prefix") => Some(ident!(u8)),
+            None => None,
+        };
+
+        let ignore_trailing = uint_lit_arg(attr, "ignore_trailing")?;
+
+        let ignore_trailing_all = attr.args.contains_key("ignore_trailing_all");
+
+        let exact = attr.args.contains_key("exact");
+
+        if exact && (ignore_trailing.is_some() || ignore_trailing_all) {
+            return Err(Error::new(
+                Span::call_site(),
+                "`exact` and `ignore_trailing` attributes can't be present together",
+            ));
         }
 
-        let use_crate = attr
+        if attr.args.contains_key("checksum")
+            && attr.args.contains_key("optional_checksum")
+        {
+            return Err(Error::new(
+                Span::call_site(),
+                "`checksum` and `optional_checksum` attributes can't be present together",
+            ));
+        }
+        // Optional checksum is written in the same way as the mandatory one,
+        // differing only in its decoding
+        let optional_checksum = attr.args.contains_key("optional_checksum");
+        let checksum = match attr
             .args
-            .get("crate")
+            .get("checksum")
+            .or_else(|| attr.args.get("optional_checksum"))
             .cloned()
-            .unwrap_or_else(|| ArgValue::from(ident!(strict_encoding)))
-            .try_into()
-            .expect("amplify_syn is broken: requirements for crate arg are not satisfied");
+        {
+            None => None,
+            Some(arg) => {
+                let lit: LitStr = arg.try_into().expect(
+                    "amplify_syn is broken: requirements for checksum arg are not satisfied",
+                );
+                if !CHECKSUMS.contains(&&*lit.value()) {
+                    return Err(Error::new(
+                        lit.span(),
+                        format!(
+                            "unsupported checksum algorithm; supported algorithms are: {}",
+                            CHECKSUMS.join(", ")
+                        ),
+                    ));
+                }
+                Some(lit.value())
+            }
+        };
 
-        let value = attr
-            .args
-            .get("value")
-            .map(|a| a.clone().try_into().expect("amplify_syn is broken: requirements for value arg are not satisfied"));
+        if checksum.is_some()
+            && (transparent
+                || exact
+                || ignore_trailing.is_some()
+                || ignore_trailing_all)
+        {
+            return Err(Error::new(
+                Span::call_site(),
+                "`checksum` can't be combined with `transparent`, `exact` or `ignore_trailing` attributes",
+            ));
+        }
 
-        let skip = attr.args.get("skip").is_some();
+        let pad_to = uint_lit_arg(attr, "pad_to")?;
+        let align = uint_lit_arg(attr, "align")?;
+        // Presence of the optional checksum is detected from the remaining
+        // data, so nothing may follow it
+        if optional_checksum && (pad_to.is_some() || align.is_some()) {
+            return Err(Error::new(
+                Span::call_site(),
+                "`optional_checksum` can't be combined with `pad_to` or `align` attributes",
+            ));
+        }
+        if align == Some(0) {
+            return Err(Error::new(
+                Span::call_site(),
+                "`align` requires non-zero number of bytes",
+            ));
+        }
 
-        let by_order = !attr.args.contains_key("by_value");
+        let batch_writes = attr.args.contains_key("batch_writes");
+        let const_size = attr.args.contains_key("const_size");
+        let size_hint = attr.args.contains_key("size_hint");
+        let bitpack = attr.args.contains_key("bitpack");
+        if bitpack && (transparent || batch_writes) {
+            return Err(Error::new(
+                Span::call_site(),
+                "`bitpack` can't be combined with `transparent` or `batch_writes` attributes",
+            ));
+        }
+        if batch_writes && transparent {
+            return Err(Error::new(
+                Span::call_site(),
+                "`batch_writes` and `transparent` attributes can't be present together",
+            ));
+        }
+        let c_layout = attr.args.contains_key("c_layout");
+        if c_layout
+            && (transparent
+                || batch_writes
+                || bitpack
+                || optional_tail
+                || decode_into
+                || construct_with.is_some()
+                || const_size
+                || size_hint
+                || magic.is_some()
+                || checksum.is_some())
+        {
+            return Err(Error::new(
+                Span::call_site(),
+                "`c_layout` can't be combined with `transparent`, `batch_writes`, `bitpack`, `optional_tail`, `emit_decode_into`, `construct_with`, `const_size`, `size_hint`, `magic` or `checksum` attributes",
+            ));
+        }
+        if field_prefix.is_some()
+            && (transparent || batch_writes || c_layout || bitpack)
+        {
+            return Err(Error::new(
+                Span::call_site(),
+                "`field_prefix` can't be combined with `transparent`, `batch_writes`, `c_layout` or `bitpack` attributes",
+            ));
+        }
+        if is_global
+            && max_len.is_some()
+            && (transparent || batch_writes || c_layout)
+        {
+            return Err(Error::new(
+                Span::call_site(),
+                "type-level `max_len` can't be combined with `transparent`, `batch_writes` or `c_layout` attributes",
+            ));
+        }
+
+        if pad_to.is_some()
+            && (transparent
+                || exact
+                || ignore_trailing.is_some()
+                || ignore_trailing_all)
+        {
+            return Err(Error::new(
+                Span::call_site(),
+                "`pad_to` can't be combined with `transparent`, `exact` or `ignore_trailing` attributes",
+            ));
+        }
+        if align.is_some()
+            && (pad_to.is_some()
+                || transparent
+                || exact
+                || ignore_trailing.is_some()
+                || ignore_trailing_all)
+        {
+            return Err(Error::new(
+                Span::call_site(),
+                "`align` can't be combined with `pad_to`, `transparent`, `exact` or `ignore_trailing` attributes",
+            ));
+        }
+
+        if exact && transparent {
+            return Err(Error::new(
+                Span::call_site(),
+                "`exact` and `transparent` attributes can't be present together",
+            ));
+        }
+
+        if ignore_trailing.is_some() && ignore_trailing_all {
+            return Err(Error::new(
+                Span::call_site(),
+                "`ignore_trailing` and `ignore_trailing_all` attributes can't be present together",
+            ));
+        }
+
+        if (ignore_trailing.is_some() || ignore_trailing_all) && transparent {
+            return Err(Error::new(
+                Span::call_site(),
+                "`ignore_trailing` and `transparent` attributes can't be present together",
+            ));
+        }
+
+        let (version, version_repr) = match attr.args.get("version") {
+            Some(ArgValue::Literal(Lit::Int(int))) => {
+                let repr =
+                    match int.suffix() {
+                        "" | "u8" => ident!(u8),
+                        "u16" => ident!(u16),
+                        _ => return Err(Error::new(
+                            int.span(),
+                            "`version` requires `u8` or `u16` integer literal",
+                        )),
+                    };
+                let version = if repr == "u8" {
+                    int.base10_parse::<u8>()? as u64
+                } else {
+                    int.base10_parse::<u16>()? as u64
+                };
+                (Some(version), repr)
+            }
+            _ => (None, ident!(u8)),
+        };
+
+        let order = uint_lit_arg(attr, "order")?;
+        let encode_order = uint_lit_arg(attr, "encode_order")?;
+        let decode_order = uint_lit_arg(attr, "decode_order")?;
+        if skip && (encode_order.is_some() || decode_order.is_some()) {
+            return Err(Error::new(
+                Span::call_site(),
+                "skipped fields can't have `encode_order` or `decode_order` arguments",
+            ));
+        }
+        let allow_order_skew = attr.args.contains_key("allow_order_skew");
+
+        let sequence = member_list(attr, "sequence")?;
+        if sequence.is_some() && (optional_tail || transparent || c_layout) {
+            return Err(Error::new(
+                Span::call_site(),
+                "`sequence` can't be combined with `optional_tail`, `transparent` or `c_layout` attributes",
+            ));
+        }
+
+        let since = uint_lit_arg(attr, "since")?;
+        let until = uint_lit_arg(attr, "until")?;
+
+        if version.is_some() && (transparent || c_layout) {
+            return Err(Error::new(
+                Span::call_site(),
+                "`version` can't be combined with `transparent` or `c_layout` attributes",
+            ));
+        }
+
+        if length_prefixed.is_some() && transparent {
+            return Err(Error::new(
+                Span::call_site(),
+                "`length_prefixed` and `transparent` attributes can't be present together",
+            ));
+        }
+        if max_encoded_len.is_some()
+            && (transparent
+                || into.is_some()
+                || try_from.is_some()
+                || length_suffix.is_some())
+        {
+            return Err(Error::new(
+                Span::call_site(),
+                "`max_encoded_len` can't be combined with `transparent`, `into`, `try_from` or `length_suffix` attributes",
+            ));
+        }
+        if matches!(max_encoded_len, Some(limit) if limit > usize::MAX as u64) {
+            return Err(Error::new(
+                Span::call_site(),
+                "`max_encoded_len` value must fit into `usize`",
+            ));
+        }
+        if conversions && (no_tag || length_suffix.is_some()) {
+            return Err(Error::new(
+                Span::call_site(),
+                "`conversions` can't be used with `no_tag` or `length_suffix` attributes, which do not implement `StrictDecode`",
+            ));
+        }
+        if error_type.is_some() && (no_tag || length_suffix.is_some()) {
+            return Err(Error::new(
+                Span::call_site(),
+                "`error_type` can't be used with `no_tag` or `length_suffix` attributes, which do not implement `StrictDecode`",
+            ));
+        }
+        // Length suffix is found from the end of the data, so nothing else
+        // may precede or follow the fields
+        if length_suffix.is_some()
+            && (length_prefixed.is_some()
+                || transparent
+                || decode_into
+                || try_from.is_some()
+                || construct_with.is_some()
+                || type_tagged
+                || magic.is_some()
+                || version.is_some()
+                || checksum.is_some()
+                || pad_to.is_some()
+                || align.is_some()
+                || optional_tail
+                || exact
+                || ignore_trailing.is_some()
+                || ignore_trailing_all)
+        {
+            return Err(Error::new(
+                Span::call_site(),
+                "`length_suffix` can't be combined with `length_prefixed`, `transparent`, `emit_decode_into`, `try_from`, `construct_with`, `type_tagged`, `magic`, `version`, `checksum`, `pad_to`, `align`, `optional_tail`, `exact` or `ignore_trailing` attributes",
+            ));
+        }
+
+        if magic.is_some() && transparent {
+            return Err(Error::new(
+                Span::call_site(),
+                "`magic` and `transparent` attributes can't be present together",
+            ));
+        }
+
+        if construct_with.is_some() && transparent {
+            return Err(Error::new(
+                Span::call_site(),
+                "`construct_with` and `transparent` attributes can't be present together",
+            ));
+        }
 
         Ok(EncodingDerive {
             use_crate,
             skip,
+            byte_blob,
+            sorted_map,
+            count_type,
+            tuple_fields,
+            tuple_arity,
+            collection,
+            dumb,
+            max_len,
+            max_encoded_len,
+            assert_eq,
+            transparent,
+            via_deref,
+            decode_into,
             by_order,
             value,
+            deprecated,
             repr,
+            default_variant,
+            union_tag,
+            no_tag,
+            count_assert,
+            const_values,
+            into,
+            try_from,
+            error_type,
+            construct_with,
+            magic,
+            length_prefixed,
+            length_suffix,
+            field_prefix,
+            ignore_trailing,
+            ignore_trailing_all,
+            exact,
+            checksum,
+            optional_checksum,
+            proptest,
+            dyn_safe,
+            append,
+            canonical,
+            to_vec,
+            scoped_helpers,
+            helpers,
+            len_helper,
+            conversions,
+            encode_only,
+            decode_only,
+            pad_to,
+            align,
+            batch_writes,
+            c_layout,
+            const_size,
+            size_hint,
+            bitpack,
+            type_tagged,
+            name,
+            inline,
+            encode_bound,
+            decode_bound,
+            encode_no_bound,
+            decode_no_bound,
+            version,
+            version_repr,
+            since,
+            until,
+            optional_tail,
+            default_on_eof,
+            default,
+            skip_if_const,
+            replace_with,
+            order,
+            encode_order,
+            decode_order,
+            sequence,
+            allow_order_skew,
+        })
+    }
+}
+
+/// Parses value provided as a string literal to the attribute argument, like
+/// type name in `into = "WireForm"`.
+fn parsed_arg<T: Parse>(
+    attr: &ParametrizedAttr,
+    name: &str,
+) -> Result<Option<T>> {
+    let lit: Option<LitStr> = match attr.args.get(name).cloned() {
+        None => return Ok(None),
+        Some(arg) => arg.try_into().expect(
+            "amplify_syn is broken: requirements for string arg are not satisfied",
+        ),
+    };
+    match lit {
+        Some(lit) => lit.parse().map(Some),
+        None => Err(Error::new(
+            Span::call_site(),
+            format!("`{}` requires value to be a string literal", name),
+        )),
+    }
+}
+
+/// Parses unsigned integer literal value of the attribute argument.
+fn uint_lit_arg(attr: &ParametrizedAttr, name: &str) -> Result<Option<u64>> {
+    attr.args
+        .get(name)
+        .cloned()
+        .map(|arg| -> Result<u64> {
+            let lit: LitInt = arg.try_into().expect(
+                "amplify_syn is broken: requirements for integer arg are not satisfied",
+            );
+            lit.base10_parse()
         })
+        .transpose()
+}
+
+/// Parses argument value which must be one of unsigned integer type names
+/// (`u8`, `u16`, `u32` or `u64`).
+fn uint_arg(arg: ArgValue, name: &str) -> Result<Ident> {
+    let ty: Option<Ident> = arg.try_into().ok();
+    match ty {
+        Some(ty) if ["u8", "u16", "u32", "u64"].contains(&&*ty.to_string()) => {
+            Ok(ty)
+        }
+        _ => Err(Error::new(
+            Span::call_site(),
+            format!(
+                "`{}` requires unsigned integer type identifier (`u8`, `u16`, `u32` or `u64`)",
+                name
+            ),
+        )),
+    }
+}
+
+/// Converts integer magic literal into its strict encoding, i.e. little-endian
+/// bytes of the integer type specified by the literal suffix.
+fn magic_int(int: &LitInt) -> Result<Vec<u8>> {
+    Ok(match int.suffix() {
+        "u8" => int.base10_parse::<u8>()?.to_le_bytes().to_vec(),
+        "u16" => int.base10_parse::<u16>()?.to_le_bytes().to_vec(),
+        "u32" => int.base10_parse::<u32>()?.to_le_bytes().to_vec(),
+        "u64" => int.base10_parse::<u64>()?.to_le_bytes().to_vec(),
+        _ => {
+            return Err(Error::new(
+                int.span(),
+                "`magic` integer literal requires unsigned integer type suffix, like `0x5354_4B31u32`",
+            ))
+        }
+    })
+}
+
+/// Parses `strict_encoding` attribute of a structure or enum variant field.
+/// Fields of `PhantomData` type are treated as having `skip` argument unless
+/// they are marked with `#[strict_encoding(no_skip)]`.
+pub(crate) fn field_param(field: &Field) -> Result<ParametrizedAttr> {
+    let mut param = ParametrizedAttr::with(ATTR_NAME, &field.attrs)?;
+    let opt_out = param.paths.iter().any(|path| path.is_ident("no_skip"))
+        || param.args.contains_key("no_skip");
+    let explicit = param.paths.iter().any(|path| path.is_ident("skip"))
+        || param.args.contains_key("skip");
+    if is_phantom_data(&field.ty) && !opt_out && !explicit {
+        param.paths.push(parse_quote! { skip });
+    }
+    Ok(param)
+}
+
+/// Returns index and field definition of the only non-skipped field of a
+/// structure marked with `#[strict_encoding(transparent)]`.
+pub(crate) fn transparent_field(fields: &Fields) -> Result<(usize, &Field)> {
+    let mut found = None;
+    for (index, field) in fields.iter().enumerate() {
+        let mut local_param = field_param(field)?;
+        let encoding =
+            EncodingDerive::try_from(&mut local_param, false, false)?;
+        if encoding.skip {
+            continue;
+        }
+        if found.is_some() {
+            return Err(Error::new(
+                Span::call_site(),
+                "`transparent` requires structure to have exactly one non-skipped field",
+            ));
+        }
+        found = Some((index, field));
+    }
+    found.ok_or_else(|| {
+        Error::new(
+            Span::call_site(),
+            "`transparent` requires structure to have exactly one non-skipped field",
+        )
+    })
+}
+
+/// Checks that structure marked with `#[strict_encoding(via_deref)]` has
+/// exactly one field, which must be the `Deref::Target` of the structure.
+pub(crate) fn via_deref_check(fields: &Fields) -> Result<()> {
+    if fields.len() != 1 {
+        return Err(Error::new(
+            Span::call_site(),
+            "`via_deref` requires structure to have exactly one field",
+        ));
+    }
+    Ok(())
+}
+
+/// Returns non-skipped fields of a union together with the tag values
+/// assigned to them with `value` attribute argument, which is required for
+/// each of the fields.
+pub(crate) fn union_fields(data: &DataUnion) -> Result<Vec<(&Field, Expr)>> {
+    let mut fields = Vec::with_capacity(data.fields.named.len());
+    for field in &data.fields.named {
+        let mut local_param = field_param(field)?;
+        let encoding = EncodingDerive::try_from(&mut local_param, false, true)?;
+        if encoding.skip {
+            continue;
+        }
+        let value = encoding.value.ok_or_else(|| {
+            Error::new(
+                field.span(),
+                "union fields require tag `value` to be assigned",
+            )
+        })?;
+        fields.push((field, value));
+    }
+    Ok(fields)
+}
+
+/// Returns element types of the tuple field marked with
+/// `#[strict_encoding(tuple_fields)]`, checking the tuple arity against the
+/// field `tuple_arity` argument, if any.
+pub(crate) fn tuple_elems<'a>(
+    field: &'a Field,
+    encoding: &EncodingDerive,
+) -> Result<Vec<&'a Type>> {
+    let max_arity = encoding.tuple_arity.unwrap_or(MAX_TUPLE_ARITY);
+    match field.ty {
+        Type::Tuple(ref tuple) if tuple.elems.len() as u64 <= max_arity => {
+            Ok(tuple.elems.iter().collect())
+        }
+        Type::Tuple(_) => Err(Error::new(
+            field.ty.span(),
+            format!(
+                "`tuple_fields` supports tuples of up to {} elements; use \
+                 `tuple_arity` argument to allow longer tuples",
+                max_arity
+            ),
+        )),
+        _ => Err(Error::new(
+            field.ty.span(),
+            "`tuple_fields` can be used with fields of tuple type only",
+        )),
+    }
+}
+
+/// Returns index of the first field of the structure optional tail, i.e. of
+/// the sequence of trailing fields which may be absent at the end of the
+/// encoded data. Fields are included into the tail if they are marked with
+/// `optional_tail` or `default_on_eof` arguments, or, if `optional_tail` is
+/// given at the structure level, if they are trailing `Option` fields.
+pub(crate) fn optional_tail_start(
+    fields: &Fields,
+    implied: bool,
+) -> Result<Option<usize>> {
+    let mut marked_start = None;
+    let mut start = None;
+    let mut ordered = false;
+    for (index, field) in fields.iter().enumerate() {
+        let mut local_param = field_param(field)?;
+        let encoding =
+            EncodingDerive::try_from(&mut local_param, false, false)?;
+        ordered |= encoding.order.is_some()
+            || encoding.encode_order.is_some()
+            || encoding.decode_order.is_some();
+        if encoding.skip {
+            continue;
+        }
+        let is_option = is_option(&field.ty);
+        if encoding.optional_tail && !is_option {
+            return Err(Error::new(
+                field.span(),
+                "`optional_tail` requires field of `Option` type",
+            ));
+        }
+        let marked = encoding.optional_tail || encoding.default_on_eof;
+        let in_tail = marked || (implied && is_option);
+        if !in_tail {
+            if marked_start.is_some() {
+                return Err(Error::new(
+                    field.span(),
+                    "all fields following `optional_tail` or `default_on_eof` field must be optional",
+                ));
+            }
+            start = None;
+            continue;
+        }
+        if marked && marked_start.is_none() {
+            marked_start = Some(index);
+        }
+        if start.is_none() {
+            start = Some(index);
+        }
+    }
+    if start.is_some() && ordered {
+        return Err(Error::new(
+            Span::call_site(),
+            "optional tail fields can't be used in structures with explicit field `order`",
+        ));
+    }
+    Ok(start)
+}
+
+/// Direction of the data flow, which may have its own field order if the
+/// structure has `allow_order_skew` argument.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum OrderSide {
+    Encode,
+    Decode,
+}
+
+/// Returns fields together with their declaration indexes in the order they
+/// are encoded (or decoded, if `side` is [`OrderSide::Decode`]). The order can
+/// be given with `sequence` argument of the structure, listing all non-skipped
+/// fields. Otherwise, if any of the fields has explicit `order` argument (or
+/// `encode_order`/`decode_order` for the given side), all of the non-skipped
+/// fields must have it, and the fields are sorted by its value. Skipped fields,
+/// not being encoded, are put at the end. Fields of enum variants are always
+/// encoded in the order of their declaration.
+pub(crate) fn wire_order<'a>(
+    fields: impl IntoIterator<Item = &'a Field>,
+    parent_param: &ParametrizedAttr,
+    is_enum: bool,
+    side: OrderSide,
+) -> Result<Vec<(usize, &'a Field)>> {
+    if is_enum {
+        return Ok(fields.into_iter().enumerate().collect());
+    }
+    let parent =
+        EncodingDerive::try_from(&mut parent_param.clone(), true, false)?;
+    let sequence = parent.sequence;
+
+    let mut ordered = Vec::new();
+    let mut unordered = Vec::new();
+    let mut skipped = Vec::new();
+    for (index, field) in fields.into_iter().enumerate() {
+        let mut local_param = field_param(field)?;
+        let encoding =
+            EncodingDerive::try_from(&mut local_param, false, false)?;
+        let skewed =
+            encoding.encode_order.is_some() || encoding.decode_order.is_some();
+        if sequence.is_some()
+            && (encoding.order.is_some()
+                || skewed
+                || encoding.optional_tail
+                || encoding.default_on_eof)
+        {
+            return Err(Error::new(
+                field.span(),
+                "fields of structure with `sequence` can't have `order`, `encode_order`, `decode_order`, `optional_tail` or `default_on_eof` arguments",
+            ));
+        }
+        if skewed && !parent.allow_order_skew {
+            return Err(Error::new(
+                field.span(),
+                "`encode_order` and `decode_order` make encoding incompatible with decoding and require `allow_order_skew` argument of the structure",
+            ));
+        }
+        let order = match side {
+            OrderSide::Encode => encoding.encode_order.or(encoding.order),
+            OrderSide::Decode => encoding.decode_order.or(encoding.order),
+        };
+        match (encoding.skip, order) {
+            (true, _) => skipped.push((index, field)),
+            (false, Some(order)) => ordered.push((order, index, field)),
+            (false, None) => unordered.push((index, field)),
+        }
+    }
+
+    if let Some(sequence) = sequence {
+        return sequenced(&sequence, unordered, skipped);
+    }
+    if ordered.is_empty() {
+        unordered.extend(skipped);
+        return Ok(unordered);
+    }
+    if let Some((_, field)) = unordered.first() {
+        return Err(Error::new(
+            field.span(),
+            "`order` must be specified for all non-skipped fields if it is used for any of them",
+        ));
+    }
+
+    ordered.sort_by_key(|(order, ..)| *order);
+    for pair in ordered.windows(2) {
+        if pair[0].0 == pair[1].0 {
+            return Err(Error::new(
+                pair[1].2.span(),
+                format!("duplicated field `order` value {}", pair[1].0),
+            ));
+        }
+    }
+    Ok(ordered
+        .into_iter()
+        .map(|(_, index, field)| (index, field))
+        .chain(skipped)
+        .collect())
+}
+
+/// Returns statement marking the generated code which encodes fields in the
+/// order different from the one used for decoding, since such code can't
+/// decode data it has encoded.
+pub(crate) fn order_skew_note(
+    parent_param: &ParametrizedAttr,
+    is_enum: bool,
+) -> Result<TokenStream2> {
+    if is_enum
+        || !EncodingDerive::try_from(&mut parent_param.clone(), true, false)?
+            .allow_order_skew
+    {
+        return Ok(TokenStream2::new());
+    }
+    Ok(quote! {
+        let _ = "WARNING: `allow_order_skew` is set, so fields are encoded and \
+            decoded in different order and the encoded data can't be decoded back";
+    })
+}
+
+/// Orders non-skipped fields according to the `sequence` argument, checking
+/// that it lists each of them exactly once.
+fn sequenced<'a>(
+    sequence: &[Member],
+    mut fields: Vec<(usize, &'a Field)>,
+    skipped: Vec<(usize, &'a Field)>,
+) -> Result<Vec<(usize, &'a Field)>> {
+    let is_member = |member: &Member, index: usize, field: &Field| match member
+    {
+        Member::Named(ident) => field.ident.as_ref() == Some(ident),
+        Member::Unnamed(member) => member.index as usize == index,
+    };
+    let mut ordered = Vec::with_capacity(fields.len());
+    for member in sequence {
+        let name = member.to_token_stream();
+        match fields
+            .iter()
+            .position(|(index, field)| is_member(member, *index, field))
+        {
+            Some(pos) => ordered.push(fields.remove(pos)),
+            None if ordered
+                .iter()
+                .any(|(index, field)| is_member(member, *index, field)) =>
+            {
+                return Err(Error::new(
+                    member.span(),
+                    format!("field `{}` is listed in `sequence` twice", name),
+                ))
+            }
+            None if skipped
+                .iter()
+                .any(|(index, field)| is_member(member, *index, field)) =>
+            {
+                return Err(Error::new(
+                    member.span(),
+                    format!(
+                        "skipped field `{}` can't be listed in `sequence`",
+                        name
+                    ),
+                ))
+            }
+            None => {
+                return Err(Error::new(
+                    member.span(),
+                    format!("structure has no field `{}`", name),
+                ))
+            }
+        }
+    }
+    if let Some((_, field)) = fields.first() {
+        return Err(Error::new(
+            field.span(),
+            "non-skipped field is missing from structure `sequence`",
+        ));
+    }
+    ordered.extend(skipped);
+    Ok(ordered)
+}
+
+/// Returns integer type of the field prefixes enabled for the structure with
+/// `field_prefix` attribute. Fields of enum variants never have prefixes.
+pub(crate) fn field_prefix_repr(
+    global_param: &ParametrizedAttr,
+    is_enum: bool,
+) -> Result<Option<Ident>> {
+    if is_enum {
+        return Ok(None);
+    }
+    Ok(
+        EncodingDerive::try_from(&mut global_param.clone(), true, false)?
+            .field_prefix,
+    )
+}
+
+/// Returns maximal number of items of the collection fields given with
+/// `max_len` argument at the type or enum variant level, which applies to the
+/// fields not having their own `max_len`.
+pub(crate) fn container_max_len(
+    parent_param: &ParametrizedAttr,
+) -> Result<Option<u64>> {
+    uint_lit_arg(parent_param, "max_len")
+}
+
+/// Detects `BTreeMap` fields, which are already sorted and for which
+/// `sorted_map` argument has no effect.
+pub(crate) fn is_btree_map(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident == "BTreeMap")
+            .unwrap_or_default(),
+        _ => false,
+    }
+}
+
+/// Detects standard library collection types, to which type-level `max_len`
+/// applies.
+pub(crate) fn is_std_collection(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path
+            .path
+            .segments
+            .last()
+            .map(|segment| {
+                STD_COLLECTIONS.contains(&&*segment.ident.to_string())
+            })
+            .unwrap_or_default(),
+        _ => false,
+    }
+}
+
+/// Returns value of the prefix written before the field with the given index,
+/// failing if the index does not fit into the field prefix type.
+pub(crate) fn field_prefix_lit(
+    repr: &Ident,
+    index: usize,
+    field: &Field,
+) -> Result<Literal> {
+    let max = match repr.to_string().as_str() {
+        "u8" => u8::MAX as u64,
+        "u16" => u16::MAX as u64,
+        "u32" => u32::MAX as u64,
+        _ => u64::MAX,
+    };
+    if index as u64 > max {
+        return Err(Error::new(
+            field.span(),
+            format!(
+                "field index {} exceeds capacity of `{}` field prefix; use larger `field_prefix_type`",
+                index, repr
+            ),
+        ));
+    }
+    Ok(Literal::u64_unsuffixed(index as u64))
+}
+
+/// Detects array fields other than byte arrays, which have no strict encoding
+/// implementation in `strict_encoding` crate and are encoded element by
+/// element instead, without length prefix.
+pub(crate) fn elementwise_array(ty: &Type) -> Option<&TypeArray> {
+    match ty {
+        Type::Array(array) => match *array.elem {
+            Type::Path(ref path)
+                if path.qself.is_none() && path.path.is_ident("u8") =>
+            {
+                None
+            }
+            _ => Some(array),
+        },
+        Type::Group(group) => elementwise_array(&group.elem),
+        Type::Paren(paren) => elementwise_array(&paren.elem),
+        _ => None,
+    }
+}
+
+/// Detects `Cow` fields, returning their borrowed type. Such fields are encoded
+/// from the borrowed data and always decoded into the owned variant, so the
+/// decoded value does not depend on the lifetime of the type. Only `Cow`,
+/// `std::borrow::Cow` and `alloc::borrow::Cow` paths are recognized.
+pub(crate) fn cow_borrowed(ty: &Type) -> Option<&Type> {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => {
+            let segments = path.path.segments.iter().collect::<Vec<_>>();
+            let (segment, prefix) = segments.split_last()?;
+            let is_cow = segment.ident == "Cow"
+                && match prefix {
+                    [] => path.path.leading_colon.is_none(),
+                    [krate, module] => {
+                        (krate.ident == "std" || krate.ident == "alloc")
+                            && module.ident == "borrow"
+                            && krate.arguments.is_empty()
+                            && module.arguments.is_empty()
+                    }
+                    _ => false,
+                };
+            if !is_cow {
+                return None;
+            }
+            match segment.arguments {
+                PathArguments::AngleBracketed(ref args) => {
+                    args.args.iter().find_map(|arg| match arg {
+                        GenericArgument::Type(ty) => Some(ty),
+                        _ => None,
+                    })
+                }
+                _ => None,
+            }
+        }
+        Type::Group(group) => cow_borrowed(&group.elem),
+        Type::Paren(paren) => cow_borrowed(&paren.elem),
+        _ => None,
+    }
+}
+
+/// Detects `Result<T, E>` fields, which have no strict encoding implementation
+/// in `strict_encoding` crate and are encoded as a `u8` tag (0 for `Ok` and 1
+/// for `Err`) followed by the value. Only `Result`, `std::result::Result` and
+/// `core::result::Result` paths are recognized; aliases like `io::Result<T>`
+/// and other types named `Result` are encoded with their own implementation.
+pub(crate) fn is_result(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => {
+            let segments = path.path.segments.iter().collect::<Vec<_>>();
+            let (segment, prefix) = match segments.split_last() {
+                Some(split) => split,
+                None => return false,
+            };
+            let is_result = segment.ident == "Result"
+                && match prefix {
+                    [] => path.path.leading_colon.is_none(),
+                    [krate, module] => {
+                        (krate.ident == "std" || krate.ident == "core")
+                            && module.ident == "result"
+                            && krate.arguments.is_empty()
+                            && module.arguments.is_empty()
+                    }
+                    _ => false,
+                };
+            is_result
+                && match segment.arguments {
+                    PathArguments::AngleBracketed(ref args) => {
+                        args.args
+                            .iter()
+                            .filter(|arg| {
+                                matches!(arg, GenericArgument::Type(_))
+                            })
+                            .count()
+                            == 2
+                    }
+                    _ => false,
+                }
+        }
+        Type::Group(group) => is_result(&group.elem),
+        Type::Paren(paren) => is_result(&paren.elem),
+        _ => false,
+    }
+}
+
+/// Detects fields of `Infallible` type, which can't have any value. Used for
+/// fields and enum variants which are made unreachable by generic protocol
+/// types.
+pub(crate) fn is_infallible(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident == "Infallible")
+            .unwrap_or_default(),
+        _ => false,
+    }
+}
+
+/// Parses string value of the `bound`-like attribute argument into a list of
+/// where predicates.
+fn where_predicates(
+    attr: &ParametrizedAttr,
+    name: &str,
+) -> Result<Option<Vec<WherePredicate>>> {
+    let arg = match attr.args.get(name).cloned() {
+        None => return Ok(None),
+        Some(arg) => arg,
+    };
+    let lit: LitStr = arg.try_into().expect(
+        "amplify_syn is broken: requirements for bound arg are not satisfied",
+    );
+    let predicates = lit.parse_with(
+        Punctuated::<WherePredicate, Token![,]>::parse_terminated,
+    )?;
+    Ok(Some(predicates.into_iter().collect()))
+}
+
+/// Parses string value of the `sequence` attribute argument into a list of
+/// field names or tuple field indexes.
+fn member_list(
+    attr: &ParametrizedAttr,
+    name: &str,
+) -> Result<Option<Vec<Member>>> {
+    let arg = match attr.args.get(name).cloned() {
+        None => return Ok(None),
+        Some(arg) => arg,
+    };
+    let lit: LitStr = arg.try_into().expect(
+        "amplify_syn is broken: requirements for sequence arg are not satisfied",
+    );
+    let members =
+        lit.parse_with(Punctuated::<Member, Token![,]>::parse_terminated)?;
+    Ok(Some(members.into_iter().collect()))
+}
+
+/// Parses string value of the `no_bound`-like attribute argument into a list
+/// of identifiers.
+fn ident_list(attr: &ParametrizedAttr, name: &str) -> Result<Vec<Ident>> {
+    let arg = match attr.args.get(name).cloned() {
+        None => return Ok(vec![]),
+        Some(arg) => arg,
+    };
+    let lit: LitStr = arg.try_into().expect(
+        "amplify_syn is broken: requirements for no_bound arg are not satisfied",
+    );
+    let idents =
+        lit.parse_with(Punctuated::<Ident, Token![,]>::parse_terminated)?;
+    Ok(idents.into_iter().collect())
+}
+
+/// Checks whether the attribute has an argument with the given name, either
+/// already checked or still kept as a bare path.
+fn has_arg(attr: &ParametrizedAttr, name: &str) -> bool {
+    attr.args.contains_key(name)
+        || attr.paths.iter().any(|path| path.is_ident(name))
+}
+
+pub(crate) fn is_phantom_data(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident == "PhantomData")
+            .unwrap_or_default(),
+        _ => false,
+    }
+}
+
+fn is_option(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) => path
+            .path
+            .segments
+            .last()
+            .map(|segment| segment.ident == "Option")
+            .unwrap_or_default(),
+        _ => false,
+    }
+}
+
+/// Standard library collection types which items may be preceded by the count
+/// of `count_type` type.
+const COUNTED_COLLECTIONS: &[&str] =
+    &["Vec", "VecDeque", "LinkedList", "HashSet", "BTreeSet"];
+
+/// Checks that the field with `count_type` argument either has `collection`
+/// argument or is a standard library sequence or set, which items can be
+/// encoded one by one after the count of a custom type.
+pub(crate) fn count_type_check(
+    field: &Field,
+    encoding: &EncodingDerive,
+) -> Result<()> {
+    if encoding.count_type.is_none() || encoding.collection.is_some() {
+        return Ok(());
+    }
+    let counted = match field.ty {
+        Type::Path(ref path) if path.qself.is_none() => path
+            .path
+            .segments
+            .last()
+            .map(|segment| {
+                COUNTED_COLLECTIONS.contains(&&*segment.ident.to_string())
+            })
+            .unwrap_or_default(),
+        _ => false,
+    };
+    if counted {
+        Ok(())
+    } else {
+        Err(Error::new(
+            field.span(),
+            format!(
+                "`count_type` can be used only with fields having `collection` argument or of {} types",
+                COUNTED_COLLECTIONS.join(", ")
+            ),
+        ))
     }
 }
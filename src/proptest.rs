@@ -0,0 +1,79 @@
+// LNP/BP client-side-validation library implementing respective LNPBP
+// specifications & standards (LNPBP-7, 8, 9, 42)
+//
+// Written in 2019-2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the Apache 2.0 License along with this
+// software. If not, see <https://opensource.org/licenses/Apache-2.0>.
+
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::{DeriveInput, Error, Result};
+
+use crate::flavor::Import;
+
+/// Generates test module checking that arbitrary values of the type are
+/// decoded back from their strict encoding unchanged. The module is compiled
+/// only for tests of the user crate with `arbitrary` feature enabled, and
+/// requires the type to implement `proptest::arbitrary::Arbitrary`, `Debug`
+/// and `PartialEq`. If `len_helper` is set, the test also checks that
+/// `strict_serialized_len` matches the length of the encoded data.
+pub(crate) fn roundtrip_test(
+    input: &DeriveInput,
+    import: &Import,
+    len_helper: bool,
+) -> Result<TokenStream2> {
+    let encode_trait = &import.encode_trait;
+    let serialized_len = import.method("serialized_len");
+    let encoding_roundtrip = import.method("encoding_roundtrip");
+    let serialize = import.method("serialize");
+    let deserialize = import.method("deserialize");
+    if !input.generics.params.is_empty() {
+        return Err(Error::new(
+            Span::call_site(),
+            "`proptest` requires type without generic parameters",
+        ));
+    }
+
+    let ident_name = &input.ident;
+    let mod_name = format_ident!(
+        "__{}_{}",
+        import.method("encoding_proptest"),
+        ident_name
+    );
+    let len_check = if len_helper {
+        quote! {
+            ::proptest::prop_assert_eq!(value.#serialized_len(), data.len());
+        }
+    } else {
+        TokenStream2::new()
+    };
+
+    Ok(quote! {
+        #[cfg(all(test, feature = "arbitrary"))]
+        #[allow(non_snake_case, clippy::all)]
+        mod #mod_name {
+            use super::*;
+
+            ::proptest::proptest! {
+                #[test]
+                fn #encoding_roundtrip(
+                    value in ::proptest::prelude::any::<#ident_name>()
+                ) {
+                    let data = #encode_trait::#serialize(&value)
+                        .expect("strict encoding of arbitrary value failed");
+                    let decoded: #ident_name =
+                        #import::#deserialize(&data)
+                            .expect("strict decoding of encoded value failed");
+                    #len_check
+                    ::proptest::prop_assert_eq!(decoded, value);
+                }
+            }
+        }
+    })
+}
@@ -0,0 +1,73 @@
+// LNP/BP client-side-validation library implementing respective LNPBP
+// specifications & standards (LNPBP-7, 8, 9, 42)
+//
+// Written in 2019-2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the Apache 2.0 License along with this
+// software. If not, see <https://opensource.org/licenses/Apache-2.0>.
+
+use proc_macro2::Ident;
+use syn::spanned::Spanned;
+use syn::{Attribute, Data, DeriveInput, Error, Fields, Result};
+
+use crate::ATTR_NAME;
+
+/// Name of the shorthand attribute for `#[strict_encoding(skip)]`.
+pub(crate) const SKIP_ATTR_NAME: &str = "strict_skip";
+
+/// Rewrites `#[strict_skip]` attributes of fields and enum variants into
+/// `#[strict_encoding(skip)]` before the derivation.
+pub(crate) fn expand(mut input: DeriveInput) -> Result<DeriveInput> {
+    if let Some(attr) = input.attrs.iter().find(|attr| is_skip(attr)) {
+        return Err(Error::new(
+            attr.span(),
+            "`strict_skip` can be used with fields and enum variants only",
+        ));
+    }
+    match input.data {
+        Data::Struct(ref mut data) => expand_fields(&mut data.fields)?,
+        Data::Enum(ref mut data) => {
+            for variant in &mut data.variants {
+                expand_attrs(&mut variant.attrs)?;
+                expand_fields(&mut variant.fields)?;
+            }
+        }
+        Data::Union(ref mut data) => {
+            for field in &mut data.fields.named {
+                expand_attrs(&mut field.attrs)?;
+            }
+        }
+    }
+    Ok(input)
+}
+
+fn expand_fields(fields: &mut Fields) -> Result<()> {
+    for field in fields.iter_mut() {
+        expand_attrs(&mut field.attrs)?;
+    }
+    Ok(())
+}
+
+fn expand_attrs(attrs: &mut [Attribute]) -> Result<()> {
+    for attr in attrs.iter_mut().filter(|attr| is_skip(attr)) {
+        if !attr.tokens.is_empty() {
+            return Err(Error::new(
+                attr.tokens.span(),
+                "`strict_skip` attribute does not take arguments",
+            ));
+        }
+        let name = Ident::new(ATTR_NAME, attr.path.span());
+        *attr = parse_quote! { #[#name(skip)] };
+    }
+    Ok(())
+}
+
+fn is_skip(attr: &Attribute) -> bool {
+    attr.path.is_ident(SKIP_ATTR_NAME)
+}
@@ -0,0 +1,350 @@
+// LNP/BP client-side-validation library implementing respective LNPBP
+// specifications & standards (LNPBP-7, 8, 9, 42)
+//
+// Written in 2019-2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the Apache 2.0 License along with this
+// software. If not, see <https://opensource.org/licenses/Apache-2.0>.
+
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use syn::ext::IdentExt;
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Error, Ident, Result, Type};
+
+use amplify::proc_attr::ParametrizedAttr;
+
+use crate::flavor::Import;
+use crate::param::{
+    field_param, optional_tail_start, wire_order, EncodingDerive, OrderSide,
+};
+
+/// Collection and pointer types which never have statically known size of
+/// their strict encoding.
+const DYNAMIC_TYPES: &[&str] = &[
+    "Vec",
+    "VecDeque",
+    "LinkedList",
+    "String",
+    "Box",
+    "Rc",
+    "Arc",
+    "Cow",
+    "Option",
+    "Result",
+    "HashMap",
+    "HashSet",
+    "BTreeMap",
+    "BTreeSet",
+];
+
+/// Kind of the size of the structure encoding computed at compile time.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum SizeMode {
+    /// Exact size, failing if it can't be known at compile time
+    Exact,
+    /// Exact size, if it can be known from the primitive fields
+    Hint,
+    /// Lower bound, counting only the parts of known size
+    LowerBound,
+}
+
+/// Returns size of the primitive type encoding and whether the type is `bool`,
+/// or `None` if the type is not a primitive integer, float or bool type.
+pub(crate) fn primitive_size(ty: &Type) -> Option<(usize, bool)> {
+    let path = match ty {
+        Type::Path(path) if path.qself.is_none() => &path.path,
+        _ => return None,
+    };
+    let segment = path.segments.last()?;
+    if !segment.arguments.is_empty() {
+        return None;
+    }
+    Some(match segment.ident.to_string().as_str() {
+        "bool" => (1, true),
+        "u8" | "i8" => (1, false),
+        "u16" | "i16" => (2, false),
+        "u32" | "i32" | "f32" => (4, false),
+        "u64" | "i64" | "f64" => (8, false),
+        "u128" | "i128" => (16, false),
+        _ => return None,
+    })
+}
+
+/// Generates inherent `ENCODED_SIZE` constant for the structure marked with
+/// `#[strict_encoding(const_size)]`.
+pub(crate) fn encoded_size_impl(
+    input: &DeriveInput,
+    global_param: ParametrizedAttr,
+    vis: &TokenStream2,
+) -> Result<TokenStream2> {
+    let size = static_size(input, global_param, SizeMode::Exact)?
+        .expect("exact static size is always known");
+
+    let ident_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+    Ok(quote! {
+        #[allow(clippy::all, unused_qualifications)]
+        impl #impl_generics #ident_name #ty_generics #where_clause {
+            /// Size of the strict encoding of the type values, in bytes
+            #vis const ENCODED_SIZE: usize = #size;
+        }
+    })
+}
+
+/// Generates inherent `strict_encode_size_hint` method for the structure
+/// marked with `#[strict_encoding(size_hint)]`.
+pub(crate) fn size_hint_impl(
+    input: &DeriveInput,
+    global_param: ParametrizedAttr,
+    import: &Import,
+    vis: &TokenStream2,
+) -> Result<TokenStream2> {
+    let encode_size_hint = import.method("encode_size_hint");
+    let size = match static_size(input, global_param, SizeMode::Hint)? {
+        Some(size) => quote! { Some(#size) },
+        None => quote! { None },
+    };
+
+    let ident_name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) =
+        input.generics.split_for_impl();
+    Ok(quote! {
+        #[allow(clippy::all, unused_qualifications)]
+        impl #impl_generics #ident_name #ty_generics #where_clause {
+            /// Returns size of the strict encoding of the value, in bytes, if
+            /// it is known without performing the encoding.
+            #[inline]
+            #vis fn #encode_size_hint(&self) -> Option<usize> {
+                #size
+            }
+        }
+    })
+}
+
+/// Returns expression for the lower bound of the size of the value encoding,
+/// which is used for pre-allocating buffers. The parts of the encoding which
+/// size is not known at compile time are counted as empty; for enums and
+/// unions just the tag size is taken.
+pub(crate) fn lower_bound_size(
+    input: &DeriveInput,
+    mut global_param: ParametrizedAttr,
+) -> Result<TokenStream2> {
+    if let Data::Struct(_) = input.data {
+        return Ok(static_size(input, global_param, SizeMode::LowerBound)?
+            .unwrap_or_else(|| quote! { 0usize }));
+    }
+    let encoding = EncodingDerive::try_from(&mut global_param, true, true)?;
+    if encoding.no_tag {
+        return Ok(quote! { 0usize });
+    }
+    let repr = &encoding.repr;
+    Ok(match primitive_size(&parse_quote! { #repr }) {
+        Some((size, _)) => quote! { #size },
+        None => quote! { 0usize },
+    })
+}
+
+/// Computes expression for the size of the structure encoding. For size hints
+/// returns `None` instead of failing when the size can't be known at compile
+/// time, which includes fields of non-primitive types. For lower bounds such
+/// parts of the encoding are skipped.
+fn static_size(
+    input: &DeriveInput,
+    mut global_param: ParametrizedAttr,
+    mode: SizeMode,
+) -> Result<Option<TokenStream2>> {
+    let encoding = EncodingDerive::try_from(&mut global_param, true, false)?;
+    let fields = match input.data {
+        Data::Struct(ref data) => &data.fields,
+        _ => unreachable!(
+            "`const_size` and `size_hint` are not allowed for enums and unions"
+        ),
+    };
+    let hint = mode != SizeMode::Exact;
+    let bound = mode == SizeMode::LowerBound;
+    let unknown = |err: Error| if hint { Ok(None) } else { Err(err) };
+
+    if let Some(pad_to) = encoding.pad_to {
+        let pad_to = pad_to as usize;
+        return Ok(Some(quote! { #pad_to }));
+    }
+
+    let tail_start = optional_tail_start(fields, encoding.optional_tail)?;
+    if tail_start.is_some() && !bound {
+        return unknown(Error::new(
+            Span::call_site(),
+            "`const_size` can't be used with optional tail fields",
+        ));
+    }
+
+    let mut sizes = Vec::new();
+    if encoding.type_tagged {
+        // Type tag is encoded as a string with 16-bit length prefix
+        let len = 2 + encoding
+            .name
+            .clone()
+            .unwrap_or_else(|| input.ident.unraw().to_string())
+            .len();
+        sizes.push(quote! { #len });
+    }
+    if let Some(ref magic) = encoding.magic {
+        let len = magic.len();
+        sizes.push(quote! { #len });
+    }
+    if encoding.version.is_some() {
+        match int_size(&encoding.version_repr) {
+            Ok(size) => sizes.push(size),
+            Err(_) if bound => {}
+            Err(err) => return unknown(err),
+        }
+    }
+    if let Some(repr) = encoding
+        .length_prefixed
+        .as_ref()
+        .or(encoding.length_suffix.as_ref())
+    {
+        match int_size(repr) {
+            Ok(size) => sizes.push(size),
+            Err(_) if bound => {}
+            Err(err) => return unknown(err),
+        }
+    }
+    if encoding.checksum.is_some() {
+        // CRC32 is the only supported checksum
+        sizes.push(quote! { 4usize });
+    }
+
+    let mut parent_param = global_param;
+    let fields = wire_order(fields, &parent_param, false, OrderSide::Encode)?;
+    EncodingDerive::strip_global_args(&mut parent_param);
+    // Number of bool fields packed into the currently open byte
+    let mut packed = 0usize;
+    for (index, field) in fields {
+        // Fields of the optional tail may be absent from the encoding
+        if matches!(tail_start, Some(start) if index >= start) {
+            continue;
+        }
+        let mut local_param = field_param(field)?;
+        let mut combined = EncodingDerive::merged(
+            &parent_param,
+            local_param.clone(),
+            field.span(),
+            "field",
+            "type",
+        )?;
+        let _ = EncodingDerive::try_from(&mut local_param, false, false)?;
+        let field_encoding =
+            EncodingDerive::try_from(&mut combined, false, false)?;
+        if field_encoding.skip
+            || !field_encoding.is_present_in(encoding.version)?
+        {
+            continue;
+        }
+        if field_encoding.byte_blob
+            || field_encoding.collection.is_some()
+            || field_encoding.sorted_map
+        {
+            if bound {
+                continue;
+            }
+            return unknown(dynamic_size_error(field.span()));
+        }
+        if encoding.bitpack && primitive_size(&field.ty) == Some((1, true)) {
+            if packed == 0 || packed == 8 {
+                sizes.push(quote! { 1usize });
+                packed = 0;
+            }
+            packed += 1;
+            continue;
+        }
+        packed = 0;
+        let size = match (type_size(&field.ty, hint), &encoding.field_prefix) {
+            (Ok(Some(size)), Some(repr)) => match int_size(repr) {
+                Ok(prefix) => Ok(Some(quote! { (#prefix + #size) })),
+                Err(err) => Err(err),
+            },
+            (size, _) => size,
+        };
+        match size {
+            Ok(Some(size)) => match field_encoding.skip_if_const {
+                Some(condition) => sizes.push(quote! {
+                    (if #condition { 0 } else { #size })
+                }),
+                None => sizes.push(size),
+            },
+            Ok(None) | Err(_) if bound => {}
+            Ok(None) => return Ok(None),
+            Err(err) => return unknown(err),
+        }
+    }
+    Ok(Some(match encoding.align {
+        Some(align) => {
+            let align = align as usize;
+            quote! { (0usize #( + #sizes )* + #align - 1) / #align * #align }
+        }
+        None => quote! { 0usize #( + #sizes )* },
+    }))
+}
+
+fn int_size(repr: &Ident) -> Result<TokenStream2> {
+    let (size, _) =
+        primitive_size(&parse_quote! { #repr }).ok_or_else(|| {
+            Error::new(
+                repr.span(),
+                "`const_size` requires integer type to be given explicitly",
+            )
+        })?;
+    Ok(quote! { #size })
+}
+
+/// Returns expression for the size of the type encoding. Sizes of the types
+/// other than primitives and arrays are taken from their `ENCODED_SIZE`
+/// constant, unless `primitives_only` is set, in which case `None` is returned
+/// for them.
+fn type_size(ty: &Type, primitives_only: bool) -> Result<Option<TokenStream2>> {
+    if let Some((size, _)) = primitive_size(ty) {
+        return Ok(Some(quote! { #size }));
+    }
+    match ty {
+        Type::Array(array) => {
+            let len = &array.len;
+            Ok(type_size(&array.elem, primitives_only)?
+                .map(|elem| quote! { (#elem) * (#len) }))
+        }
+        Type::Paren(paren) => type_size(&paren.elem, primitives_only),
+        Type::Group(group) => type_size(&group.elem, primitives_only),
+        Type::Path(_) if primitives_only => Ok(None),
+        Type::Path(_) if !is_dynamic(ty) => {
+            Ok(Some(quote_spanned! { ty.span() => <#ty>::ENCODED_SIZE }))
+        }
+        _ => Err(dynamic_size_error(ty.span())),
+    }
+}
+
+fn is_dynamic(ty: &Type) -> bool {
+    match ty {
+        Type::Path(path) if path.qself.is_none() => path
+            .path
+            .segments
+            .last()
+            .map(|segment| {
+                DYNAMIC_TYPES.contains(&segment.ident.to_string().as_str())
+            })
+            .unwrap_or_default(),
+        _ => false,
+    }
+}
+
+fn dynamic_size_error(span: Span) -> Error {
+    Error::new(
+        span,
+        "`const_size` requires all encoded fields to have statically known encoded size",
+    )
+}
@@ -0,0 +1,75 @@
+// LNP/BP client-side-validation library implementing respective LNPBP
+// specifications & standards (LNPBP-7, 8, 9, 42)
+//
+// Written in 2019-2021 by
+//     Dr. Maxim Orlovsky <orlovsky@pandoracore.com>
+//
+// To the extent possible under law, the author(s) have dedicated all
+// copyright and related and neighboring rights to this software to
+// the public domain worldwide. This software is distributed without
+// any warranty.
+//
+// You should have received a copy of the Apache 2.0 License along with this
+// software. If not, see <https://opensource.org/licenses/Apache-2.0>.
+
+use proc_macro2::TokenStream as TokenStream2;
+use syn::ext::IdentExt;
+use syn::DeriveInput;
+
+use crate::flavor::Import;
+use crate::param::EncodingDerive;
+
+/// Returns expression evaluating to the type tag of the type marked with
+/// `#[strict_encoding(type_tagged)]`, or `None` if the type is not tagged.
+/// The tag is a type name, stringified by the macro, so it doesn't depend on
+/// the compiler version.
+pub(crate) fn type_tag(
+    input: &DeriveInput,
+    encoding: &EncodingDerive,
+) -> Option<TokenStream2> {
+    if !encoding.type_tagged {
+        return None;
+    }
+    let name = encoding
+        .name
+        .clone()
+        .unwrap_or_else(|| input.ident.unraw().to_string());
+    Some(quote! { #name })
+}
+
+/// Writes the type tag as a length-prefixed string.
+pub(crate) fn encode_type_tag(
+    tag: Option<TokenStream2>,
+    import: &Import,
+) -> TokenStream2 {
+    let encode_trait = &import.encode_trait;
+    let encode = import.method("encode");
+    match tag {
+        Some(tag) => quote! {
+            __strict_len += #encode_trait::#encode(&#tag, &mut __strict_e)?;
+        },
+        None => TokenStream2::new(),
+    }
+}
+
+/// Reads the type tag and checks that it matches the tag of the type.
+pub(crate) fn decode_type_tag(
+    tag: Option<TokenStream2>,
+    import: &Import,
+) -> TokenStream2 {
+    let decode_trait = &import.decode_trait;
+    let decode = import.method("decode");
+    match tag {
+        Some(tag) => quote! {
+            let __strict_actual: String = #decode_trait::#decode(&mut __strict_d)?;
+            let __strict_expected = #tag;
+            if __strict_actual != __strict_expected {
+                return Err(#import::Error::DataIntegrityError(format!(
+                    "type tag `{}` does not match expected `{}`",
+                    __strict_actual, __strict_expected,
+                )));
+            }
+        },
+        None => TokenStream2::new(),
+    }
+}